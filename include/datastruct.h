@@ -0,0 +1,68 @@
+#ifndef DATASTRUCT_H
+#define DATASTRUCT_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct DValue DValue;
+
+// Parses `input` in the text format and returns a new handle, or null if
+// `input` is null, not valid UTF-8 or not valid text.
+//
+// # Safety
+//
+// `input` must be null or point to a NUL-terminated string.
+DValue *datastruct_parse(const char *input);
+
+// Looks up `path` (e.g. `a.b[0]`) and returns a new handle holding a copy
+// of the value found, or null if it doesn't exist.
+//
+// # Safety
+//
+// `value` must be null or a live handle, and `path` must be null or point
+// to a NUL-terminated string.
+DValue *datastruct_get_path(const DValue *value, const char *path);
+
+// Returns the type name of the value, such as `"Dict"`, as a static
+// string that must not be freed. A null handle reports `"None"`.
+//
+// # Safety
+//
+// `value` must be null or a live handle.
+const char *datastruct_type(const DValue *value);
+
+// Serializes the value as JSON. The result must be released with
+// `datastruct_string_free`; null is returned for a null handle.
+//
+// # Safety
+//
+// `value` must be null or a live handle.
+char *datastruct_to_json(const DValue *value);
+
+// Releases a handle. Passing null is a no-op.
+//
+// # Safety
+//
+// `value` must be null or a live handle, and must not be used afterwards.
+void datastruct_free(DValue *value);
+
+// Releases a string returned by `datastruct_to_json`. Passing null is a
+// no-op.
+//
+// # Safety
+//
+// `s` must be null or a string from `datastruct_to_json` that hasn't been
+// freed yet.
+void datastruct_string_free(char *s);
+
+// Describes why the last call on this thread that can fail returned null,
+// or returns null if it succeeded. The string belongs to the library and
+// stays valid until the next such call on the same thread; it must not be
+// freed.
+const char *datastruct_last_error(void);
+
+#endif  /* DATASTRUCT_H */
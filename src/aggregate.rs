@@ -0,0 +1,155 @@
+use crate::DValue;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How list aggregations treat members that are not numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonNumeric {
+    /// Leave them out of the result.
+    Skip,
+
+    /// Fail with `AggregateError::NonNumeric`.
+    Error,
+}
+
+/// Summary of the numbers in a list, returned by `DValue::aggregate`.
+///
+/// `min` and `max` live here rather than on `DValue`, where they would be
+/// shadowed by `Ord::min` and `Ord::max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aggregate {
+    pub count: usize,
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateError {
+    /// The value being aggregated is not a list; holds its datatype.
+    NotAList(String),
+
+    /// A member is not a number and `NonNumeric::Error` was requested.
+    NonNumeric { index: usize, found: String },
+}
+
+impl Aggregate {
+    /// The arithmetic mean, or `None` for an empty list.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as f64)
+        }
+    }
+}
+
+impl fmt::Display for AggregateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateError::NotAList(found) => write!(f, "expected List, found {}", found),
+            AggregateError::NonNumeric { index, found } => {
+                write!(f, "expected Number at index {}, found {}", index, found)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AggregateError {}
+
+impl DValue {
+    /// Computes the count, sum, min and max of the numbers in a list.
+    ///
+    /// ```
+    /// use datastruct::aggregate::NonNumeric;
+    /// use datastruct::DValue;
+    ///
+    /// let list = "[4, \"x\", 1, 7]".parse::<DValue>().unwrap();
+    /// let stats = list.aggregate(NonNumeric::Skip).unwrap();
+    ///
+    /// assert_eq!((stats.count, stats.sum, stats.min, stats.max), (3, 12.0, Some(1.0), Some(7.0)));
+    /// assert!(list.aggregate(NonNumeric::Error).is_err());
+    /// ```
+    pub fn aggregate(&self, policy: NonNumeric) -> Result<Aggregate, AggregateError> {
+        let list = match self {
+            DValue::List(list) => list,
+            other => return Err(AggregateError::NotAList(other.datatype())),
+        };
+
+        let mut result = Aggregate {
+            count: 0,
+            sum: 0.0,
+            min: None,
+            max: None,
+        };
+
+        for (index, item) in list.iter().enumerate() {
+            let n = match (item, policy) {
+                (DValue::Number(n), _) => *n,
+                (_, NonNumeric::Skip) => continue,
+                (other, NonNumeric::Error) => {
+                    return Err(AggregateError::NonNumeric {
+                        index,
+                        found: other.datatype(),
+                    })
+                }
+            };
+
+            result.count += 1;
+            result.sum += n;
+            result.min = Some(result.min.map_or(n, |m| m.min(n)));
+            result.max = Some(result.max.map_or(n, |m| m.max(n)));
+        }
+
+        Ok(result)
+    }
+
+    pub fn sum(&self, policy: NonNumeric) -> Result<f64, AggregateError> {
+        Ok(self.aggregate(policy)?.sum)
+    }
+
+    pub fn mean(&self, policy: NonNumeric) -> Result<Option<f64>, AggregateError> {
+        Ok(self.aggregate(policy)?.mean())
+    }
+
+    /// Counts the members of a list by their `datatype()`. Other variants
+    /// return an empty map.
+    pub fn count_by_type(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+
+        for item in self.iter() {
+            *counts.entry(item.datatype()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::aggregate::{AggregateError, NonNumeric};
+    use crate::DValue;
+
+    #[test]
+    fn aggregate_list() {
+        let list = "[2, 4, true, \"x\", 6]".parse::<DValue>().unwrap();
+
+        assert_eq!(list.sum(NonNumeric::Skip), Ok(12.0));
+        assert_eq!(list.mean(NonNumeric::Skip), Ok(Some(4.0)));
+        assert_eq!(
+            list.sum(NonNumeric::Error),
+            Err(AggregateError::NonNumeric {
+                index: 2,
+                found: "Boolean".to_string()
+            })
+        );
+        assert_eq!(
+            "[]".parse::<DValue>().unwrap().mean(NonNumeric::Error),
+            Ok(None)
+        );
+        assert!(DValue::Number(1.0).sum(NonNumeric::Skip).is_err());
+
+        let counts = list.count_by_type();
+        assert_eq!(counts["Number"], 3);
+        assert_eq!(counts["String"], 1);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::binary_util::Binary;
+use crate::{DValue, TextError, ValueParser};
+use bumpalo::collections::Vec as BumpVec;
+use nom::{
+    bytes::complete::tag,
+    character::complete::multispace0,
+    error::{ErrorKind, ParseError},
+    sequence::{delimited, separated_pair},
+    IResult,
+};
+
+pub use bumpalo::Bump;
+
+/// A value whose nodes all live in a `Bump` arena.
+///
+/// Produced by `parse_in`. Dropping the arena frees the whole document at
+/// once, which makes parsing and discarding many small documents much
+/// cheaper than building and dropping `DValue` trees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DValueIn<'a> {
+    None,
+    String(&'a str),
+    Number(f64),
+    Boolean(bool),
+    List(&'a [DValueIn<'a>]),
+    Dict(&'a [(&'a str, DValueIn<'a>)]),
+    Tuple(&'a (DValueIn<'a>, DValueIn<'a>)),
+    Binary(&'a [u8]),
+}
+
+impl<'a> DValueIn<'a> {
+    /// Returns the value stored under `key` if this is a dict. A key that
+    /// appears more than once gives its last value, as in a parsed `DValue`.
+    pub fn get(&self, key: &str) -> Option<&DValueIn<'a>> {
+        match self {
+            DValueIn::Dict(entries) => entries
+                .iter()
+                .rev()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn datatype(&self) -> String {
+        match self {
+            DValueIn::None => "None",
+            DValueIn::String(_) => "String",
+            DValueIn::Number(_) => "Number",
+            DValueIn::Boolean(_) => "Boolean",
+            DValueIn::List(_) => "List",
+            DValueIn::Dict(_) => "Dict",
+            DValueIn::Tuple(_) => "Tuple",
+            DValueIn::Binary(_) => "Binary",
+        }
+        .to_string()
+    }
+
+    /// Copies the value out of the arena into an owned `DValue`.
+    pub fn to_dvalue(&self) -> DValue {
+        match self {
+            DValueIn::None => DValue::None,
+            DValueIn::String(s) => DValue::String(s.to_string()),
+            DValueIn::Number(n) => DValue::Number(*n),
+            DValueIn::Boolean(b) => DValue::Boolean(*b),
+            DValueIn::List(items) => DValue::List(items.iter().map(|v| v.to_dvalue()).collect()),
+            DValueIn::Dict(entries) => DValue::Dict(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_dvalue()))
+                    .collect(),
+            ),
+            DValueIn::Tuple(pair) => {
+                DValue::Tuple((Box::new(pair.0.to_dvalue()), Box::new(pair.1.to_dvalue())))
+            }
+            DValueIn::Binary(bytes) => DValue::BinaryUtil(Binary::new(bytes.to_vec())),
+        }
+    }
+}
+
+/// Parses `data` with every node allocated from `bump`.
+///
+/// Accepts the same text format as `DValue`'s `FromStr`, but returns
+/// `DValueIn::None` when the input can't be parsed.
+///
+/// ```
+/// use datastruct::arena::{parse_in, Bump, DValueIn};
+///
+/// let bump = Bump::new();
+/// let value = parse_in(&bump, "{\"id\": 7, \"tags\": [\"a\", \"b\"]}");
+///
+/// assert_eq!(value.get("id"), Some(&DValueIn::Number(7.0)));
+/// assert_eq!(value.get("tags").unwrap().datatype(), "List");
+/// ```
+pub fn parse_in<'a>(bump: &'a Bump, data: &str) -> DValueIn<'a> {
+    let Ok(data) = DValue::unwrap_b64(data) else {
+        return DValueIn::None;
+    };
+
+    match value(bump, &data) {
+        Ok(("", v)) => v,
+        _ => DValueIn::None,
+    }
+}
+
+fn value<'a, 'i>(bump: &'a Bump, msg: &'i str) -> IResult<&'i str, DValueIn<'a>, TextError<'i>> {
+    let (msg, _) = multispace0(msg)?;
+
+    let (rest, value) = if let Ok((rest, _)) = tag::<_, _, TextError>("none")(msg) {
+        (rest, DValueIn::None)
+    } else if let Ok((rest, n)) = ValueParser::parse_num(msg) {
+        (rest, DValueIn::Number(n))
+    } else if let Ok((rest, b)) = ValueParser::parse_bool(msg) {
+        (rest, DValueIn::Boolean(b))
+    } else if let Ok((rest, s)) = ValueParser::parse_string(msg) {
+        (rest, DValueIn::String(bump.alloc_str(&s)))
+    } else if msg.starts_with('[') {
+        let (rest, items) = sequence(bump, msg, ("[", "]"), true, |m| value(bump, m))?;
+        (rest, DValueIn::List(items.into_bump_slice()))
+    } else if msg.starts_with('{') {
+        let (rest, entries) = sequence(bump, msg, ("{", "}"), true, |m| entry(bump, m))?;
+        (rest, DValueIn::Dict(entries.into_bump_slice()))
+    } else if msg.starts_with('(') {
+        let (rest, items) = sequence(bump, msg, ("(", ")"), false, |m| value(bump, m))?;
+        let [first, second] = items[..] else {
+            return Err(nom::Err::Error(TextError::from_error_kind(
+                msg,
+                ErrorKind::Verify,
+            )));
+        };
+        (rest, DValueIn::Tuple(bump.alloc((first, second))))
+    } else {
+        let (rest, bin) = ValueParser::parse_bin(msg)?;
+        (
+            rest,
+            DValueIn::Binary(bump.alloc_slice_copy(bin.as_bytes())),
+        )
+    };
+
+    let (rest, _) = multispace0(rest)?;
+    Ok((rest, value))
+}
+
+fn entry<'a, 'i>(
+    bump: &'a Bump,
+    msg: &'i str,
+) -> IResult<&'i str, (&'a str, DValueIn<'a>), TextError<'i>> {
+    let (rest, (key, value)) = separated_pair(
+        ValueParser::parse_string,
+        delimited(multispace0, tag(":"), multispace0),
+        |m| value(bump, m),
+    )(msg)?;
+
+    Ok((rest, (&*bump.alloc_str(&key), value)))
+}
+
+/// Parses `open element (, element)* close` into an arena vector, with a
+/// trailing comma before `close` if `trailing` is set, as lists and dicts
+/// allow.
+fn sequence<'a, 'i, T>(
+    bump: &'a Bump,
+    msg: &'i str,
+    (open, close): (&'static str, &'static str),
+    trailing: bool,
+    mut element: impl FnMut(&'i str) -> IResult<&'i str, T, TextError<'i>>,
+) -> IResult<&'i str, BumpVec<'a, T>, TextError<'i>> {
+    let (rest, _) = tag(open)(msg)?;
+    let (mut rest, _) = multispace0(rest)?;
+    let mut items = BumpVec::new_in(bump);
+
+    if let Ok((after, _)) = tag::<_, _, TextError>(close)(rest) {
+        return Ok((after, items));
+    }
+
+    loop {
+        let (after, item) = element(rest)?;
+        items.push(item);
+
+        let (after, _) = multispace0(after)?;
+        let (after, comma) = ValueParser::trailing_comma(after)?;
+        let closed = tag::<_, _, TextError>(close)(after);
+
+        match (comma, closed) {
+            (None, closed) => return closed.map(|(after, _)| (after, items)),
+            (Some(_), Ok((after, _))) if trailing => return Ok((after, items)),
+            (Some(_), Ok(_)) => {
+                return Err(nom::Err::Error(TextError::from_error_kind(
+                    after,
+                    ErrorKind::Verify,
+                )))
+            }
+            (Some(_), Err(_)) => rest = after,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::arena::{parse_in, Bump, DValueIn};
+    use crate::DValue;
+
+    #[test]
+    fn matches_owned_parser() {
+        let input = "{\"a\": [1, true, \"x\"], \"t\": (binary!(aGk=), -2.5), \"e\": {}}";
+        let bump = Bump::new();
+        let value = parse_in(&bump, input);
+
+        let owned = input.parse::<DValue>().unwrap();
+        for key in ["a", "t", "e"] {
+            assert_eq!(
+                value.get(key).map(|v| v.to_dvalue()).as_ref(),
+                owned.get_path(key)
+            );
+        }
+        assert_eq!(
+            value.get("t"),
+            Some(&DValueIn::Tuple(&(
+                DValueIn::Binary(b"hi"),
+                DValueIn::Number(-2.5)
+            )))
+        );
+        assert_eq!(parse_in(&bump, "(1, 2, 3)"), DValueIn::None);
+        assert_eq!(parse_in(&bump, "[1,"), DValueIn::None);
+    }
+
+    #[test]
+    fn accepts_the_same_text_as_str_parse() {
+        let inputs = [
+            "1 garbage",
+            "[1, 2,]",
+            "[1, 2 , ]",
+            "{\"a\": 1,}",
+            "{\"a\": 1, \"a\": 2}",
+            "[,]",
+            "[1,,]",
+            "[ ]",
+            "{ }",
+            "(1, 2)",
+            "(1, 2,)",
+            "( )",
+            "  [true]  ",
+            "[1] [2]",
+            "b:WzEsIDJd:",
+            "b:!!:",
+        ];
+        let bump = Bump::new();
+
+        for input in inputs {
+            let expected = input.parse::<DValue>().unwrap_or(DValue::None);
+            assert_eq!(parse_in(&bump, input).to_dvalue(), expected, "{}", input);
+        }
+        assert_eq!(
+            parse_in(&bump, "{\"a\": 1, \"a\": 2}").get("a"),
+            Some(&DValueIn::Number(2.0))
+        );
+    }
+}
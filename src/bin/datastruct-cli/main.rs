@@ -0,0 +1,200 @@
+use datastruct::expr::Expr;
+use datastruct::DValue;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+#[cfg(feature = "repl")]
+mod repl;
+
+const USAGE: &str = "\
+Usage: datastruct-cli <command> [options] [file]
+
+Reads a document from `file`, or from stdin if it is missing or `-`.
+
+Commands:
+  convert        Print the document in the output format
+  get <path>     Print the value at `path`, e.g. `users[0].name`
+  eval <expr>    Evaluate an expression against the document
+  type           Print the datatype of the document
+  repl           Explore and edit the document interactively (needs a file
+                 and the `repl` feature)
+
+Options:
+  -f, --from <format>   Input format: text (default) or json
+  -t, --to <format>     Output format: text (default), json or pretty
+  -h, --help            Print this help
+";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+    Pretty,
+}
+
+impl Format {
+    fn parse(name: &str) -> Result<Format, String> {
+        match name {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "pretty" => Ok(Format::Pretty),
+            _ => Err(format!("unknown format {:?}", name)),
+        }
+    }
+}
+
+struct Args {
+    command: String,
+    operand: Option<String>,
+    file: Option<String>,
+    from: Format,
+    to: Format,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut positional = Vec::new();
+    let mut from = Format::Text;
+    let mut to = Format::Text;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-f" | "--from" | "-t" | "--to" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a format", arg))?;
+                match arg.as_str() {
+                    "-f" | "--from" if value == "pretty" => {
+                        return Err("pretty is an output format".to_string())
+                    }
+                    "-f" | "--from" => from = Format::parse(&value)?,
+                    _ => to = Format::parse(&value)?,
+                }
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option {}", arg))
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = positional.next().ok_or("missing command")?;
+    let operand = match command.as_str() {
+        "get" | "eval" => Some(
+            positional
+                .next()
+                .ok_or_else(|| format!("{} needs an argument", command))?,
+        ),
+        "convert" | "type" | "repl" => None,
+        _ => return Err(format!("unknown command {:?}", command)),
+    };
+    let file = positional.next();
+
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument {:?}", extra));
+    }
+    Ok(Some(Args {
+        command,
+        operand,
+        file,
+        from,
+        to,
+    }))
+}
+
+fn read_input(file: Option<&str>) -> Result<String, String> {
+    match file {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|e| format!("failed to read stdin: {}", e))?;
+            Ok(input)
+        }
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))
+        }
+    }
+}
+
+fn parse_document(input: &str, format: Format) -> Result<DValue, String> {
+    match format {
+        Format::Json => DValue::try_from_json(input).map_err(|e| format!("{}", e)),
+        _ if input.trim().is_empty() => Ok(DValue::None),
+        _ => input.parse().map_err(|e| format!("{}", e)),
+    }
+}
+
+fn render(value: &DValue, format: Format) -> Result<String, String> {
+    match format {
+        Format::Text => Ok(value.to_string()),
+        Format::Json => Ok(value.to_json()),
+        // Reindented from `to_json` rather than serialized directly, so
+        // whole numbers are written as integers here too.
+        Format::Pretty => serde_json::from_str::<serde_json::Value>(&value.to_json())
+            .and_then(|json| serde_json::to_string_pretty(&json))
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Like `render`, but highlights the text format when stdout is a terminal.
+fn display(value: &DValue, format: Format) -> Result<String, String> {
+    match format {
+        Format::Text => Ok(value.to_string_colored()),
+        _ => render(value, format),
+    }
+}
+
+fn run(args: Args) -> Result<String, String> {
+    if args.command == "repl" && matches!(args.file.as_deref(), None | Some("-")) {
+        return Err("repl reads commands from stdin, so it needs a file".to_string());
+    }
+
+    let document = parse_document(&read_input(args.file.as_deref())?, args.from)?;
+    let operand = args.operand.as_deref().unwrap_or_default();
+
+    let value = match args.command.as_str() {
+        "get" => document
+            .get_path(operand)
+            .cloned()
+            .ok_or_else(|| format!("no value at {}", operand))?,
+        "eval" => {
+            let expr: Expr = operand.parse().map_err(|e| format!("{}", e))?;
+            expr.eval(&document).map_err(|e| format!("{}", e))?
+        }
+        "type" => return Ok(document.datatype()),
+        #[cfg(feature = "repl")]
+        "repl" => return repl::run(document, args.to).map(|()| String::new()),
+        #[cfg(not(feature = "repl"))]
+        "repl" => return Err("repl needs the repl feature".to_string()),
+        _ => document,
+    };
+    display(&value, args.to)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprint!("datastruct-cli: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(output) if output.is_empty() => ExitCode::SUCCESS,
+        Ok(output) => {
+            let _ = writeln!(io::stdout(), "{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("datastruct-cli: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
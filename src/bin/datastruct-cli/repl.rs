@@ -0,0 +1,330 @@
+use crate::{display, parse_document, render, Format};
+use datastruct::expr::Expr;
+use datastruct::path::{Path, Segment};
+use datastruct::wal::Op;
+use datastruct::DValue;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::history::DefaultHistory;
+use rustyline::{Context, Editor, Helper, Highlighter, Hinter, Validator};
+
+const HELP: &str = "\
+Paths are relative to the current location, e.g. `users[0].name`.
+
+  ls [path]              List the keys or items at `path`
+  cd [path|..]           Move to `path`, up one level, or back to the root
+  pwd                    Print the current location
+  get [path]             Print the value at `path`
+  type [path]            Print the datatype of the value at `path`
+  eval <expr>            Evaluate an expression against the current value
+  set <path> <value>     Replace the value at `path` with a text-format value
+  merge <path> <value>   Merge a text-format value into the one at `path`
+  rm <path>              Remove the entry or item at `path`
+  format <format>        Print values as text, json or pretty
+  save <file>            Write the whole document to `file` in the output format
+  help                   Print this help
+  quit                   Leave the REPL";
+
+/// The document being explored, and where in it the user currently is.
+pub(crate) struct Session {
+    document: DValue,
+    cwd: Path,
+    format: Format,
+}
+
+impl Session {
+    pub(crate) fn new(document: DValue, format: Format) -> Self {
+        Self {
+            document,
+            cwd: Path::root(),
+            format,
+        }
+    }
+
+    /// Runs one command line. `Ok(None)` means the user asked to quit.
+    pub(crate) fn execute(&mut self, line: &str) -> Result<Option<String>, String> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        let output = match command {
+            "" => String::new(),
+            "quit" | "exit" => return Ok(None),
+            "help" => HELP.to_string(),
+            "pwd" => format!("/{}", self.cwd),
+            "ls" => self.list(rest)?,
+            "cd" => {
+                self.cwd = match rest {
+                    "" | "/" => Path::root(),
+                    ".." => self.cwd.parent().unwrap_or_default(),
+                    _ => {
+                        let path = self.resolve(rest)?;
+                        self.lookup(&path)?;
+                        path
+                    }
+                };
+                String::new()
+            }
+            "get" => display(self.lookup(&self.resolve(rest)?)?, self.format)?,
+            "type" => self.lookup(&self.resolve(rest)?)?.datatype(),
+            "eval" => {
+                let expr: Expr = rest.parse().map_err(|e| format!("{}", e))?;
+                let value = expr
+                    .eval(self.lookup(&self.cwd)?)
+                    .map_err(|e| format!("{}", e))?;
+                display(&value, self.format)?
+            }
+            "set" | "merge" => {
+                let (path, value) = rest
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| format!("{} needs a path and a value", command))?;
+                let path = self.resolve(path)?;
+                let value = parse_document(value.trim(), Format::Text)?;
+                let op = match command {
+                    "set" => Op::Set(path, value),
+                    _ => Op::Merge(path, value),
+                };
+                self.apply(op)?
+            }
+            "rm" => {
+                let path = self.resolve(rest)?;
+                if path.is_root() {
+                    return Err("cannot remove the root".to_string());
+                }
+                self.apply(Op::Remove(path))?
+            }
+            "format" => {
+                self.format = Format::parse(rest)?;
+                String::new()
+            }
+            "save" if rest.is_empty() => return Err("save needs a file".to_string()),
+            "save" => {
+                let output = render(&self.document, self.format)?;
+                std::fs::write(rest, output + "\n")
+                    .map_err(|e| format!("failed to write {}: {}", rest, e))?;
+                format!("saved to {}", rest)
+            }
+            _ => return Err(format!("unknown command {:?}, try help", command)),
+        };
+        Ok(Some(output))
+    }
+
+    /// Lists the child keys or indices of the value at `path` with their types.
+    fn list(&self, path: &str) -> Result<String, String> {
+        let value = self.lookup(&self.resolve(path)?)?;
+        let lines: Vec<String> = match value {
+            DValue::Dict(dict) => {
+                let mut keys: Vec<&String> = dict.keys().collect();
+                keys.sort();
+                keys.into_iter()
+                    .map(|key| format!("{}  {}", key, dict[key].datatype()))
+                    .collect()
+            }
+            DValue::List(list) => list
+                .iter()
+                .enumerate()
+                .map(|(i, item)| format!("[{}]  {}", i, item.datatype()))
+                .collect(),
+            DValue::Tuple(tuple) => vec![
+                format!("[0]  {}", tuple.0.datatype()),
+                format!("[1]  {}", tuple.1.datatype()),
+            ],
+            other => vec![display(other, self.format)?],
+        };
+        Ok(lines.join("\n"))
+    }
+
+    fn apply(&mut self, op: Op) -> Result<String, String> {
+        op.apply(&mut self.document).map_err(|e| format!("{}", e))?;
+
+        // The current location may have been removed or replaced by a scalar.
+        while self.document.get_path(&self.cwd).is_none() {
+            self.cwd = self.cwd.parent().unwrap_or_default();
+        }
+        Ok(String::new())
+    }
+
+    /// Joins `path` onto the current location.
+    fn resolve(&self, path: &str) -> Result<Path, String> {
+        let mut resolved = self.cwd.clone();
+        for segment in Path::parse(path)
+            .map_err(|e| format!("invalid path: {}", e))?
+            .segments()
+        {
+            resolved.push(segment.clone());
+        }
+        Ok(resolved)
+    }
+
+    fn lookup(&self, path: &Path) -> Result<&DValue, String> {
+        self.document
+            .get_path(path)
+            .ok_or_else(|| format!("no value at {}", path))
+    }
+
+    /// Completes the path being typed in `word` against the keys and indices
+    /// that exist in the document, returning whole replacement words.
+    pub(crate) fn complete(&self, word: &str) -> Vec<String> {
+        let split = word.rfind(['.', '[']).map_or(0, |i| i + 1);
+        let (parent, partial) = word.split_at(split);
+        let parent_path = match parent.strip_suffix(['.', '[']) {
+            Some(parent) => self.resolve(parent).ok(),
+            None => Some(self.cwd.clone()),
+        };
+
+        let Some(value) = parent_path.and_then(|path| self.document.get_path(&path)) else {
+            return Vec::new();
+        };
+        let children: Vec<Segment> = match value {
+            DValue::Dict(dict) => dict.keys().map(|k| Segment::Key(k.clone())).collect(),
+            DValue::List(list) => (0..list.len()).map(Segment::Index).collect(),
+            DValue::Tuple(_) => vec![Segment::Index(0), Segment::Index(1)],
+            _ => Vec::new(),
+        };
+
+        let mut candidates: Vec<String> = children
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Key(key) if !parent.ends_with('[') && key.starts_with(partial) => {
+                    Some(format!("{}{}", parent, key))
+                }
+                Segment::Index(index) if parent.ends_with('[') || partial.is_empty() => {
+                    let index = index.to_string();
+                    let open = if parent.ends_with('[') { "" } else { "[" };
+                    index
+                        .starts_with(partial)
+                        .then(|| format!("{}{}{}]", parent, open, index))
+                }
+                _ => None,
+            })
+            .collect();
+        candidates.sort();
+        candidates
+    }
+}
+
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ReplHelper {
+    session: Session,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        Ok((start, self.session.complete(&line[start..pos])))
+    }
+}
+
+/// Runs the interactive loop until the user quits or closes stdin.
+pub(crate) fn run(document: DValue, format: Format) -> Result<(), String> {
+    let mut editor: Editor<ReplHelper, DefaultHistory> =
+        Editor::new().map_err(|e| format!("failed to start the REPL: {}", e))?;
+    editor.set_helper(Some(ReplHelper {
+        session: Session::new(document, format),
+    }));
+
+    loop {
+        let helper = editor.helper().expect("helper is set above");
+        let prompt = format!("/{}> ", helper.session.cwd);
+
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(format!("failed to read input: {}", e)),
+        };
+        let _ = editor.add_history_entry(line.as_str());
+
+        let session = &mut editor.helper_mut().expect("helper is set above").session;
+        match session.execute(&line) {
+            Ok(None) => return Ok(()),
+            Ok(Some(output)) if output.is_empty() => {}
+            Ok(Some(output)) => println!("{}", output),
+            Err(message) => eprintln!("error: {}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Session;
+    use crate::Format;
+    use datastruct::DValue;
+
+    fn session() -> Session {
+        let document = r#"{"users": [{"name": "ada", "admin": true}], "count": 1}"#
+            .parse::<DValue>()
+            .unwrap();
+        Session::new(document, Format::Text)
+    }
+
+    #[test]
+    fn navigate_and_query() {
+        let mut session = session();
+
+        assert_eq!(
+            session.execute("ls").unwrap().unwrap(),
+            "count  Number\nusers  List"
+        );
+        session.execute("cd users[0]").unwrap();
+        assert_eq!(session.execute("pwd").unwrap().unwrap(), "/users[0]");
+        assert_eq!(session.execute("get name").unwrap().unwrap(), "\"ada\"");
+        assert_eq!(
+            session.execute("eval admin && true").unwrap().unwrap(),
+            "true"
+        );
+
+        session.execute("cd ..").unwrap();
+        assert_eq!(session.execute("type").unwrap().unwrap(), "List");
+        assert!(session.execute("cd missing").is_err());
+        assert_eq!(session.execute("quit").unwrap(), None);
+    }
+
+    #[test]
+    fn mutate() {
+        let mut session = session();
+
+        session.execute("set users[0].name \"grace\"").unwrap();
+        session
+            .execute("merge users[0] {\"admin\": false}")
+            .unwrap();
+        assert_eq!(
+            session.execute("get users[0].name").unwrap().unwrap(),
+            "\"grace\""
+        );
+        assert_eq!(
+            session.execute("get users[0].admin").unwrap().unwrap(),
+            "false"
+        );
+
+        session.execute("cd users[0]").unwrap();
+        session.execute("set name [").unwrap_err();
+        session.execute("rm name").unwrap();
+        assert_eq!(session.execute("ls").unwrap().unwrap(), "admin  Boolean");
+
+        // Removing the current location moves back up to what is left.
+        session.execute("cd ..").unwrap();
+        session.execute("rm [0]").unwrap();
+        assert_eq!(session.execute("get").unwrap().unwrap(), "[]");
+    }
+
+    #[test]
+    fn complete_paths() {
+        let mut session = session();
+
+        assert_eq!(session.complete("us"), vec!["users"]);
+        assert_eq!(session.complete("users["), vec!["users[0]"]);
+        assert_eq!(session.complete("users[0].a"), vec!["users[0].admin"]);
+        assert!(session.complete("nope.").is_empty());
+
+        session.execute("cd users").unwrap();
+        assert_eq!(session.complete(""), vec!["[0]"]);
+    }
+}
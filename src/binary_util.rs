@@ -1,51 +1,734 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 use anyhow::Context;
+#[cfg(feature = "base64")]
 use base64::{engine::general_purpose as base64_engine, Engine as _};
-use serde::{Deserialize, Serialize};
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Bound, Deref, RangeBounds};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "checksum")]
+use sha1::Sha1;
+#[cfg(feature = "checksum")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
 use std::fs;
-use std::path::PathBuf;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Binary {
-    data: Vec<u8>,
+    pub(crate) data: Storage,
+}
+
+/// Backing store of a `Binary`: an owned buffer or, with the `mmap` feature,
+/// a read-only file mapping that is copied on the first modification.
+#[derive(Debug, Clone)]
+pub(crate) enum Storage {
+    Owned(Vec<u8>),
+
+    #[cfg(feature = "mmap")]
+    Mapped(std::sync::Arc<memmap2::Mmap>),
 }
 
 impl Binary {
     /// Creates a new `BinaryUtil` instance from a `Vec<u8>`
     pub fn new(data: Vec<u8>) -> Self {
-        Self { data }
+        Self {
+            data: Storage::Owned(data),
+        }
+    }
+
+    /// Creates an empty `BinaryUtil` instance with room for `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::new(Vec::with_capacity(capacity))
+    }
+
+    /// Maps a file into memory instead of reading it, so `size`, `slice` and
+    /// `read_range` work without loading the whole file. The data is copied
+    /// only if the binary is modified.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or any
+    /// other, while this binary or a clone of it is alive. Otherwise reads
+    /// may see the data change underneath them or fault.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn mmap(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let file = fs::File::open(path)?;
+
+        // SAFETY: the caller guarantees the file isn't changed while mapped.
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self {
+            data: Storage::Mapped(std::sync::Arc::new(map)),
+        })
+    }
+
+    /// Returns `true` if the binary data is backed by a memory-mapped file.
+    pub fn is_mapped(&self) -> bool {
+        match self.data {
+            Storage::Owned(_) => false,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(_) => true,
+        }
+    }
+
+    /// Joins several `BinaryUtil` instances into one.
+    pub fn concat(parts: &[Binary]) -> Self {
+        let mut data = Vec::with_capacity(parts.iter().map(|p| p.size()).sum());
+        for part in parts {
+            data.extend_from_slice(&part.data);
+        }
+
+        Self::new(data)
     }
 
     /// Reads a binary file and returns a `BinaryUtil` instance.
-    pub fn from_file(path: PathBuf) -> anyhow::Result<Self> {
+    #[cfg(feature = "std")]
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
         let data = fs::read(path)?;
 
-        return Ok(Self { data });
+        Ok(Self::new(data))
+    }
+
+    /// Reads everything from `reader` and returns a `BinaryUtil` instance.
+    #[cfg(feature = "std")]
+    pub fn from_reader(mut reader: impl Read) -> anyhow::Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Ok(Self::new(data))
+    }
+
+    /// Like `from_reader`, but fails once more than `max_bytes` have been read.
+    #[cfg(feature = "std")]
+    pub fn from_reader_limited(reader: impl Read, max_bytes: u64) -> anyhow::Result<Self> {
+        let mut data = Vec::new();
+        reader
+            .take(max_bytes.saturating_add(1))
+            .read_to_end(&mut data)?;
+
+        if data.len() as u64 > max_bytes {
+            anyhow::bail!("Input exceeds the limit of {} bytes", max_bytes);
+        }
+        Ok(Self::new(data))
+    }
+
+    /// Writes the binary data to a file, replacing its contents.
+    #[cfg(feature = "std")]
+    pub fn to_file(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        fs::write(path, &self.data)?;
+
+        Ok(())
+    }
+
+    /// Writes the binary data to a temporary file next to `path` and renames
+    /// it into place, so readers never observe a partially written file. The
+    /// directory is synced afterwards, so the rename survives a crash.
+    #[cfg(feature = "std")]
+    pub fn to_file_atomic(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .context("Failed to write file: path has no file name")?;
+
+        // Unique per call, so concurrent writers in one process don't share
+        // a temporary file.
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+
+        let result = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(&self.data)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temp_path, path));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result?;
+        Ok(sync_parent(path)?)
     }
 
     /// Decode a base64-encoded string and return a `BinaryUtil` instance.
+    #[cfg(feature = "base64")]
     pub fn from_b64(value: String) -> anyhow::Result<Self> {
         let data = base64_engine::STANDARD
             .decode(&value)
-            .context("Failed to decode base64 string")?;
-        return Ok(Self { data });
+            .map_err(|e| anyhow::anyhow!("Failed to decode base64 string: {}", e))?;
+        Ok(Self::new(data))
+    }
+
+    /// Encodes the binary data as a padded, standard base64 string.
+    ///
+    /// Encoding is built in rather than taken from the `base64` feature, so
+    /// text output works without it.
+    pub fn to_b64(&self) -> String {
+        let mut out = String::with_capacity(self.size().div_ceil(3) * 4);
+
+        for chunk in self.data.chunks(3) {
+            let bytes = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            let group = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(B64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    /// Formats the binary data as a `binary!(<base64>)` literal, the form
+    /// accepted by `DValue`'s `FromStr`.
+    pub fn to_literal(&self) -> String {
+        format!("binary!({})", self.to_b64())
+    }
+
+    /// Decode a hex string (upper or lower case) and return a `BinaryUtil` instance.
+    pub fn from_hex(value: &str) -> anyhow::Result<Self> {
+        let digits = value
+            .chars()
+            .map(|c| {
+                c.to_digit(16)
+                    .context("Failed to decode hex string: invalid digit")
+            })
+            .collect::<anyhow::Result<Vec<u32>>>()?;
+
+        if digits.len() % 2 != 0 {
+            anyhow::bail!("Failed to decode hex string: odd number of digits");
+        }
+
+        let data = digits
+            .chunks(2)
+            .map(|pair| (pair[0] * 16 + pair[1]) as u8)
+            .collect();
+        Ok(Self::new(data))
+    }
+
+    /// Encodes the binary data as a lowercase hex string.
+    pub fn to_hex(&self) -> String {
+        self.data.iter().map(|b| format!("{:02x}", b)).collect()
     }
 
     /// Gets the size of the binary data in bytes.
     pub fn size(&self) -> usize {
-        return self.data.len();
+        self.data.len()
+    }
+
+    /// The heap memory held by the binary data. A memory-mapped file is
+    /// backed by the page cache rather than the heap and counts as nothing.
+    pub(crate) fn heap_usage(&self) -> usize {
+        match &self.data {
+            Storage::Owned(data) => data.capacity(),
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(_) => 0,
+        }
+    }
+
+    /// Computes the SHA-256 digest of the binary data.
+    #[cfg(feature = "checksum")]
+    pub fn sha256(&self) -> Binary {
+        Binary::new(Sha256::digest(&self.data).to_vec())
+    }
+
+    /// Computes the SHA-1 digest of the binary data.
+    #[cfg(feature = "checksum")]
+    pub fn sha1(&self) -> Binary {
+        Binary::new(Sha1::digest(&self.data).to_vec())
     }
 
-    /// Returns a clone of the binary data.
+    /// Computes the CRC-32 (IEEE) checksum of the binary data.
+    #[cfg(feature = "checksum")]
+    pub fn crc32(&self) -> u32 {
+        crc32fast::hash(&self.data)
+    }
+
+    /// Returns a clone of the binary data. Use `as_bytes` to avoid the copy.
     pub fn read(&self) -> Vec<u8> {
-        return self.data.clone();
+        self.data.to_vec()
+    }
+
+    /// Borrows the binary data.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Consumes the `BinaryUtil` instance and returns the binary data without copying.
+    pub fn into_vec(self) -> Vec<u8> {
+        match self.data {
+            Storage::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(map) => map.to_vec(),
+        }
+    }
+
+    /// Appends the contents of `other` to the binary data.
+    pub fn append(&mut self, other: &Binary) {
+        self.data.to_mut().extend_from_slice(&other.data);
+    }
+
+    /// Appends `bytes` to the binary data.
+    pub fn extend_from_slice(&mut self, bytes: &[u8]) {
+        self.data.to_mut().extend_from_slice(bytes);
+    }
+
+    /// Returns a borrowed view of `range`, or `None` if it is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Option<BinaryView<'_>> {
+        view(&self.data, range)
+    }
+
+    /// Guesses the MIME type of the binary data from its magic bytes, e.g.
+    /// `image/png`. Returns `None` when no known signature matches.
+    pub fn detect_mime(&self) -> Option<&'static str> {
+        SIGNATURES
+            .iter()
+            .find(|(parts, _)| {
+                parts.iter().all(|(offset, magic)| {
+                    self.data
+                        .get(*offset..offset + magic.len())
+                        .is_some_and(|bytes| bytes == *magic)
+                })
+            })
+            .map(|(_, mime)| *mime)
+    }
+
+    /// Iterates over the bytes of the binary data.
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.data.iter().copied()
+    }
+
+    /// Iterates over the binary data in chunks of `size` bytes; the last
+    /// chunk may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// If `size` is 0, as `slice::chunks` does.
+    pub fn chunks(&self, size: usize) -> core::slice::Chunks<'_, u8> {
+        self.data.chunks(size)
+    }
+
+    /// Copies `range` out of the binary data, or returns `None` if it is out of bounds.
+    pub fn read_range(&self, range: impl RangeBounds<usize>) -> Option<Vec<u8>> {
+        self.slice(range).map(|view| view.as_bytes().to_vec())
+    }
+}
+
+impl AsRef<[u8]> for Binary {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl PartialEq for Binary {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for Binary {}
+
+impl Hash for Binary {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl Deref for Binary {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl Storage {
+    fn to_mut(&mut self) -> &mut Vec<u8> {
+        #[cfg(feature = "mmap")]
+        if let Storage::Mapped(map) = self {
+            *self = Storage::Owned(map.to_vec());
+        }
+
+        match self {
+            Storage::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(_) => unreachable!(),
+        }
+    }
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            Storage::Mapped(map) => map,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Storage {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+// Serialized as a plain byte sequence, whatever the backing store.
+impl Serialize for Storage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Storage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Storage::Owned)
+    }
+}
+
+const B64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Magic bytes and the offset they appear at.
+type Magic = (usize, &'static [u8]);
+
+/// Magic bytes that must all match, and the MIME type they identify.
+const SIGNATURES: &[(&[Magic], &str)] = &[
+    (&[(0, b"\x89PNG\r\n\x1a\n")], "image/png"),
+    (&[(0, b"\xff\xd8\xff")], "image/jpeg"),
+    (&[(0, b"GIF87a")], "image/gif"),
+    (&[(0, b"GIF89a")], "image/gif"),
+    (&[(0, b"RIFF"), (8, b"WEBP")], "image/webp"),
+    // "BM", the reserved bytes and the size of one of the usual DIB headers.
+    (
+        &[(0, b"BM"), (6, b"\0\0\0\0"), (14, b"\x0c\0\0\0")],
+        "image/bmp",
+    ),
+    (
+        &[(0, b"BM"), (6, b"\0\0\0\0"), (14, b"\x28\0\0\0")],
+        "image/bmp",
+    ),
+    (
+        &[(0, b"BM"), (6, b"\0\0\0\0"), (14, b"\x6c\0\0\0")],
+        "image/bmp",
+    ),
+    (
+        &[(0, b"BM"), (6, b"\0\0\0\0"), (14, b"\x7c\0\0\0")],
+        "image/bmp",
+    ),
+    (&[(0, b"\x00\x00\x01\x00")], "image/x-icon"),
+    (&[(0, b"%PDF-")], "application/pdf"),
+    (&[(0, b"\x1f\x8b")], "application/gzip"),
+    (&[(0, b"PK\x03\x04")], "application/zip"),
+    (&[(0, b"\x28\xb5\x2f\xfd")], "application/zstd"),
+    (&[(0, b"BZh")], "application/x-bzip2"),
+    (&[(0, b"\xfd7zXZ\x00")], "application/x-xz"),
+    (&[(0, b"7z\xbc\xaf\x27\x1c")], "application/x-7z-compressed"),
+    (&[(257, b"ustar")], "application/x-tar"),
+    (&[(0, b"\x00asm")], "application/wasm"),
+    (&[(0, b"\x7fELF")], "application/x-elf"),
+    (&[(0, b"SQLite format 3\x00")], "application/vnd.sqlite3"),
+    (&[(0, b"RIFF"), (8, b"WAVE")], "audio/wav"),
+    (&[(0, b"OggS")], "audio/ogg"),
+    (&[(0, b"fLaC")], "audio/flac"),
+    (&[(0, b"ID3")], "audio/mpeg"),
+    (&[(4, b"ftyp")], "video/mp4"),
+];
+
+/// A borrowed range of a `Binary`, returned by `Binary::slice`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinaryView<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BinaryView<'a> {
+    /// Gets the size of the view in bytes.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns a sub-range of the view, or `None` if it is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Option<BinaryView<'a>> {
+        view(self.data, range)
+    }
+
+    /// Copies the view into a new `BinaryUtil` instance.
+    pub fn to_binary(&self) -> Binary {
+        Binary::new(self.data.to_vec())
     }
 }
 
-impl ToString for Binary {
-    fn to_string(&self) -> String {
-        format!(
-            "binary util!({})",
-            base64_engine::STANDARD.encode(self.data.clone())
-        )
+fn view(data: &[u8], range: impl RangeBounds<usize>) -> Option<BinaryView<'_>> {
+    let bounds: (Bound<usize>, Bound<usize>) =
+        (range.start_bound().cloned(), range.end_bound().cloned());
+
+    data.get(bounds).map(|data| BinaryView { data })
+}
+
+/// Shows the size and the first few bytes in hex, e.g.
+/// `Binary(11 bytes: 48656c6c6f20576f…)`, so that logging a large blob stays
+/// cheap. The alternate form, `{:#}`, writes the full `binary!(<base64>)`
+/// literal instead.
+impl fmt::Display for Binary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const PREFIX: usize = 8;
+
+        if f.alternate() {
+            return f.write_str(&self.to_literal());
+        }
+
+        let unit = if self.size() == 1 { "byte" } else { "bytes" };
+        write!(f, "Binary({} {}", self.size(), unit)?;
+        if !self.data.is_empty() {
+            write!(f, ": ")?;
+            for b in self.data.iter().take(PREFIX) {
+                write!(f, "{:02x}", b)?;
+            }
+            if self.size() > PREFIX {
+                write!(f, "…")?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+/// Syncs the directory holding `path`, so a file just renamed into it is
+/// still there after a crash. Not every platform can open a directory to
+/// sync it; there this does nothing.
+#[cfg(feature = "std")]
+pub(crate) fn sync_parent(path: &Path) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    match fs::File::open(parent) {
+        Ok(dir) => dir.sync_all(),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::binary_util::Binary;
+
+    #[test]
+    fn hex_round_trip() {
+        let binary = Binary::from_hex("DEADbeef00").unwrap();
+
+        assert_eq!(binary.read(), vec![0xde, 0xad, 0xbe, 0xef, 0x00]);
+        assert_eq!(binary.to_hex(), "deadbeef00");
+        assert!(Binary::from_hex("abc").is_err());
+        assert!(Binary::from_hex("zz").is_err());
+        assert!(Binary::from_hex("+f").is_err());
+    }
+
+    #[test]
+    fn b64_padding() {
+        let encoded: Vec<String> = (0..5)
+            .map(|n| Binary::new(b"abcd"[..n].to_vec()).to_b64())
+            .collect();
+
+        assert_eq!(encoded, vec!["", "YQ==", "YWI=", "YWJj", "YWJjZA=="]);
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn b64_round_trip() {
+        let binary = Binary::new((0..=255).collect());
+
+        assert_eq!(Binary::from_b64(binary.to_b64()).unwrap(), binary);
+        assert!(Binary::from_b64("not base64!".to_string()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "checksum")]
+    fn digests() {
+        let binary = Binary::new(b"abc".to_vec());
+
+        assert_eq!(
+            binary.sha256().to_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            binary.sha1().to_hex(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(binary.crc32(), 0x352441c2);
+    }
+
+    #[test]
+    fn slice_views() {
+        let binary = Binary::new(b"hello world".to_vec());
+        let world = binary.slice(6..).unwrap();
+
+        assert_eq!(world.as_bytes(), b"world");
+        assert_eq!(world.slice(..=2).unwrap().to_binary().read(), b"wor");
+        assert!(binary.slice(5..20).is_none());
+        assert_eq!(binary.into_vec().len(), 11);
+    }
+
+    #[test]
+    fn build_frames() {
+        let mut frame = Binary::with_capacity(8);
+        frame.extend_from_slice(&[0x01, 0x02]);
+        frame.append(&Binary::new(vec![0x03]));
+
+        let joined = Binary::concat(&[frame.clone(), Binary::new(vec![0xff])]);
+
+        assert_eq!(frame.as_bytes(), &[1, 2, 3]);
+        assert_eq!(joined.as_bytes(), &[1, 2, 3, 255]);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_file() {
+        let path = std::env::temp_dir().join(format!("datastruct-mmap-{}.bin", std::process::id()));
+        std::fs::write(&path, b"mapped bytes").unwrap();
+
+        // SAFETY: nothing else touches this test's file.
+        let mut binary = unsafe { Binary::mmap(&path) }.unwrap();
+        assert!(binary.is_mapped());
+        assert_eq!(binary.size(), 12);
+        assert_eq!(binary.read_range(7..).unwrap(), b"bytes");
+
+        binary.extend_from_slice(b"!");
+        assert!(!binary.is_mapped());
+        assert_eq!(binary.as_bytes(), b"mapped bytes!");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn byte_access() {
+        let binary = Binary::new(vec![1, 2, 3, 4, 5]);
+        let sum = |bytes: &[u8]| bytes.iter().map(|b| *b as u32).sum::<u32>();
+
+        assert_eq!(sum(&binary), 15);
+        assert_eq!(binary.as_ref().len(), 5);
+        assert_eq!(binary.bytes().max(), Some(5));
+        assert_eq!(binary.chunks(2).last(), Some(&[5][..]));
+        assert!(std::panic::catch_unwind(|| binary.chunks(0).count()).is_err());
+    }
+
+    #[test]
+    fn sniff_mime() {
+        let png = Binary::new(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec());
+        let webp = Binary::new(b"RIFF\x10\x00\x00\x00WEBPVP8 ".to_vec());
+
+        assert_eq!(png.detect_mime(), Some("image/png"));
+        assert_eq!(webp.detect_mime(), Some("image/webp"));
+        assert_eq!(
+            Binary::new(b"%PDF-1.7".to_vec()).detect_mime(),
+            Some("application/pdf")
+        );
+        assert_eq!(Binary::new(b"hello".to_vec()).detect_mime(), None);
+
+        // Only a RIFF container is WebP or WAV, and only a real BMP header
+        // is a bitmap rather than text that starts with "BM".
+        let mut bmp = b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0".to_vec();
+        assert_eq!(Binary::new(bmp.clone()).detect_mime(), Some("image/bmp"));
+        bmp[14] = 0x29;
+        assert_eq!(Binary::new(bmp).detect_mime(), None);
+        assert_eq!(
+            Binary::new(b"BMW 3 Series: 1998 to 2005".to_vec()).detect_mime(),
+            None
+        );
+        assert_eq!(
+            Binary::new(b"ABCDEFGHWEBPVP8 ".to_vec()).detect_mime(),
+            None
+        );
+        assert_eq!(
+            Binary::new(b"RIFF\x24\0\0\0WAVEfmt ".to_vec()).detect_mime(),
+            Some("audio/wav")
+        );
+        assert_eq!(
+            Binary::new(b"12345678WAVEfmt ".to_vec()).detect_mime(),
+            None
+        );
+    }
+
+    #[test]
+    fn equality_and_hash() {
+        use std::collections::HashSet;
+
+        let a = Binary::new(vec![1, 2, 3]);
+        let b = Binary::from_hex("010203").unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, Binary::new(vec![1, 2]));
+        assert_eq!(HashSet::from([a, b]).len(), 1);
+    }
+
+    #[test]
+    fn short_display() {
+        let binary = Binary::new(b"Hello World".to_vec());
+
+        assert_eq!(binary.to_string(), "Binary(11 bytes: 48656c6c6f20576f…)");
+        assert_eq!(binary.to_b64(), "SGVsbG8gV29ybGQ=");
+        assert_eq!(Binary::new(vec![]).to_string(), "Binary(0 bytes)");
+        assert_eq!(Binary::new(vec![0xab]).to_string(), "Binary(1 byte: ab)");
+        assert_eq!(format!("{:#}", binary), "binary!(SGVsbG8gV29ybGQ=)");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reader_limit() {
+        let input: &[u8] = b"hello";
+
+        assert_eq!(Binary::from_reader(input).unwrap().size(), 5);
+        assert_eq!(Binary::from_reader_limited(input, 5).unwrap().size(), 5);
+        assert!(Binary::from_reader_limited(input, 4).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn file_round_trip() {
+        let path = std::env::temp_dir().join(format!("datastruct-{}.bin", std::process::id()));
+        let binary = Binary::new(vec![1, 2, 3]);
+
+        binary.to_file(&path).unwrap();
+        assert_eq!(Binary::from_file(&path).unwrap().read(), vec![1, 2, 3]);
+
+        Binary::new(vec![4]).to_file_atomic(&path).unwrap();
+        assert_eq!(Binary::from_file(&path).unwrap().read(), vec![4]);
+
+        // Writers on several threads each get their own temporary file.
+        std::thread::scope(|scope| {
+            for n in 0..8u8 {
+                let path = &path;
+                scope.spawn(move || Binary::new(vec![n; 1000]).to_file_atomic(path).unwrap());
+            }
+        });
+        let data = Binary::from_file(&path).unwrap().read();
+        assert!(data.len() == 1000 && data.iter().all(|&b| b == data[0]));
+
+        std::fs::remove_file(&path).unwrap();
     }
 }
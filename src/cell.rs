@@ -0,0 +1,164 @@
+use crate::path::{AccessError, IntoPath};
+use crate::DValue;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A `DValue` shared between threads behind a read-write lock.
+///
+/// Clones are handles to the same document. Every method takes the lock for
+/// the duration of a single call, so a closure passed to `update_path` sees
+/// and modifies the value atomically. A panic while holding the lock does
+/// not poison the cell; the next caller sees the value as it was left.
+///
+/// ```
+/// use datastruct::cell::DCell;
+/// use datastruct::DValue;
+/// use std::thread;
+///
+/// let cell = DCell::new("{\"hits\": 0}".parse::<DValue>().unwrap());
+///
+/// let workers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let cell = cell.clone();
+///         thread::spawn(move || cell.update_path("hits", |v| v.incr("", 1.0)))
+///     })
+///     .collect();
+/// for worker in workers {
+///     worker.join().unwrap().unwrap().unwrap();
+/// }
+///
+/// assert_eq!(cell.get_path("hits"), Some(DValue::Number(4.0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DCell(Arc<RwLock<DValue>>);
+
+impl DCell {
+    pub fn new(value: DValue) -> Self {
+        Self(Arc::new(RwLock::new(value)))
+    }
+
+    /// Returns a copy of the value at `path`.
+    pub fn get_path<P: IntoPath>(&self, path: P) -> Option<DValue> {
+        self.read().get_path(path).cloned()
+    }
+
+    /// Returns a copy of the whole document.
+    pub fn snapshot(&self) -> DValue {
+        self.read().clone()
+    }
+
+    /// Runs `f` on the document while holding a read lock.
+    pub fn with<R>(&self, f: impl FnOnce(&DValue) -> R) -> R {
+        f(&self.read())
+    }
+
+    /// Runs `f` on the value at `path` while holding the write lock.
+    ///
+    /// Missing dict keys along the path are created as they are by
+    /// `DValue::incr`, so `f` receives `DValue::None` for a new entry.
+    pub fn update_path<P: IntoPath, R>(
+        &self,
+        path: P,
+        f: impl FnOnce(&mut DValue) -> R,
+    ) -> Result<R, AccessError> {
+        let path = path.into_path()?;
+        let mut value = self.write();
+        Ok(f(value.entry_path(&path)?))
+    }
+
+    /// Replaces the value at `path` with `new` if it currently equals
+    /// `expected`, and returns whether it did. A missing entry compares equal
+    /// to `DValue::None`, so passing that as `expected` inserts only if absent.
+    pub fn compare_and_swap<P: IntoPath>(
+        &self,
+        path: P,
+        expected: &DValue,
+        new: DValue,
+    ) -> Result<bool, AccessError> {
+        let path = path.into_path()?;
+        let mut value = self.write();
+
+        if value.get_path(&path).unwrap_or(&DValue::None) != expected {
+            return Ok(false);
+        }
+        *value.entry_path(&path)? = new;
+        Ok(true)
+    }
+
+    /// Replaces the whole document and returns the previous one.
+    pub fn replace(&self, value: DValue) -> DValue {
+        std::mem::replace(&mut self.write(), value)
+    }
+
+    /// Returns the document if this is the last handle to it.
+    pub fn try_into_inner(self) -> Result<DValue, Self> {
+        Arc::try_unwrap(self.0)
+            .map(|lock| lock.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(Self)
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, DValue> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, DValue> {
+        self.0.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<DValue> for DCell {
+    fn from(value: DValue) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::cell::DCell;
+    use crate::path::AccessError;
+    use crate::DValue;
+
+    #[test]
+    fn compare_and_swap() {
+        let cell = DCell::new("{\"state\": \"idle\"}".parse::<DValue>().unwrap());
+        let idle = DValue::String("idle".to_string());
+        let busy = DValue::String("busy".to_string());
+
+        assert_eq!(
+            cell.compare_and_swap("state", &idle, busy.clone()),
+            Ok(true)
+        );
+        assert_eq!(
+            cell.compare_and_swap("state", &idle, busy.clone()),
+            Ok(false)
+        );
+        assert_eq!(cell.get_path("state"), Some(busy));
+
+        assert_eq!(
+            cell.compare_and_swap("owner", &DValue::None, DValue::Number(1.0)),
+            Ok(true)
+        );
+        assert_eq!(
+            cell.compare_and_swap("owner", &DValue::None, DValue::Number(2.0)),
+            Ok(false)
+        );
+        assert_eq!(
+            cell.compare_and_swap("missing", &idle, DValue::None),
+            Ok(false)
+        );
+        assert_eq!(cell.get_path("missing"), None);
+
+        assert!(matches!(
+            cell.update_path("state.x[0]", |_| ()),
+            Err(AccessError::NotFound(_))
+        ));
+        assert!(cell
+            .compare_and_swap("new[0]", &DValue::None, DValue::None)
+            .is_err());
+        assert_eq!(cell.get_path("new"), None);
+        assert!(cell.clone().try_into_inner().is_err());
+        assert_eq!(
+            cell.try_into_inner().unwrap().get_path("owner"),
+            Some(&DValue::Number(1.0))
+        );
+    }
+}
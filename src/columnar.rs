@@ -0,0 +1,310 @@
+use crate::binary_util::Binary;
+use crate::table::{self, Kind};
+use crate::{DValue, Map, ParseError};
+use arrow_array::cast::AsArray;
+use arrow_array::types::{
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type,
+    UInt64Type, UInt8Type,
+};
+use arrow_array::{
+    Array, ArrayRef, BinaryArray, BooleanArray, Float64Array, RecordBatch, RecordBatchOptions,
+    StringArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::errors::ParquetError;
+use parquet::file::reader::ChunkReader;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Field metadata marking a `Utf8` column whose cells hold values in the
+/// text format rather than plain strings.
+const ENCODING_KEY: &str = "datastruct.encoding";
+const ENCODING_TEXT: &str = "text";
+
+#[derive(Debug)]
+pub enum ColumnarError {
+    /// The value is not a list of dicts; holds the offending type.
+    NotTable(String),
+    /// A column has an Arrow type with no `DValue` equivalent.
+    UnsupportedType(DataType),
+    /// A cell of a column marked as holding the text format doesn't parse.
+    InvalidCell {
+        column: String,
+        row: usize,
+        error: ParseError,
+    },
+    Arrow(ArrowError),
+    Parquet(ParquetError),
+}
+
+impl fmt::Display for ColumnarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColumnarError::NotTable(found) => {
+                write!(f, "expected a list of dicts, found {}", found)
+            }
+            ColumnarError::UnsupportedType(ty) => write!(f, "unsupported column type {}", ty),
+            ColumnarError::InvalidCell { column, row, error } => {
+                write!(
+                    f,
+                    "invalid value in column {:?}, row {}: {}",
+                    column, row, error
+                )
+            }
+            ColumnarError::Arrow(err) => write!(f, "arrow error: {}", err),
+            ColumnarError::Parquet(err) => write!(f, "parquet error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ColumnarError {}
+
+impl From<ArrowError> for ColumnarError {
+    fn from(err: ArrowError) -> Self {
+        ColumnarError::Arrow(err)
+    }
+}
+
+impl From<ParquetError> for ColumnarError {
+    fn from(err: ParquetError) -> Self {
+        ColumnarError::Parquet(err)
+    }
+}
+
+impl DValue {
+    /// Converts a list of dicts into an Arrow `RecordBatch`.
+    ///
+    /// Every key becomes a nullable column, in sorted order, and rows that
+    /// lack a key get a null. Columns holding only numbers, booleans,
+    /// strings or binary data get the matching Arrow type. Anything mixed or
+    /// nested is stored as `Utf8` in the text format and restored by
+    /// `from_record_batch`.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let rows = "[{\"id\": 1, \"ok\": true}, {\"id\": 2, \"tags\": [\"x\"]}]".parse::<DValue>().unwrap();
+    /// let batch = rows.to_record_batch().unwrap();
+    ///
+    /// assert_eq!((batch.num_rows(), batch.num_columns()), (2, 3));
+    /// assert_eq!(DValue::from_record_batch(&batch).unwrap().get_path("[1].tags"), rows.get_path("[1].tags"));
+    /// ```
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ColumnarError> {
+        let (rows, kinds) = table::columns(self).map_err(ColumnarError::NotTable)?;
+
+        let mut fields = Vec::with_capacity(kinds.len());
+        let mut columns: Vec<ArrayRef> = Vec::with_capacity(kinds.len());
+
+        for (key, kind) in kinds {
+            let cells = table::cells(rows, key);
+
+            let (field, column): (Field, ArrayRef) = match kind {
+                Kind::Number => (
+                    Field::new(key, DataType::Float64, true),
+                    Arc::new(Float64Array::from_iter(cells.map(|c| c?.as_number()))),
+                ),
+                Kind::Boolean => (
+                    Field::new(key, DataType::Boolean, true),
+                    Arc::new(BooleanArray::from_iter(cells.map(|c| c?.as_bool()))),
+                ),
+                Kind::String | Kind::Empty => (
+                    Field::new(key, DataType::Utf8, true),
+                    Arc::new(StringArray::from_iter(cells.map(|c| match c? {
+                        DValue::String(s) => Some(s.as_str()),
+                        _ => None,
+                    }))),
+                ),
+                Kind::Binary => (
+                    Field::new(key, DataType::Binary, true),
+                    Arc::new(BinaryArray::from_iter(cells.map(|c| match c? {
+                        DValue::BinaryUtil(bin) => Some(bin.as_bytes()),
+                        _ => None,
+                    }))),
+                ),
+                Kind::Text => (
+                    Field::new(key, DataType::Utf8, true).with_metadata(HashMap::from([(
+                        ENCODING_KEY.to_string(),
+                        ENCODING_TEXT.to_string(),
+                    )])),
+                    Arc::new(StringArray::from_iter(cells.map(|c| Some(c?.to_string())))),
+                ),
+            };
+
+            fields.push(field);
+            columns.push(column);
+        }
+
+        let options = RecordBatchOptions::new().with_row_count(Some(rows.len()));
+        Ok(RecordBatch::try_new_with_options(
+            Arc::new(Schema::new(fields)),
+            columns,
+            &options,
+        )?)
+    }
+
+    /// Converts a `RecordBatch` back into a list of dicts. Null cells are
+    /// left out of their row, and integer and float columns become numbers.
+    pub fn from_record_batch(batch: &RecordBatch) -> Result<DValue, ColumnarError> {
+        let mut rows = vec![Map::new(); batch.num_rows()];
+        let schema = batch.schema();
+
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            for (row, cell) in rows.iter_mut().zip(cells(field, column)?) {
+                if let Some(value) = cell {
+                    row.insert(field.name().clone(), value);
+                }
+            }
+        }
+
+        Ok(DValue::List(rows.into_iter().map(DValue::Dict).collect()))
+    }
+
+    /// Writes a list of dicts as a Parquet file with the columns produced
+    /// by `to_record_batch`.
+    pub fn write_parquet<W: Write + Send>(&self, out: W) -> Result<(), ColumnarError> {
+        let batch = self.to_record_batch()?;
+        let mut writer = ArrowWriter::try_new(out, batch.schema(), None)?;
+
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Reads every row of a Parquet file into a list of dicts.
+    pub fn read_parquet<R: ChunkReader + 'static>(reader: R) -> Result<DValue, ColumnarError> {
+        let mut rows = Vec::new();
+
+        for batch in ParquetRecordBatchReaderBuilder::try_new(reader)?.build()? {
+            if let DValue::List(batch_rows) = DValue::from_record_batch(&batch?)? {
+                rows.extend(batch_rows);
+            }
+        }
+        Ok(DValue::List(rows))
+    }
+}
+
+macro_rules! numbers {
+    ($column:expr, $ty:ty) => {
+        $column
+            .as_primitive::<$ty>()
+            .iter()
+            .map(|v| v.map(|n| DValue::Number(n as f64)))
+            .collect()
+    };
+}
+
+fn cells(field: &Field, column: &ArrayRef) -> Result<Vec<Option<DValue>>, ColumnarError> {
+    let text = field.metadata().get(ENCODING_KEY).map(String::as_str) == Some(ENCODING_TEXT);
+    let string = |row: usize, s: Option<&str>| match (text, s) {
+        (_, None) => Ok(None),
+        (true, Some(s)) => s
+            .parse()
+            .map(Some)
+            .map_err(|error| ColumnarError::InvalidCell {
+                column: field.name().clone(),
+                row,
+                error,
+            }),
+        (false, Some(s)) => Ok(Some(DValue::String(s.to_string()))),
+    };
+
+    Ok(match column.data_type() {
+        DataType::Float64 => numbers!(column, Float64Type),
+        DataType::Float32 => numbers!(column, Float32Type),
+        DataType::Int8 => numbers!(column, Int8Type),
+        DataType::Int16 => numbers!(column, Int16Type),
+        DataType::Int32 => numbers!(column, Int32Type),
+        DataType::Int64 => numbers!(column, Int64Type),
+        DataType::UInt8 => numbers!(column, UInt8Type),
+        DataType::UInt16 => numbers!(column, UInt16Type),
+        DataType::UInt32 => numbers!(column, UInt32Type),
+        DataType::UInt64 => numbers!(column, UInt64Type),
+        DataType::Boolean => column
+            .as_boolean()
+            .iter()
+            .map(|v| v.map(DValue::Boolean))
+            .collect(),
+        DataType::Utf8 => column
+            .as_string::<i32>()
+            .iter()
+            .enumerate()
+            .map(|(row, v)| string(row, v))
+            .collect::<Result<_, _>>()?,
+        DataType::LargeUtf8 => column
+            .as_string::<i64>()
+            .iter()
+            .enumerate()
+            .map(|(row, v)| string(row, v))
+            .collect::<Result<_, _>>()?,
+        DataType::Binary => column
+            .as_binary::<i32>()
+            .iter()
+            .map(|v| v.map(|b| DValue::BinaryUtil(Binary::new(b.to_vec()))))
+            .collect(),
+        DataType::LargeBinary => column
+            .as_binary::<i64>()
+            .iter()
+            .map(|v| v.map(|b| DValue::BinaryUtil(Binary::new(b.to_vec()))))
+            .collect(),
+        DataType::Null => vec![None; column.len()],
+        other => return Err(ColumnarError::UnsupportedType(other.clone())),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::columnar::{ColumnarError, ENCODING_KEY, ENCODING_TEXT};
+    use crate::DValue;
+    use arrow_array::{RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::sync::Arc;
+
+    #[test]
+    fn parquet_round_trip() {
+        let rows = "[{\"n\": 1.5, \"s\": \"a\", \"b\": binary!(aGk=), \"mixed\": 1}, {\"n\": 2, \"ok\": false, \"mixed\": {\"k\": [true]}}]".parse::<DValue>().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.parquet");
+
+        rows.write_parquet(File::create(&path).unwrap()).unwrap();
+        let back = DValue::read_parquet(File::open(&path).unwrap()).unwrap();
+
+        for path in [
+            "[0].n",
+            "[0].s",
+            "[0].b",
+            "[0].mixed",
+            "[1].ok",
+            "[1].mixed.k[0]",
+        ] {
+            assert_eq!(back.get_path(path), rows.get_path(path), "{}", path);
+        }
+        assert_eq!(back.get_path("[1].s"), None);
+
+        assert!(matches!(
+            "[1]".parse::<DValue>().unwrap().to_record_batch(),
+            Err(ColumnarError::NotTable(found)) if found == "Number"
+        ));
+    }
+
+    #[test]
+    fn invalid_text_cell() {
+        let field = Field::new("x", DataType::Utf8, true).with_metadata(HashMap::from([(
+            ENCODING_KEY.to_string(),
+            ENCODING_TEXT.to_string(),
+        )]));
+        let cells = StringArray::from(vec![Some("[1]"), None, Some("[1,")]);
+        let batch = RecordBatch::try_new(Arc::new(Schema::new(vec![field])), vec![Arc::new(cells)])
+            .unwrap();
+
+        assert!(matches!(
+            DValue::from_record_batch(&batch),
+            Err(ColumnarError::InvalidCell { column, row: 2, .. }) if column == "x"
+        ));
+    }
+}
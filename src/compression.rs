@@ -0,0 +1,359 @@
+use crate::binary_util::Binary;
+use crate::DValue;
+#[cfg(feature = "parser")]
+use anyhow::Context;
+use std::io::Read;
+
+/// Starts every snapshot written by `DValue::to_compressed_bytes`.
+const SNAPSHOT_MAGIC: &[u8; 4] = b"DSNP";
+const SNAPSHOT_VERSION: u8 = 1;
+/// Magic, version, codec and the uncompressed length as a `u64`.
+const SNAPSHOT_HEADER: usize = 14;
+
+/// The largest text `DValue::from_compressed_bytes` will decompress.
+pub const DEFAULT_MAX_SNAPSHOT_SIZE: u64 = 1 << 30;
+
+/// The largest output `Binary::decompress` will produce.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 1 << 30;
+
+/// A compression format for `Binary::compress`.
+///
+/// Each codec is enabled by the cargo feature of the same name (`gzip`,
+/// `zstd` or `lz4`); using a codec whose feature is disabled returns an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+
+    Zstd,
+
+    /// LZ4 frame format.
+    Lz4,
+}
+
+impl Codec {
+    /// Guesses the codec from the magic bytes at the start of `data`.
+    pub fn detect(data: &[u8]) -> Option<Codec> {
+        if data.starts_with(&[0x1f, 0x8b]) {
+            Some(Codec::Gzip)
+        } else if data.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Codec::Zstd)
+        } else if data.starts_with(&[0x04, 0x22, 0x4d, 0x18]) {
+            Some(Codec::Lz4)
+        } else {
+            None
+        }
+    }
+}
+
+impl Codec {
+    fn id(self) -> u8 {
+        match self {
+            Codec::Gzip => 1,
+            Codec::Zstd => 2,
+            Codec::Lz4 => 3,
+        }
+    }
+
+    #[cfg(feature = "parser")]
+    fn from_id(id: u8) -> Option<Codec> {
+        match id {
+            1 => Some(Codec::Gzip),
+            2 => Some(Codec::Zstd),
+            3 => Some(Codec::Lz4),
+            _ => None,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Codec::Gzip => gzip::compress(data),
+            Codec::Zstd => zstd::compress(data),
+            Codec::Lz4 => lz4::compress(data),
+        }
+    }
+
+    fn decompress(self, data: &[u8], max_bytes: u64) -> anyhow::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        self.decoder(data)?
+            .take(max_bytes.saturating_add(1))
+            .read_to_end(&mut out)?;
+        if out.len() as u64 > max_bytes {
+            anyhow::bail!("Decompressed data is over the limit of {} bytes", max_bytes);
+        }
+        Ok(out)
+    }
+
+    /// A reader over the decompressed `data`.
+    fn decoder(self, data: &[u8]) -> anyhow::Result<Box<dyn Read + '_>> {
+        match self {
+            Codec::Gzip => gzip::decoder(data),
+            Codec::Zstd => zstd::decoder(data),
+            Codec::Lz4 => lz4::decoder(data),
+        }
+    }
+}
+
+impl Binary {
+    /// Compresses the binary data with `codec`.
+    pub fn compress(&self, codec: Codec) -> anyhow::Result<Binary> {
+        Ok(Binary::new(codec.compress(&self.data)?))
+    }
+
+    /// Decompresses the binary data, detecting the codec from its header.
+    /// Fails if the output would be larger than
+    /// `DEFAULT_MAX_DECOMPRESSED_SIZE`.
+    pub fn decompress(&self) -> anyhow::Result<Binary> {
+        self.decompress_limited(DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Like `decompress`, but fails if the output is larger than
+    /// `max_bytes`. Decompression stops once it passes the limit, so a small
+    /// hostile payload can't expand to more than that.
+    pub fn decompress_limited(&self, max_bytes: u64) -> anyhow::Result<Binary> {
+        let Some(codec) = Codec::detect(&self.data) else {
+            anyhow::bail!("Failed to decompress: unknown codec");
+        };
+
+        Ok(Binary::new(codec.decompress(&self.data, max_bytes)?))
+    }
+}
+
+impl DValue {
+    /// Encodes the value as a compressed snapshot for storage: its canonical
+    /// text compressed with `codec`, behind a header of
+    /// `[magic: "DSNP"][version: u8][codec: u8][text len: u64]` with the
+    /// length in little endian.
+    ///
+    /// The canonical text keeps every value exactly, including tuples,
+    /// binary data and non-finite numbers, and equal values give identical
+    /// snapshots.
+    ///
+    /// ```
+    /// # #[cfg(feature = "zstd")] {
+    /// use datastruct::compression::Codec;
+    /// use datastruct::DValue;
+    ///
+    /// let doc = format!("[{}]", vec!["{\"id\": 1, \"tags\": [\"a\", \"b\"]}"; 1000].join(","))
+    ///     .parse::<DValue>()
+    ///     .unwrap();
+    /// let bytes = doc.to_compressed_bytes(Codec::Zstd).unwrap();
+    ///
+    /// assert!(bytes.len() < doc.to_string().len() / 10);
+    /// assert_eq!(DValue::from_compressed_bytes(&bytes).unwrap(), doc);
+    /// # }
+    /// ```
+    pub fn to_compressed_bytes(&self, codec: Codec) -> anyhow::Result<Vec<u8>> {
+        let text = self.to_canonical_string();
+        let body = codec.compress(text.as_bytes())?;
+
+        let mut out = Vec::with_capacity(SNAPSHOT_HEADER + body.len());
+        out.extend_from_slice(SNAPSHOT_MAGIC);
+        out.push(SNAPSHOT_VERSION);
+        out.push(codec.id());
+        out.extend_from_slice(&(text.len() as u64).to_le_bytes());
+        out.extend_from_slice(&body);
+        Ok(out)
+    }
+
+    /// Decodes a snapshot written by `to_compressed_bytes`. Fails if the
+    /// header is missing or from a newer version, the codec's feature is
+    /// disabled, the data is corrupt, or the text would be larger than
+    /// `DEFAULT_MAX_SNAPSHOT_SIZE`.
+    #[cfg(feature = "parser")]
+    pub fn from_compressed_bytes(data: &[u8]) -> anyhow::Result<DValue> {
+        DValue::from_compressed_bytes_limited(data, DEFAULT_MAX_SNAPSHOT_SIZE)
+    }
+
+    /// Like `from_compressed_bytes`, but fails if the text is larger than
+    /// `max_bytes`. Decompression stops as soon as it passes the length in
+    /// the header, so a small corrupt or hostile snapshot can't expand to
+    /// more than that.
+    #[cfg(feature = "parser")]
+    pub fn from_compressed_bytes_limited(data: &[u8], max_bytes: u64) -> anyhow::Result<DValue> {
+        if data.len() < SNAPSHOT_HEADER || !data.starts_with(SNAPSHOT_MAGIC) {
+            anyhow::bail!("Not a compressed snapshot");
+        }
+        if data[4] != SNAPSHOT_VERSION {
+            anyhow::bail!("Unsupported snapshot version {}", data[4]);
+        }
+        let codec =
+            Codec::from_id(data[5]).with_context(|| format!("Unknown codec {}", data[5]))?;
+        let len = u64::from_le_bytes(data[6..SNAPSHOT_HEADER].try_into()?);
+        if len > max_bytes {
+            anyhow::bail!("Snapshot is {} bytes, over the limit of {}", len, max_bytes);
+        }
+
+        let mut text = Vec::new();
+        codec
+            .decoder(&data[SNAPSHOT_HEADER..])?
+            .take(len + 1)
+            .read_to_end(&mut text)?;
+        if text.len() as u64 != len {
+            anyhow::bail!("Snapshot text doesn't match its length of {} bytes", len);
+        }
+        Ok(std::str::from_utf8(&text)?.parse::<DValue>()?)
+    }
+}
+
+#[cfg(feature = "gzip")]
+mod gzip {
+    use std::io::{Read, Write};
+
+    pub fn compress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn decoder(data: &[u8]) -> anyhow::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(flate2::read::GzDecoder::new(data)))
+    }
+}
+
+#[cfg(feature = "zstd")]
+mod zstd {
+    use std::io::Read;
+
+    pub fn compress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        Ok(::zstd::encode_all(data, 0)?)
+    }
+
+    pub fn decoder(data: &[u8]) -> anyhow::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(::zstd::stream::read::Decoder::new(data)?))
+    }
+}
+
+#[cfg(feature = "lz4")]
+mod lz4 {
+    use std::io::{Read, Write};
+
+    pub fn compress(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        encoder.write_all(data)?;
+        Ok(encoder.finish()?)
+    }
+
+    pub fn decoder(data: &[u8]) -> anyhow::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(lz4_flex::frame::FrameDecoder::new(data)))
+    }
+}
+
+macro_rules! disabled_codec {
+    ($name:ident, $feature:literal) => {
+        #[cfg(not(feature = $feature))]
+        mod $name {
+            use std::io::Read;
+
+            pub fn compress(_: &[u8]) -> anyhow::Result<Vec<u8>> {
+                anyhow::bail!(concat!("The `", $feature, "` feature is not enabled"))
+            }
+
+            pub fn decoder(_: &[u8]) -> anyhow::Result<Box<dyn Read + '_>> {
+                anyhow::bail!(concat!("The `", $feature, "` feature is not enabled"))
+            }
+        }
+    };
+}
+
+disabled_codec!(gzip, "gzip");
+disabled_codec!(zstd, "zstd");
+disabled_codec!(lz4, "lz4");
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "parser")]
+    use crate::DValue;
+    use crate::{binary_util::Binary, compression::Codec};
+
+    #[test]
+    fn compress_round_trip() {
+        let binary = Binary::new(b"abcabcabcabcabcabcabcabcabcabc".repeat(10));
+
+        for (codec, enabled) in [
+            (Codec::Gzip, cfg!(feature = "gzip")),
+            (Codec::Zstd, cfg!(feature = "zstd")),
+            (Codec::Lz4, cfg!(feature = "lz4")),
+        ] {
+            let compressed = match binary.compress(codec) {
+                Ok(compressed) => compressed,
+                Err(_) => {
+                    assert!(!enabled);
+                    continue;
+                }
+            };
+
+            assert!(compressed.size() < binary.size());
+            assert_eq!(Codec::detect(&compressed.read()), Some(codec));
+            assert_eq!(compressed.decompress().unwrap().read(), binary.read());
+        }
+
+        assert!(Binary::new(b"plain".to_vec()).decompress().is_err());
+    }
+
+    #[test]
+    fn decompress_limit() {
+        // A megabyte that compresses to almost nothing.
+        let binary = Binary::new(vec![0; 1 << 20]);
+
+        for codec in [Codec::Gzip, Codec::Zstd, Codec::Lz4] {
+            let Ok(compressed) = binary.compress(codec) else {
+                continue;
+            };
+
+            let err = compressed.decompress_limited(1000).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Decompressed data is over the limit of 1000 bytes"
+            );
+            assert_eq!(
+                compressed.decompress_limited(1 << 20).unwrap().read(),
+                binary.read()
+            );
+        }
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn snapshot_round_trip() {
+        let doc = "{\"a\": [1, -inf, none], \"b\": (\"x\", binary!(aGk=))}"
+            .parse::<DValue>()
+            .unwrap();
+
+        for (codec, enabled) in [
+            (Codec::Gzip, cfg!(feature = "gzip")),
+            (Codec::Zstd, cfg!(feature = "zstd")),
+            (Codec::Lz4, cfg!(feature = "lz4")),
+        ] {
+            let Ok(bytes) = doc.to_compressed_bytes(codec) else {
+                assert!(!enabled);
+                continue;
+            };
+            assert_eq!(DValue::from_compressed_bytes(&bytes).unwrap(), doc);
+
+            let mut newer = bytes.clone();
+            newer[4] = 2;
+            assert!(DValue::from_compressed_bytes(&newer).is_err());
+            assert!(DValue::from_compressed_bytes(&bytes[..bytes.len() / 2]).is_err());
+
+            // A megabyte of text that compresses to almost nothing.
+            let big = DValue::String("a".repeat(1 << 20))
+                .to_compressed_bytes(codec)
+                .unwrap();
+            let err = DValue::from_compressed_bytes_limited(&big, 1000).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Snapshot is 1048578 bytes, over the limit of 1000"
+            );
+            let mut lying = big.clone();
+            lying[6..14].copy_from_slice(&10u64.to_le_bytes());
+            let err = DValue::from_compressed_bytes(&lying).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Snapshot text doesn't match its length of 10 bytes"
+            );
+        }
+
+        assert!(DValue::from_compressed_bytes(b"DSNP").is_err());
+        assert!(DValue::from_compressed_bytes(doc.to_string().as_bytes()).is_err());
+    }
+}
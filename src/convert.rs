@@ -0,0 +1,676 @@
+use crate::binary_util::Binary;
+use crate::path::Segment;
+use crate::{DValue, Map};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Returned when a `DValue` cannot be converted to the requested type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    /// The value has the wrong variant; holds the expected and found datatypes.
+    WrongType { expected: String, found: String },
+
+    /// The number has a fractional part, or is NaN or infinite.
+    NotIntegral(f64),
+
+    /// The number does not fit in the target type.
+    OutOfRange(f64),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ConversionError::NotIntegral(n) => write!(f, "{} is not an integer", n),
+            ConversionError::OutOfRange(n) => write!(f, "{} is out of range", n),
+        }
+    }
+}
+
+impl core::error::Error for ConversionError {}
+
+/// The variants of `DValue`, without their contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DType {
+    None,
+    String,
+    Number,
+    Boolean,
+    List,
+    Dict,
+    Tuple,
+    Binary,
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DType::None => "None",
+            DType::String => "String",
+            DType::Number => "Number",
+            DType::Boolean => "Boolean",
+            DType::List => "List",
+            DType::Dict => "Dict",
+            DType::Tuple => "Tuple",
+            DType::Binary => "Binary",
+        })
+    }
+}
+
+/// Returned by `DValue::cast`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastError {
+    /// There is no conversion between the two types.
+    Unsupported { from: DType, to: DType },
+
+    /// The types convert, but not this value, e.g. a string that isn't a
+    /// number.
+    Invalid {
+        from: DType,
+        to: DType,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CastError::Unsupported { from, to } => write!(f, "cannot cast {} to {}", from, to),
+            CastError::Invalid { from, to, reason } => {
+                write!(f, "cannot cast {} to {}: {}", from, to, reason)
+            }
+        }
+    }
+}
+
+impl core::error::Error for CastError {}
+
+/// Returned by `DValue::try_into_vec` and `DValue::try_into_map`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractError<E> {
+    /// The value isn't a list or dict at all.
+    WrongType { expected: String, found: String },
+
+    /// The item at this index or key failed to convert.
+    Item { at: Segment, error: E },
+}
+
+impl<E: fmt::Display> fmt::Display for ExtractError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ExtractError::Item {
+                at: Segment::Index(i),
+                error,
+            } => write!(f, "item {}: {}", i, error),
+            ExtractError::Item {
+                at: Segment::Key(key),
+                error,
+            } => write!(f, "key {:?}: {}", key, error),
+            ExtractError::Item {
+                at: Segment::Wildcard,
+                error,
+            } => write!(f, "{}", error),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for ExtractError<E> {}
+
+macro_rules! try_from_dvalue {
+    ($ty:ty, $value:ident => $convert:expr) => {
+        impl TryFrom<DValue> for $ty {
+            type Error = ConversionError;
+
+            fn try_from($value: DValue) -> Result<Self, ConversionError> {
+                $convert
+            }
+        }
+    };
+}
+
+try_from_dvalue!(f64, value => match value {
+    DValue::Number(n) => Ok(n),
+    other => Err(other.wrong_type("Number")),
+});
+try_from_dvalue!(bool, value => match value {
+    DValue::Boolean(b) => Ok(b),
+    other => Err(other.wrong_type("Boolean")),
+});
+try_from_dvalue!(String, value => match value {
+    DValue::String(s) => Ok(s),
+    other => Err(other.wrong_type("String")),
+});
+try_from_dvalue!(Binary, value => match value {
+    DValue::BinaryUtil(bin) => Ok(bin),
+    other => Err(other.wrong_type("Binary")),
+});
+try_from_dvalue!(i64, value => value.as_i64_checked());
+try_from_dvalue!(u64, value => value.as_u64_checked());
+try_from_dvalue!(usize, value => value.as_usize_checked());
+
+impl DValue {
+    pub fn dtype(&self) -> DType {
+        match self {
+            DValue::None => DType::None,
+            DValue::String(_) => DType::String,
+            DValue::Number(_) => DType::Number,
+            DValue::Boolean(_) => DType::Boolean,
+            DValue::List(_) => DType::List,
+            DValue::Dict(_) => DType::Dict,
+            DValue::Tuple(_) => DType::Tuple,
+            DValue::BinaryUtil(_) => DType::Binary,
+        }
+    }
+
+    /// Converts the value to another variant. Casting to the value's own
+    /// type returns a copy; the other conversions are:
+    ///
+    /// | From    | To      | Rule                                                      |
+    /// |---------|---------|-----------------------------------------------------------|
+    /// | String  | None    | `""`, `"none"` or `"null"`, ignoring case                 |
+    /// | Number  | String  | as in the text format                                     |
+    /// | Boolean | String  | `"true"` or `"false"`                                     |
+    /// | Binary  | String  | the bytes as UTF-8                                        |
+    /// | String  | Number  | the trimmed string as Rust's `f64` parses it              |
+    /// | Boolean | Number  | 1 or 0                                                    |
+    /// | Number  | Boolean | only 1 and 0                                              |
+    /// | String  | Boolean | `true`/`yes`/`on`/`1` or `false`/`no`/`off`/`0`           |
+    /// | Tuple   | List    | the two items                                             |
+    /// | Dict    | List    | `(key, value)` tuples, sorted by key                      |
+    /// | Binary  | List    | one number per byte                                       |
+    /// | List    | Dict    | `(key, value)` tuples or two-item lists, with string keys |
+    /// | List    | Tuple   | a list of exactly two items                               |
+    /// | String  | Binary  | the UTF-8 bytes                                           |
+    /// | List    | Binary  | whole numbers from 0 to 255                               |
+    ///
+    /// Anything else is `CastError::Unsupported`.
+    ///
+    /// ```
+    /// use datastruct::convert::{CastError, DType};
+    /// use datastruct::DValue;
+    ///
+    /// let pair = "[\"port\", 8080]".parse::<DValue>().unwrap();
+    /// assert_eq!(pair.cast(DType::Tuple).unwrap().to_string(), "(\"port\", 8080)");
+    /// assert_eq!(DValue::Number(1.5).cast(DType::String), Ok(DValue::String("1.5".to_string())));
+    /// assert!(matches!(
+    ///     DValue::String("x".to_string()).cast(DType::Number),
+    ///     Err(CastError::Invalid { .. })
+    /// ));
+    /// ```
+    pub fn cast(&self, target: DType) -> Result<DValue, CastError> {
+        let from = self.dtype();
+        let invalid = |reason: String| CastError::Invalid {
+            from,
+            to: target,
+            reason,
+        };
+
+        Ok(match (self, target) {
+            (value, target) if value.dtype() == target => value.clone(),
+
+            (DValue::String(s), DType::None) => match s.trim().to_lowercase().as_str() {
+                "" | "none" | "null" => DValue::None,
+                _ => return Err(invalid(alloc::format!("{:?} is not empty or null", s))),
+            },
+
+            (DValue::Number(_) | DValue::Boolean(_), DType::String) => {
+                DValue::String(self.coerce_string())
+            }
+            (DValue::BinaryUtil(bin), DType::String) => {
+                match core::str::from_utf8(bin.as_bytes()) {
+                    Ok(s) => DValue::String(s.to_string()),
+                    Err(e) => return Err(invalid(e.to_string())),
+                }
+            }
+
+            (DValue::String(s), DType::Number) => match self.coerce_number() {
+                Some(n) => DValue::Number(n),
+                None => return Err(invalid(alloc::format!("{:?} is not a number", s))),
+            },
+            (DValue::Boolean(_), DType::Number) => {
+                DValue::Number(self.coerce_number().unwrap_or(0.0))
+            }
+
+            (DValue::Number(n), DType::Boolean) => match *n {
+                0.0 => DValue::Boolean(false),
+                1.0 => DValue::Boolean(true),
+                n => return Err(invalid(alloc::format!("{} is not 0 or 1", n))),
+            },
+            (DValue::String(s), DType::Boolean) => match s.trim().to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => DValue::Boolean(true),
+                "false" | "no" | "off" | "0" => DValue::Boolean(false),
+                _ => return Err(invalid(alloc::format!("{:?} is not a boolean", s))),
+            },
+
+            (DValue::Tuple(pair), DType::List) => {
+                DValue::List(Vec::from([(*pair.0).clone(), (*pair.1).clone()]))
+            }
+            (DValue::Dict(dict), DType::List) => {
+                let mut entries: Vec<(&String, &DValue)> = dict.iter().collect();
+                entries.sort_unstable_by_key(|(k, _)| *k);
+                DValue::List(
+                    entries
+                        .into_iter()
+                        .map(|(k, v)| {
+                            DValue::Tuple((
+                                Box::new(DValue::String(k.clone())),
+                                Box::new(v.clone()),
+                            ))
+                        })
+                        .collect(),
+                )
+            }
+            (DValue::BinaryUtil(bin), DType::List) => {
+                DValue::List(bin.bytes().map(|b| DValue::Number(f64::from(b))).collect())
+            }
+
+            (DValue::List(list), DType::Dict) => DValue::Dict(
+                list.iter()
+                    .enumerate()
+                    .map(|(i, item)| match item {
+                        DValue::Tuple(pair) => match &*pair.0 {
+                            DValue::String(k) => Ok((k.clone(), (*pair.1).clone())),
+                            _ => Err(i),
+                        },
+                        DValue::List(pair) => match pair.as_slice() {
+                            [DValue::String(k), v] => Ok((k.clone(), v.clone())),
+                            _ => Err(i),
+                        },
+                        _ => Err(i),
+                    })
+                    .collect::<Result<Map<String, DValue>, usize>>()
+                    .map_err(|i| invalid(alloc::format!("item {} is not a key and value", i)))?,
+            ),
+
+            (DValue::List(list), DType::Tuple) => match list.as_slice() {
+                [first, second] => {
+                    DValue::Tuple((Box::new(first.clone()), Box::new(second.clone())))
+                }
+                _ => return Err(invalid(alloc::format!("{} items, not 2", list.len()))),
+            },
+
+            (DValue::String(s), DType::Binary) => {
+                DValue::BinaryUtil(Binary::new(s.as_bytes().to_vec()))
+            }
+            (DValue::List(list), DType::Binary) => DValue::BinaryUtil(Binary::new(
+                list.iter()
+                    .enumerate()
+                    .map(|(i, item)| match item {
+                        DValue::Number(n) if (0.0..=255.0).contains(n) && n % 1.0 == 0.0 => {
+                            Ok(*n as u8)
+                        }
+                        _ => Err(invalid(alloc::format!("item {} is not a byte", i))),
+                    })
+                    .collect::<Result<Vec<u8>, CastError>>()?,
+            )),
+
+            (_, to) => return Err(CastError::Unsupported { from, to }),
+        })
+    }
+
+    /// Converts a whole number to `i64`, failing instead of truncating.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// assert_eq!(DValue::Number(-3.0).as_i64_checked(), Ok(-3));
+    /// assert!(DValue::Number(1.5).as_i64_checked().is_err());
+    /// ```
+    pub fn as_i64_checked(&self) -> Result<i64, ConversionError> {
+        let n = self.integral()?;
+
+        // 2^63 is exactly representable, i64::MAX is not.
+        if (-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&n) {
+            Ok(n as i64)
+        } else {
+            Err(ConversionError::OutOfRange(n))
+        }
+    }
+
+    /// Converts a whole, non-negative number to `u64`, failing instead of truncating.
+    pub fn as_u64_checked(&self) -> Result<u64, ConversionError> {
+        let n = self.integral()?;
+
+        if (0.0..18_446_744_073_709_551_616.0).contains(&n) {
+            Ok(n as u64)
+        } else {
+            Err(ConversionError::OutOfRange(n))
+        }
+    }
+
+    /// Converts a whole, non-negative number to `usize`, failing instead of truncating.
+    pub fn as_usize_checked(&self) -> Result<usize, ConversionError> {
+        let n = self.as_u64_checked()?;
+        usize::try_from(n).map_err(|_| ConversionError::OutOfRange(n as f64))
+    }
+
+    /// Reads the value as a boolean, for loosely typed input.
+    ///
+    /// `None`, `false`, zero, NaN and empty strings, lists, dicts and binary
+    /// data are false, and so are strings that spell false: `"0"`, `"false"`,
+    /// `"no"`, `"off"`, `"none"` and `"null"`, ignoring case and surrounding
+    /// whitespace. Everything else, including every tuple, is true.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// assert!(DValue::String("yes".to_string()).coerce_bool());
+    /// assert!(!DValue::String(" Off ".to_string()).coerce_bool());
+    /// assert!(!DValue::Number(0.0).coerce_bool());
+    /// assert!(!DValue::List(vec![]).coerce_bool());
+    /// ```
+    pub fn coerce_bool(&self) -> bool {
+        match self {
+            DValue::None => false,
+            DValue::Boolean(b) => *b,
+            DValue::Number(n) => *n != 0.0 && !n.is_nan(),
+            DValue::String(s) => !matches!(
+                s.trim().to_lowercase().as_str(),
+                "" | "0" | "false" | "no" | "off" | "none" | "null"
+            ),
+            DValue::List(list) => !list.is_empty(),
+            DValue::Dict(dict) => !dict.is_empty(),
+            DValue::Tuple(_) => true,
+            DValue::BinaryUtil(bin) => !bin.as_bytes().is_empty(),
+        }
+    }
+
+    /// Reads the value as a number, for loosely typed input.
+    ///
+    /// Numbers are returned as they are, `true` and `false` become 1 and 0,
+    /// and strings are parsed as Rust's `f64` reads them after trimming
+    /// whitespace, so `"1e3"`, `"-2.5"` and `"inf"` all work. Anything
+    /// else, including `None`, gives `None`.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// assert_eq!(DValue::String(" 8080 ".to_string()).coerce_number(), Some(8080.0));
+    /// assert_eq!(DValue::Boolean(true).coerce_number(), Some(1.0));
+    /// assert_eq!(DValue::String("eighty".to_string()).coerce_number(), None);
+    /// ```
+    pub fn coerce_number(&self) -> Option<f64> {
+        match self {
+            DValue::Number(n) => Some(*n),
+            DValue::Boolean(b) => Some(if *b { 1.0 } else { 0.0 }),
+            DValue::String(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Reads the value as a string, for loosely typed output.
+    ///
+    /// Strings are returned without the quotes `to_string` adds, `None`
+    /// becomes the empty string, and numbers and booleans are written as
+    /// in the text format. Lists, dicts, tuples and binary data are written
+    /// in the text format, as `to_string` does.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// assert_eq!(DValue::String("a \"b\"".to_string()).coerce_string(), "a \"b\"");
+    /// assert_eq!(DValue::Number(2.5).coerce_string(), "2.5");
+    /// assert_eq!(DValue::None.coerce_string(), "");
+    /// ```
+    pub fn coerce_string(&self) -> String {
+        match self {
+            DValue::None => String::new(),
+            DValue::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Converts every item of a list with `T`'s `TryFrom<DValue>`, stopping
+    /// at the first that fails.
+    ///
+    /// ```
+    /// use datastruct::convert::ExtractError;
+    /// use datastruct::path::Segment;
+    /// use datastruct::DValue;
+    ///
+    /// let ports = "[80, 443]".parse::<DValue>().unwrap();
+    /// assert_eq!(ports.try_into_vec::<u64>(), Ok(vec![80, 443]));
+    ///
+    /// let mixed = "[80, \"443\"]".parse::<DValue>().unwrap();
+    /// assert!(matches!(
+    ///     mixed.try_into_vec::<u64>(),
+    ///     Err(ExtractError::Item { at: Segment::Index(1), .. })
+    /// ));
+    /// ```
+    pub fn try_into_vec<T: TryFrom<DValue>>(self) -> Result<Vec<T>, ExtractError<T::Error>> {
+        let DValue::List(list) = self else {
+            return Err(self.extract_wrong_type("List"));
+        };
+
+        list.into_iter()
+            .enumerate()
+            .map(|(i, item)| {
+                T::try_from(item).map_err(|error| ExtractError::Item {
+                    at: Segment::Index(i),
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    /// Converts every value of a dict with `T`'s `TryFrom<DValue>`, stopping
+    /// at the first that fails in the dict's iteration order.
+    pub fn try_into_map<T: TryFrom<DValue>>(
+        self,
+    ) -> Result<Map<String, T>, ExtractError<T::Error>> {
+        let DValue::Dict(dict) = self else {
+            return Err(self.extract_wrong_type("Dict"));
+        };
+
+        dict.into_iter()
+            .map(|(key, value)| match T::try_from(value) {
+                Ok(value) => Ok((key, value)),
+                Err(error) => Err(ExtractError::Item {
+                    at: Segment::Key(key),
+                    error,
+                }),
+            })
+            .collect()
+    }
+
+    fn integral(&self) -> Result<f64, ConversionError> {
+        match self {
+            DValue::Number(n) if n.is_finite() && n % 1.0 == 0.0 => Ok(*n),
+            DValue::Number(n) => Err(ConversionError::NotIntegral(*n)),
+            other => Err(other.wrong_type("Number")),
+        }
+    }
+
+    fn wrong_type(&self, expected: &str) -> ConversionError {
+        ConversionError::WrongType {
+            expected: expected.to_string(),
+            found: self.datatype(),
+        }
+    }
+
+    fn extract_wrong_type<E>(&self, expected: &str) -> ExtractError<E> {
+        ExtractError::WrongType {
+            expected: expected.to_string(),
+            found: self.datatype(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::convert::ConversionError;
+    use crate::DValue;
+    #[cfg(feature = "parser")]
+    use crate::{
+        convert::{CastError, DType, ExtractError},
+        path::Segment,
+    };
+
+    #[test]
+    fn checked_integers() {
+        assert_eq!(DValue::Number(42.0).as_usize_checked(), Ok(42));
+        assert_eq!(
+            DValue::Number(-1.0).as_u64_checked(),
+            Err(ConversionError::OutOfRange(-1.0))
+        );
+        assert_eq!(
+            DValue::Number(1e19).as_i64_checked(),
+            Err(ConversionError::OutOfRange(1e19))
+        );
+        assert_eq!(
+            DValue::Number(1e19).as_u64_checked(),
+            Ok(10_000_000_000_000_000_000)
+        );
+        assert!(matches!(
+            DValue::Number(f64::NAN).as_i64_checked(),
+            Err(ConversionError::NotIntegral(_))
+        ));
+        assert!(matches!(
+            DValue::Boolean(true).as_i64_checked(),
+            Err(ConversionError::WrongType { .. })
+        ));
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn cast_matrix() {
+        let parse = |s: &str| s.parse::<DValue>().unwrap();
+        let cast = |s: &str, to: DType| parse(s).cast(to).map(|v| v.to_string());
+
+        assert_eq!(cast("\"NULL\"", DType::None), Ok("none".to_string()));
+        assert_eq!(
+            cast("binary!(aGk=)", DType::String),
+            Ok("\"hi\"".to_string())
+        );
+        assert_eq!(cast("\" 1e3\"", DType::Number), Ok("1000".to_string()));
+        assert_eq!(cast("true", DType::Number), Ok("1".to_string()));
+        assert_eq!(cast("\"Off\"", DType::Boolean), Ok("false".to_string()));
+        assert_eq!(cast("(1, 2)", DType::List), Ok("[1,2]".to_string()));
+        assert_eq!(
+            cast("{\"b\": 2, \"a\": 1}", DType::List),
+            Ok("[(\"a\", 1),(\"b\", 2)]".to_string())
+        );
+        assert_eq!(
+            cast("binary!(aGk=)", DType::List),
+            Ok("[104,105]".to_string())
+        );
+        assert_eq!(
+            cast("[(\"a\", 1), [\"b\", 2]]", DType::Dict).map(|s| parse(&s)),
+            Ok(parse("{\"a\": 1, \"b\": 2}"))
+        );
+        assert_eq!(
+            cast("\"hi\"", DType::Binary),
+            Ok("binary!(aGk=)".to_string())
+        );
+        assert_eq!(
+            cast("[104, 105]", DType::Binary),
+            Ok("binary!(aGk=)".to_string())
+        );
+        assert_eq!(cast("[1]", DType::List), Ok("[1]".to_string()));
+
+        assert_eq!(
+            parse("2").cast(DType::Boolean),
+            Err(CastError::Invalid {
+                from: DType::Number,
+                to: DType::Boolean,
+                reason: "2 is not 0 or 1".to_string()
+            })
+        );
+        assert_eq!(
+            cast("[1, 2, 3]", DType::Tuple).unwrap_err().to_string(),
+            "cannot cast List to Tuple: 3 items, not 2"
+        );
+        assert_eq!(
+            cast("[256]", DType::Binary).unwrap_err().to_string(),
+            "cannot cast List to Binary: item 0 is not a byte"
+        );
+        assert_eq!(
+            cast("[1]", DType::Dict).unwrap_err().to_string(),
+            "cannot cast List to Dict: item 0 is not a key and value"
+        );
+        assert_eq!(
+            DValue::None.cast(DType::Number),
+            Err(CastError::Unsupported {
+                from: DType::None,
+                to: DType::Number
+            })
+        );
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn typed_extraction() {
+        let parse = |s: &str| s.parse::<DValue>().unwrap();
+
+        assert_eq!(
+            parse("[\"a\", \"b\"]").try_into_vec::<String>(),
+            Ok(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(parse("[]").try_into_vec::<bool>(), Ok(vec![]));
+        assert_eq!(
+            parse("[1, 2.5]").try_into_vec::<i64>(),
+            Err(ExtractError::Item {
+                at: Segment::Index(1),
+                error: ConversionError::NotIntegral(2.5)
+            })
+        );
+        assert_eq!(
+            parse("{\"a\": 1}").try_into_vec::<f64>(),
+            Err(ExtractError::WrongType {
+                expected: "List".to_string(),
+                found: "Dict".to_string()
+            })
+        );
+
+        let map = parse("{\"a\": 1, \"b\": 2}").try_into_map::<f64>().unwrap();
+        assert_eq!((map["a"], map["b"]), (1.0, 2.0));
+        let err = parse("{\"a\": true}").try_into_map::<f64>().unwrap_err();
+        assert_eq!(err.to_string(), "key \"a\": expected Number, found Boolean");
+        assert_eq!(
+            parse("[[1], [true]]")
+                .try_into_vec::<DValue>()
+                .map(|v| v.len()),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn lossy_coercions() {
+        let string = |s: &str| DValue::String(s.to_string());
+
+        for falsy in [
+            DValue::None,
+            DValue::Number(f64::NAN),
+            DValue::Number(-0.0),
+            string(""),
+            string("  FALSE "),
+            string("null"),
+            DValue::BinaryUtil(crate::binary_util::Binary::new(Vec::new())),
+        ] {
+            assert!(!falsy.coerce_bool(), "{}", falsy);
+        }
+        for truthy in [string("x"), string("00"), DValue::Number(-1.0)] {
+            assert!(truthy.coerce_bool(), "{}", truthy);
+        }
+
+        assert_eq!(string("-1.5e2").coerce_number(), Some(-150.0));
+        assert_eq!(DValue::Boolean(false).coerce_number(), Some(0.0));
+        assert_eq!(string("").coerce_number(), None);
+        assert_eq!(DValue::None.coerce_number(), None);
+        assert_eq!(DValue::List(vec![]).coerce_number(), None);
+
+        assert_eq!(string("x").coerce_string(), "x");
+        assert_eq!(DValue::Boolean(true).coerce_string(), "true");
+        assert_eq!(
+            DValue::List(vec![string("x"), DValue::None]).coerce_string(),
+            "[\"x\",none]"
+        );
+    }
+}
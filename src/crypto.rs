@@ -0,0 +1,76 @@
+use crate::binary_util::Binary;
+use chacha20poly1305::aead::{Aead, Generate, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+const NONCE_LEN: usize = 24;
+
+impl Binary {
+    /// Encrypts the binary data with XChaCha20-Poly1305 under a 256-bit key.
+    ///
+    /// A random nonce is generated for every call and stored in front of the
+    /// ciphertext, so the result can be passed to `decrypt` as is.
+    ///
+    /// ```
+    /// use datastruct::binary_util::Binary;
+    ///
+    /// let key = [42u8; 32];
+    /// let secret = Binary::new(b"api-token".to_vec());
+    /// let sealed = secret.encrypt(&key).unwrap();
+    ///
+    /// assert_eq!(sealed.decrypt(&key).unwrap().as_bytes(), b"api-token");
+    /// assert!(sealed.decrypt(&[0u8; 32]).is_err());
+    /// ```
+    pub fn encrypt(&self, key: &[u8; 32]) -> anyhow::Result<Binary> {
+        let cipher = XChaCha20Poly1305::new(&(*key).into());
+        let nonce = XNonce::generate();
+
+        let ciphertext = cipher
+            .encrypt(&nonce, self.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt binary data"))?;
+
+        let mut data = nonce.to_vec();
+        data.extend_from_slice(&ciphertext);
+        Ok(Binary::new(data))
+    }
+
+    /// Decrypts data produced by `encrypt`, failing if the key is wrong or the
+    /// data has been tampered with.
+    pub fn decrypt(&self, key: &[u8; 32]) -> anyhow::Result<Binary> {
+        if self.size() < NONCE_LEN {
+            anyhow::bail!("Failed to decrypt binary data: input is too short");
+        }
+
+        let (nonce, ciphertext) = self.as_bytes().split_at(NONCE_LEN);
+        let cipher = XChaCha20Poly1305::new(&(*key).into());
+        let nonce = XNonce::try_from(nonce)?;
+
+        let data = cipher
+            .decrypt(&nonce, ciphertext)
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt binary data: authentication failed"))?;
+        Ok(Binary::new(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::binary_util::Binary;
+
+    #[test]
+    fn tampering_is_detected() {
+        let key = [1u8; 32];
+        let sealed = Binary::new(b"secret".to_vec()).encrypt(&key).unwrap();
+
+        let mut tampered = sealed.read();
+        *tampered.last_mut().unwrap() ^= 1;
+
+        assert!(Binary::new(tampered).decrypt(&key).is_err());
+        assert!(Binary::new(vec![0; 4]).decrypt(&key).is_err());
+        assert_ne!(
+            sealed.as_bytes(),
+            Binary::new(b"secret".to_vec())
+                .encrypt(&key)
+                .unwrap()
+                .as_bytes()
+        );
+    }
+}
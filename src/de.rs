@@ -0,0 +1,302 @@
+//! Deserializing a `DValue` into other types with serde.
+//!
+//! `&DValue` is a serde `Deserializer` that presents values in their
+//! natural shape rather than the tagged form `DValue`'s own `Deserialize`
+//! uses: dicts are maps or structs, lists and tuples are sequences, whole
+//! numbers can be read as integers, binary data as bytes and `None` as a
+//! missing `Option` or `()`. An enum variant is a string, or a dict with the
+//! variant name as its only key.
+//!
+//! ```
+//! use datastruct::DValue;
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize, Debug, PartialEq)]
+//! struct Server {
+//!     host: String,
+//!     port: u16,
+//!     tls: Option<bool>,
+//! }
+//!
+//! let config = "{\"servers\": [{\"host\": \"a.local\", \"port\": 8080}]}"
+//!     .parse::<DValue>()
+//!     .unwrap();
+//!
+//! let server: Server = config.get_as("servers[0]").unwrap();
+//! assert_eq!(
+//!     server,
+//!     Server { host: "a.local".to_string(), port: 8080, tls: None }
+//! );
+//! ```
+
+use crate::path::{AccessError, IntoPath};
+use crate::DValue;
+use alloc::string::{String, ToString};
+use core::fmt;
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+/// Returned when a `DValue` can't be deserialized into the requested type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeError {
+    /// `get_as` couldn't resolve its path.
+    Access(AccessError),
+
+    /// The value doesn't have the shape the type expects.
+    Custom(String),
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeError::Access(e) => write!(f, "{}", e),
+            DeError::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+impl core::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError::Custom(msg.to_string())
+    }
+}
+
+impl DValue {
+    /// Deserializes the value at `path` into `T`. See the module docs for
+    /// how values map onto serde's data model.
+    pub fn get_as<T: DeserializeOwned, P: IntoPath>(&self, path: P) -> Result<T, DeError> {
+        let path = path
+            .into_path()
+            .map_err(|e| DeError::Access(AccessError::InvalidPath(e)))?;
+        let value = self
+            .get_path(&path)
+            .ok_or(DeError::Access(AccessError::NotFound(path)))?;
+        T::deserialize(value)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for &'de DValue {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &'de DValue {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self {
+            DValue::None => visitor.visit_unit(),
+            DValue::Boolean(b) => visitor.visit_bool(*b),
+            // Integer visitors don't accept floats, so whole numbers are
+            // offered as integers.
+            DValue::Number(n) => match (self.as_i64_checked(), self.as_u64_checked()) {
+                (Ok(i), _) => visitor.visit_i64(i),
+                (_, Ok(u)) => visitor.visit_u64(u),
+                _ => visitor.visit_f64(*n),
+            },
+            DValue::String(s) => visitor.visit_borrowed_str(s),
+            DValue::BinaryUtil(bin) => visitor.visit_borrowed_bytes(bin.as_bytes()),
+            DValue::List(list) => {
+                let mut seq = SeqDeserializer::new(list.iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            DValue::Tuple(pair) => {
+                let mut seq = SeqDeserializer::new([&*pair.0, &*pair.1].into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            DValue::Dict(dict) => {
+                let mut map = MapDeserializer::new(dict.iter().map(|(k, v)| (k.as_str(), v)));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self {
+            DValue::None => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        match self {
+            DValue::String(variant) => visitor.visit_enum(Enum {
+                variant,
+                value: &DValue::None,
+            }),
+            DValue::Dict(dict) if dict.len() == 1 => {
+                let (variant, value) = dict.iter().next().expect("dict has one entry");
+                visitor.visit_enum(Enum { variant, value })
+            }
+            other => Err(de::Error::custom(alloc::format!(
+                "expected a string or a dict with one key for an enum, found {}",
+                other.datatype()
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// An enum variant and its contents, `DValue::None` for a unit variant.
+struct Enum<'de> {
+    variant: &'de str,
+    value: &'de DValue,
+}
+
+impl<'de> EnumAccess<'de> for Enum<'de> {
+    type Error = DeError;
+    type Variant = &'de DValue;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, &'de DValue), DeError> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self.value))
+    }
+}
+
+impl<'de> VariantAccess<'de> for &'de DValue {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        match self {
+            DValue::None => Ok(()),
+            other => Err(de::Error::custom(alloc::format!(
+                "expected no contents for a unit variant, found {}",
+                other.datatype()
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value, DeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::de::DeError;
+    use crate::path::AccessError;
+    use crate::DValue;
+    use alloc::collections::BTreeMap;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Off,
+        Fixed(f64),
+        Range { low: i32, high: i32 },
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        ratio: f32,
+        pair: (u8, String),
+        modes: Vec<Mode>,
+        limits: BTreeMap<String, i64>,
+        #[serde(with = "serde_bytes_vec")]
+        key: Vec<u8>,
+        missing: Option<String>,
+    }
+
+    mod serde_bytes_vec {
+        use serde::{Deserialize, Deserializer};
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+            <&[u8]>::deserialize(d).map(<[u8]>::to_vec)
+        }
+    }
+
+    #[test]
+    fn deserialize_subtree() {
+        let doc = "{\"app\": {\"name\": \"api\", \"ratio\": 0.5, \"pair\": (7, \"x\"), \
+                   \"modes\": [\"Off\", {\"Fixed\": 1.5}, {\"Range\": {\"low\": -1, \"high\": 3}}], \
+                   \"limits\": {\"rps\": 100}, \"key\": binary!(aGk=), \"missing\": none}}"
+            .parse::<DValue>()
+            .unwrap();
+
+        let config: Config = doc.get_as("app").unwrap();
+        assert_eq!(config.name, "api");
+        assert_eq!(config.ratio, 0.5);
+        assert_eq!(config.pair, (7, "x".to_string()));
+        assert_eq!(
+            config.modes,
+            vec![
+                Mode::Off,
+                Mode::Fixed(1.5),
+                Mode::Range { low: -1, high: 3 }
+            ]
+        );
+        assert_eq!(config.limits["rps"], 100);
+        assert_eq!(config.key, b"hi");
+        assert_eq!(config.missing, None);
+
+        assert_eq!(doc.get_as::<u8, _>("app.pair[0]"), Ok(7));
+    }
+
+    #[test]
+    fn errors() {
+        let doc = "{\"port\": 80.5, \"big\": 300}".parse::<DValue>().unwrap();
+
+        assert!(matches!(
+            doc.get_as::<u16, _>("nope"),
+            Err(DeError::Access(AccessError::NotFound(_)))
+        ));
+        assert!(matches!(
+            doc.get_as::<u16, _>("a["),
+            Err(DeError::Access(AccessError::InvalidPath(_)))
+        ));
+        assert_eq!(
+            doc.get_as::<u16, _>("port").unwrap_err().to_string(),
+            "invalid type: floating point `80.5`, expected u16"
+        );
+        assert!(doc.get_as::<u8, _>("big").is_err());
+        assert!(doc.get_as::<(u8, u8, u8), _>("").is_err());
+    }
+}
@@ -0,0 +1,228 @@
+use crate::binary_util::Binary;
+use anyhow::Context;
+use std::collections::HashMap;
+
+const MAGIC: &[u8; 4] = b"DSP1";
+const BLOCK: usize = 16;
+
+const OP_COPY: u8 = 0;
+const OP_INSERT: u8 = 1;
+
+impl Binary {
+    /// Produces a patch that turns `self` into `other`.
+    ///
+    /// The patch is a sequence of "copy from the source" and "insert literal
+    /// bytes" operations, so blobs that share most of their content produce
+    /// patches far smaller than `other`. It also records the size and CRC-32
+    /// of `self`, and `apply_patch` refuses to run against any other source.
+    ///
+    /// ```
+    /// use datastruct::binary_util::Binary;
+    ///
+    /// let v1 = Binary::new(b"firmware v1 ".repeat(100));
+    /// let mut v2 = v1.clone();
+    /// v2.extend_from_slice(b"hotfix");
+    ///
+    /// let patch = v1.diff(&v2);
+    ///
+    /// assert!(patch.size() < 32);
+    /// assert_eq!(v1.apply_patch(&patch).unwrap().as_bytes(), v2.as_bytes());
+    /// ```
+    pub fn diff(&self, other: &Binary) -> Binary {
+        let old = self.as_bytes();
+        let new = other.as_bytes();
+
+        let mut patch = MAGIC.to_vec();
+        write_varint(&mut patch, old.len() as u64);
+        write_varint(&mut patch, new.len() as u64);
+        patch.extend_from_slice(&self.crc32().to_le_bytes());
+
+        let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+        for offset in (0..old.len().saturating_sub(BLOCK - 1)).step_by(BLOCK) {
+            blocks.entry(&old[offset..offset + BLOCK]).or_insert(offset);
+        }
+
+        let mut literal_start = 0;
+        let mut i = 0;
+
+        while i + BLOCK <= new.len() {
+            let Some(&found) = blocks.get(&new[i..i + BLOCK]) else {
+                i += 1;
+                continue;
+            };
+
+            // Grow the match backwards into the pending literal, then forwards.
+            let (mut start, mut source) = (i, found);
+            while start > literal_start && source > 0 && old[source - 1] == new[start - 1] {
+                start -= 1;
+                source -= 1;
+            }
+
+            let mut end = i + BLOCK;
+            while end < new.len()
+                && source + (end - start) < old.len()
+                && old[source + (end - start)] == new[end]
+            {
+                end += 1;
+            }
+
+            write_insert(&mut patch, &new[literal_start..start]);
+            patch.push(OP_COPY);
+            write_varint(&mut patch, source as u64);
+            write_varint(&mut patch, (end - start) as u64);
+
+            literal_start = end;
+            i = end;
+        }
+
+        write_insert(&mut patch, &new[literal_start..]);
+        Binary::new(patch)
+    }
+
+    /// Applies a patch produced by `diff` to `self`, returning the target data.
+    pub fn apply_patch(&self, patch: &Binary) -> anyhow::Result<Binary> {
+        let old = self.as_bytes();
+        let mut reader = PatchReader {
+            data: patch.as_bytes(),
+            pos: 0,
+        };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            anyhow::bail!("Failed to apply patch: not a patch");
+        }
+
+        let source_len = reader.varint()?;
+        let target_len = reader.varint()? as usize;
+        let crc = u32::from_le_bytes(reader.take(4)?.try_into()?);
+
+        if source_len != old.len() as u64 || crc != self.crc32() {
+            anyhow::bail!("Failed to apply patch: it was made for a different source");
+        }
+
+        // The header is untrusted, so don't let it dictate a huge allocation.
+        let mut out = Vec::with_capacity(target_len.min(old.len() + patch.size()));
+        while reader.pos < reader.data.len() {
+            let bytes = match reader.take(1)?[0] {
+                OP_COPY => {
+                    let offset = reader.varint()? as usize;
+                    let len = reader.varint()? as usize;
+                    offset
+                        .checked_add(len)
+                        .and_then(|end| old.get(offset..end))
+                        .context("Failed to apply patch: copy out of range")?
+                }
+                OP_INSERT => {
+                    let len = reader.varint()? as usize;
+                    reader.take(len)?
+                }
+                op => anyhow::bail!("Failed to apply patch: unknown operation {}", op),
+            };
+            // Stop as soon as the output outgrows the header's length.
+            if bytes.len() > target_len - out.len() {
+                anyhow::bail!("Failed to apply patch: output has the wrong size");
+            }
+            out.extend_from_slice(bytes);
+        }
+
+        if out.len() != target_len {
+            anyhow::bail!("Failed to apply patch: output has the wrong size");
+        }
+        Ok(Binary::new(out))
+    }
+}
+
+fn write_insert(patch: &mut Vec<u8>, bytes: &[u8]) {
+    if !bytes.is_empty() {
+        patch.push(OP_INSERT);
+        write_varint(patch, bytes.len() as u64);
+        patch.extend_from_slice(bytes);
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct PatchReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> PatchReader<'a> {
+    fn take(&mut self, len: usize) -> anyhow::Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .context("Failed to apply patch: unexpected end of patch")?;
+
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> anyhow::Result<u64> {
+        let mut n = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            n |= ((byte & 0x7f) as u64) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        anyhow::bail!("Failed to apply patch: malformed length")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::binary_util::Binary;
+    use crate::delta::{write_varint, MAGIC, OP_COPY};
+
+    #[test]
+    fn diff_and_patch() {
+        let old: Vec<u8> = (0..4096u32).map(|n| (n * 7 % 251) as u8).collect();
+        let mut new = old.clone();
+        new[100] = 0xff;
+        new.splice(2000..2000, b"inserted".iter().copied());
+        new.truncate(3500);
+
+        let (old, new) = (Binary::new(old), Binary::new(new));
+        let patch = old.diff(&new);
+
+        assert!(patch.size() < 100);
+        assert_eq!(old.apply_patch(&patch).unwrap().as_bytes(), new.as_bytes());
+        assert!(new.apply_patch(&patch).is_err());
+
+        let empty = Binary::new(vec![]);
+        assert_eq!(
+            empty.apply_patch(&empty.diff(&new)).unwrap().as_bytes(),
+            new.as_bytes()
+        );
+    }
+
+    #[test]
+    fn patch_longer_than_its_header() {
+        let old = Binary::new(vec![7; 4096]);
+        let mut patch = MAGIC.to_vec();
+        write_varint(&mut patch, 4096);
+        write_varint(&mut patch, 1);
+        patch.extend_from_slice(&old.crc32().to_le_bytes());
+        for _ in 0..1000 {
+            patch.push(OP_COPY);
+            write_varint(&mut patch, 0);
+            write_varint(&mut patch, 4096);
+        }
+
+        let err = old.apply_patch(&Binary::new(patch)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to apply patch: output has the wrong size"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::binary_util::Binary;
+use crate::{DValue, Map};
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::AttributeValue;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamoError {
+    /// DynamoDB has no NaN or infinity.
+    NonFinite(f64),
+    /// An `N` value that isn't a number.
+    InvalidNumber(String),
+    /// An `N` value with more precision than an `f64` can hold.
+    InexactNumber(String),
+    /// An attribute type this SDK version doesn't know about.
+    Unknown,
+}
+
+impl fmt::Display for DynamoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DynamoError::NonFinite(n) => write!(f, "{} can't be stored in DynamoDB", n),
+            DynamoError::InvalidNumber(s) => write!(f, "invalid number {:?}", s),
+            DynamoError::InexactNumber(s) => {
+                write!(f, "number {} can't be represented exactly", s)
+            }
+            DynamoError::Unknown => write!(f, "unknown attribute value type"),
+        }
+    }
+}
+
+impl std::error::Error for DynamoError {}
+
+/// Converts to an `AttributeValue`: dicts become `M`, lists `L`, binary
+/// data `B` and `DValue::None` becomes `NULL`. DynamoDB has no tuples, so
+/// they become two-element `L` values and come back as lists.
+///
+/// ```
+/// use aws_sdk_dynamodb::types::AttributeValue;
+/// use datastruct::DValue;
+///
+/// let item = AttributeValue::try_from(&"{\"qty\": 3}".parse::<DValue>().unwrap()).unwrap();
+/// let qty = item.as_m().unwrap().get("qty").unwrap();
+///
+/// assert_eq!(qty, &AttributeValue::N("3".to_string()));
+/// ```
+impl TryFrom<&DValue> for AttributeValue {
+    type Error = DynamoError;
+
+    fn try_from(value: &DValue) -> Result<Self, DynamoError> {
+        Ok(match value {
+            DValue::None => AttributeValue::Null(true),
+            DValue::String(s) => AttributeValue::S(s.clone()),
+            DValue::Number(n) => AttributeValue::N(number_to_string(*n)?),
+            DValue::Boolean(b) => AttributeValue::Bool(*b),
+            DValue::List(items) => AttributeValue::L(
+                items
+                    .iter()
+                    .map(AttributeValue::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            DValue::Dict(entries) => AttributeValue::M(
+                entries
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), AttributeValue::try_from(v)?)))
+                    .collect::<Result<_, DynamoError>>()?,
+            ),
+            DValue::Tuple(pair) => AttributeValue::L(vec![
+                AttributeValue::try_from(&*pair.0)?,
+                AttributeValue::try_from(&*pair.1)?,
+            ]),
+            DValue::BinaryUtil(bin) => AttributeValue::B(Blob::new(bin.as_bytes())),
+        })
+    }
+}
+
+/// Converts from an `AttributeValue`. The set types `SS`, `NS` and `BS`
+/// become lists, and `N` values fail with `InexactNumber` rather than being
+/// silently rounded to the nearest `f64`.
+impl TryFrom<&AttributeValue> for DValue {
+    type Error = DynamoError;
+
+    fn try_from(value: &AttributeValue) -> Result<Self, DynamoError> {
+        Ok(match value {
+            AttributeValue::Null(_) => DValue::None,
+            AttributeValue::S(s) => DValue::String(s.clone()),
+            AttributeValue::N(n) => DValue::Number(parse_number(n)?),
+            AttributeValue::Bool(b) => DValue::Boolean(*b),
+            AttributeValue::B(blob) => DValue::BinaryUtil(Binary::new(blob.as_ref().to_vec())),
+            AttributeValue::L(items) => DValue::List(
+                items
+                    .iter()
+                    .map(DValue::try_from)
+                    .collect::<Result<_, _>>()?,
+            ),
+            AttributeValue::M(entries) => DValue::Dict(
+                entries
+                    .iter()
+                    .map(|(k, v)| Ok((k.clone(), DValue::try_from(v)?)))
+                    .collect::<Result<Map<_, _>, DynamoError>>()?,
+            ),
+            AttributeValue::Ss(items) => {
+                DValue::List(items.iter().cloned().map(DValue::String).collect())
+            }
+            AttributeValue::Ns(items) => DValue::List(
+                items
+                    .iter()
+                    .map(|n| Ok(DValue::Number(parse_number(n)?)))
+                    .collect::<Result<_, DynamoError>>()?,
+            ),
+            AttributeValue::Bs(items) => DValue::List(
+                items
+                    .iter()
+                    .map(|blob| DValue::BinaryUtil(Binary::new(blob.as_ref().to_vec())))
+                    .collect(),
+            ),
+            _ => return Err(DynamoError::Unknown),
+        })
+    }
+}
+
+fn number_to_string(n: f64) -> Result<String, DynamoError> {
+    if !n.is_finite() {
+        return Err(DynamoError::NonFinite(n));
+    }
+    Ok(if n.fract() == 0.0 && n.abs() < 1e16 {
+        format!("{}", n as i64)
+    } else {
+        format!("{:e}", n)
+    })
+}
+
+fn parse_number(s: &str) -> Result<f64, DynamoError> {
+    let n: f64 = s
+        .trim()
+        .parse()
+        .map_err(|_| DynamoError::InvalidNumber(s.to_string()))?;
+
+    // `{:e}` prints the shortest digits that round-trip, so the input was
+    // exact only if it has the same significant digits.
+    match (decimal(s.trim()), decimal(&format!("{:e}", n))) {
+        (Some(input), Some(parsed)) if input == parsed => Ok(n),
+        (Some(_), Some(_)) => Err(DynamoError::InexactNumber(s.to_string())),
+        _ => Err(DynamoError::InvalidNumber(s.to_string())),
+    }
+}
+
+/// Normalizes a decimal string to its sign, significant digits and
+/// exponent, so that "1.50", "15e-1" and "1.5" compare equal.
+fn decimal(s: &str) -> Option<(bool, String, i64)> {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], s[i + 1..].parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let digits = format!("{}{}", int, frac);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let trimmed = digits.trim_start_matches('0').trim_end_matches('0');
+    if trimmed.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+
+    let trailing = (digits.len() - digits.trim_end_matches('0').len()) as i64;
+    let exponent = exponent - frac.len() as i64 + trailing;
+    Some((negative, trimmed.to_string(), exponent))
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::dynamodb::DynamoError;
+    use crate::DValue;
+    use aws_sdk_dynamodb::types::AttributeValue;
+
+    #[test]
+    fn item_round_trip() {
+        let value = "{\"a\": [1.5, -2e300, true, \"x\"], \"b\": binary!(aGk=), \"c\": (0.1, 7)}"
+            .parse::<DValue>()
+            .unwrap();
+        let item = AttributeValue::try_from(&value).unwrap();
+        let back = DValue::try_from(&item).unwrap();
+
+        assert_eq!(back.get_path("a"), value.get_path("a"));
+        assert_eq!(back.get_path("b"), value.get_path("b"));
+        assert_eq!(back.get_path("c").unwrap().datatype(), "List");
+        assert_eq!(back.get_path("c[0]"), Some(&DValue::Number(0.1)));
+
+        let set = AttributeValue::Ns(vec!["1.50".to_string(), "-25E-1".to_string()]);
+        assert_eq!(
+            DValue::try_from(&set).unwrap(),
+            "[1.5, -2.5]".parse::<DValue>().unwrap()
+        );
+    }
+
+    #[test]
+    fn precision_loss_is_reported() {
+        let big = AttributeValue::N("12345678901234567890123".to_string());
+        assert_eq!(
+            DValue::try_from(&big),
+            Err(DynamoError::InexactNumber(
+                "12345678901234567890123".to_string()
+            ))
+        );
+        assert!(matches!(
+            DValue::try_from(&AttributeValue::N("1.2.3".to_string())),
+            Err(DynamoError::InvalidNumber(_))
+        ));
+        assert_eq!(
+            AttributeValue::try_from(&DValue::Number(f64::INFINITY)),
+            Err(DynamoError::NonFinite(f64::INFINITY))
+        );
+    }
+}
@@ -0,0 +1,286 @@
+use crate::path::Path;
+use crate::{DValue, Map};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvError {
+    /// A `${` at this byte offset of the string at `path` has no closing `}`.
+    Unclosed { path: Path, position: usize },
+
+    /// The variable `name` is not set and the placeholder at `path` has no
+    /// default.
+    Missing { path: Path, name: String },
+
+    /// With `keys` set, two keys of the dict at `path` expand to `key`.
+    DuplicateKey { path: Path, key: String },
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvError::Unclosed { path, position } => {
+                write!(f, "unclosed ${{ at {} in {}", position, path)
+            }
+            EnvError::Missing { path, name } => {
+                write!(
+                    f,
+                    "environment variable {} is not set, used in {}",
+                    name, path
+                )
+            }
+            EnvError::DuplicateKey { path, key } => {
+                write!(f, "more than one key expands to {:?} in {}", key, path)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+/// Replaces `${VAR}` and `${VAR:-default}` in the strings of a value.
+///
+/// The default is used when the variable is unset or empty, and `$${`
+/// stands for a literal `${`. Variables come from the process environment
+/// unless `vars` is given; dict keys are left alone unless `keys` is set.
+///
+/// ```
+/// use datastruct::env::Env;
+/// use datastruct::DValue;
+///
+/// let mut config = "{\"url\": \"http://${HOST}:${PORT:-8080}/\", \"${HOST}\": \"$${HOST}\"}"
+///     .parse::<DValue>()
+///     .unwrap();
+/// Env::new().vars([("HOST", "db")]).keys(true).expand(&mut config).unwrap();
+///
+/// assert_eq!(config.get_path("url"), Some(&DValue::String("http://db:8080/".to_string())));
+/// assert_eq!(config.get_path("db"), Some(&DValue::String("${HOST}".to_string())));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Env {
+    vars: Option<Map<String, String>>,
+    keys: bool,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Looks variables up in `vars` instead of the process environment.
+    pub fn vars<K, V>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        let vars = vars.into_iter().map(|(k, v)| (k.into(), v.into()));
+        self.vars = Some(vars.collect());
+        self
+    }
+
+    /// Also expands dict keys. Two keys that expand to the same one are an
+    /// error, and leave the dict as it was.
+    pub fn keys(mut self, keys: bool) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Expands every string in `value`. On error `value` may be left partly
+    /// expanded.
+    pub fn expand(&self, value: &mut DValue) -> Result<(), EnvError> {
+        self.expand_value(value, &Path::root())
+    }
+
+    /// Expands a single string; `path` is only used in errors.
+    pub fn expand_str(&self, text: &str, path: &Path) -> Result<String, EnvError> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+
+        while let Some(start) = rest.find("${") {
+            let position = text.len() - rest.len() + start;
+            if rest[..start].ends_with('$') {
+                out.push_str(&rest[..start - 1]);
+                out.push_str("${");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            out.push_str(&rest[..start]);
+
+            let body = &rest[start + 2..];
+            let end = body.find('}').ok_or_else(|| EnvError::Unclosed {
+                path: path.clone(),
+                position,
+            })?;
+            let (name, default) = match body[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&body[..end], None),
+            };
+
+            match (self.var(name).filter(|v| !v.is_empty()), default) {
+                (Some(value), _) => out.push_str(&value),
+                (None, Some(default)) => out.push_str(default),
+                (None, None) => match self.var(name) {
+                    Some(empty) => out.push_str(&empty),
+                    None => {
+                        return Err(EnvError::Missing {
+                            path: path.clone(),
+                            name: name.to_string(),
+                        })
+                    }
+                },
+            }
+            rest = &body[end + 1..];
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn var(&self, name: &str) -> Option<String> {
+        match &self.vars {
+            Some(vars) => vars.get(name).cloned(),
+            None => std::env::var(name).ok(),
+        }
+    }
+
+    fn expand_value(&self, value: &mut DValue, path: &Path) -> Result<(), EnvError> {
+        match value {
+            DValue::String(s) if s.contains("${") => *s = self.expand_str(s, path)?,
+            DValue::List(list) => {
+                for (i, item) in list.iter_mut().enumerate() {
+                    self.expand_value(item, &path.index(i))?;
+                }
+            }
+            DValue::Tuple(tuple) => {
+                self.expand_value(&mut tuple.0, &path.index(0))?;
+                self.expand_value(&mut tuple.1, &path.index(1))?;
+            }
+            DValue::Dict(dict) => {
+                if self.keys && dict.keys().any(|k| k.contains("${")) {
+                    // Expanded key to original, so nothing moves until every
+                    // key has expanded without clashing.
+                    let mut renames = Map::with_capacity(dict.len());
+                    for key in dict.keys() {
+                        let expanded = match key.contains("${") {
+                            true => self.expand_str(key, &path.key(key))?,
+                            false => key.clone(),
+                        };
+                        if renames.contains_key(&expanded) {
+                            return Err(EnvError::DuplicateKey {
+                                path: path.clone(),
+                                key: expanded,
+                            });
+                        }
+                        renames.insert(expanded, key.clone());
+                    }
+                    let mut old = core::mem::take(dict);
+                    *dict = renames
+                        .into_iter()
+                        .map(|(key, original)| {
+                            let item = old.remove(&original).unwrap_or(DValue::None);
+                            (key, item)
+                        })
+                        .collect();
+                }
+                for (key, item) in dict.iter_mut() {
+                    self.expand_value(item, &path.key(key))?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl DValue {
+    /// Expands `${VAR}` and `${VAR:-default}` in every string from the
+    /// process environment. See `Env` for the details and more options.
+    pub fn expand_env(&mut self) -> Result<(), EnvError> {
+        Env::new().expand(self)
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::env::{Env, EnvError};
+    use crate::path::Path;
+    use crate::DValue;
+
+    #[test]
+    fn expands_strings() {
+        let env = Env::new().vars([("USER", "ada"), ("EMPTY", "")]);
+        let expand = |text: &str| env.expand_str(text, &Path::root());
+
+        assert_eq!(expand("plain $USER"), Ok("plain $USER".to_string()));
+        assert_eq!(
+            expand("${USER}@${HOST:-localhost}"),
+            Ok("ada@localhost".to_string())
+        );
+        assert_eq!(
+            expand("[${EMPTY}|${EMPTY:-x}|${USER:-x}]"),
+            Ok("[|x|ada]".to_string())
+        );
+        assert_eq!(
+            expand("$${USER} ${USER:-a:-b}"),
+            Ok("${USER} ada".to_string())
+        );
+        assert_eq!(
+            expand("ok ${USER"),
+            Err(EnvError::Unclosed {
+                path: Path::root(),
+                position: 3
+            })
+        );
+    }
+
+    #[test]
+    fn expands_values() {
+        let mut doc = "{\"a\": [\"${X}\", (1, \"${X}${X}\")], \"${X}\": {\"b\": \"${MISSING}\"}}"
+            .parse::<DValue>()
+            .unwrap();
+
+        let err = Env::new().vars([("X", "x")]).expand(&mut doc).unwrap_err();
+        assert_eq!(
+            err,
+            EnvError::Missing {
+                path: Path::root().key("${X}").key("b"),
+                name: "MISSING".to_string()
+            }
+        );
+
+        let env = Env::new().vars([("X", "x"), ("MISSING", "m")]).keys(true);
+        env.expand(&mut doc).unwrap();
+        assert_eq!(
+            doc,
+            "{\"a\": [\"x\", (1, \"xx\")], \"x\": {\"b\": \"m\"}}"
+                .parse::<DValue>()
+                .unwrap()
+        );
+
+        let mut clash = "{\"a\": {\"${X}\": 1, \"x\": 2}, \"b\": 3}"
+            .parse::<DValue>()
+            .unwrap();
+        let before = clash.clone();
+        assert_eq!(
+            env.expand(&mut clash).unwrap_err(),
+            EnvError::DuplicateKey {
+                path: Path::root().key("a"),
+                key: "x".to_string()
+            }
+        );
+        assert_eq!(clash, before);
+
+        // A key that fails to expand leaves the dict whole too.
+        let mut missing = "{\"${Y}\": 1, \"z\": 2}".parse::<DValue>().unwrap();
+        assert!(env.expand(&mut missing).is_err());
+        assert_eq!(missing.len(), Some(2));
+    }
+
+    #[test]
+    fn process_environment() {
+        std::env::set_var("DATASTRUCT_ENV_TEST", "set");
+        let mut value = DValue::String("${DATASTRUCT_ENV_TEST}".to_string());
+        value.expand_env().unwrap();
+
+        assert_eq!(value, DValue::String("set".to_string()));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{DValue, ParseError};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 4] = b"DSEV";
+/// The newest format `unseal` reads, and the one `seal` writes.
+pub const FORMAT_VERSION: u8 = 1;
+/// Magic, version, creation time and body length.
+const HEADER: usize = 21;
+const CHECKSUM: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnvelopeError {
+    /// The data doesn't start like an envelope.
+    NotSealed,
+
+    /// The envelope's format version is 0 or newer than `FORMAT_VERSION`.
+    UnsupportedVersion(u8),
+
+    /// The data is shorter or longer than its header says.
+    Truncated,
+
+    /// The checksum doesn't match: the data was changed after sealing.
+    Corrupted,
+
+    /// The checksum matches but the creation time, in milliseconds since
+    /// the Unix epoch, can't be represented as a `SystemTime` here.
+    InvalidTime(u64),
+
+    /// The checksum matches but the body isn't a valid value.
+    Parse(ParseError),
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvelopeError::NotSealed => write!(f, "not a sealed envelope"),
+            EnvelopeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported envelope version {}", version)
+            }
+            EnvelopeError::Truncated => write!(f, "envelope length does not match its header"),
+            EnvelopeError::Corrupted => write!(f, "envelope checksum does not match"),
+            EnvelopeError::InvalidTime(created) => {
+                write!(f, "envelope creation time {} is out of range", created)
+            }
+            EnvelopeError::Parse(e) => write!(f, "sealed value is invalid: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+/// A value read back by `DValue::unseal`, with when it was sealed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    pub value: DValue,
+    pub created: SystemTime,
+    pub version: u8,
+}
+
+impl DValue {
+    /// Encodes the value for storage or transfer with a checksum, so that
+    /// `unseal` notices any change to the bytes instead of decoding a
+    /// different document.
+    ///
+    /// The layout is `[magic: "DSEV"][version: u8][created: u64 ms since
+    /// the Unix epoch][len: u64][canonical text][sha256: 32 bytes]`, with
+    /// integers in little endian and the hash over everything before it.
+    /// The checksum catches corruption, not deliberate forgery: anyone can
+    /// recompute it. Use `DValue::sign` for that.
+    ///
+    /// ```
+    /// use datastruct::envelope::EnvelopeError;
+    /// use datastruct::DValue;
+    ///
+    /// let doc = "{\"total\": 100}".parse::<DValue>().unwrap();
+    /// let mut sealed = doc.seal();
+    /// assert_eq!(DValue::unseal(&sealed).unwrap().value, doc);
+    ///
+    /// // A single flipped bit turns 100 into 101, just before the `}` and the
+    /// // 32-byte checksum.
+    /// let digit = sealed.len() - 34;
+    /// sealed[digit] ^= 1;
+    /// assert_eq!(DValue::unseal(&sealed), Err(EnvelopeError::Corrupted));
+    /// ```
+    pub fn seal(&self) -> Vec<u8> {
+        let body = self.to_canonical_string();
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut out = Vec::with_capacity(HEADER + body.len() + CHECKSUM);
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        out.extend_from_slice(&created.to_le_bytes());
+        out.extend_from_slice(&(body.len() as u64).to_le_bytes());
+        out.extend_from_slice(body.as_bytes());
+        let checksum = Sha256::digest(&out);
+        out.extend_from_slice(&checksum);
+        out
+    }
+
+    /// Checks and decodes an envelope written by `seal`.
+    pub fn unseal(data: &[u8]) -> Result<Envelope, EnvelopeError> {
+        if data.len() < MAGIC.len() + 1 || !data.starts_with(MAGIC) {
+            return Err(EnvelopeError::NotSealed);
+        }
+        let version = data[4];
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(EnvelopeError::UnsupportedVersion(version));
+        }
+        if data.len() < HEADER + CHECKSUM {
+            return Err(EnvelopeError::Truncated);
+        }
+
+        let (signed, checksum) = data.split_at(data.len() - CHECKSUM);
+        let len = u64::from_le_bytes(signed[13..HEADER].try_into().unwrap());
+        if len != (signed.len() - HEADER) as u64 {
+            return Err(EnvelopeError::Truncated);
+        }
+        if Sha256::digest(signed).as_slice() != checksum {
+            return Err(EnvelopeError::Corrupted);
+        }
+
+        let created = u64::from_le_bytes(signed[5..13].try_into().unwrap());
+        let created = UNIX_EPOCH
+            .checked_add(Duration::from_millis(created))
+            .ok_or(EnvelopeError::InvalidTime(created))?;
+        let value = std::str::from_utf8(&signed[HEADER..])
+            .map_err(|_| EnvelopeError::Corrupted)?
+            .parse::<DValue>()
+            .map_err(EnvelopeError::Parse)?;
+        Ok(Envelope {
+            value,
+            created,
+            version,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::envelope::{EnvelopeError, FORMAT_VERSION};
+    use crate::DValue;
+    use sha2::{Digest, Sha256};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn round_trip() {
+        let doc = "{\"a\": [1, nan, none], \"b\": (\"x\", binary!(aGk=))}"
+            .parse::<DValue>()
+            .unwrap();
+        let before = SystemTime::now() - Duration::from_millis(1);
+        let envelope = DValue::unseal(&doc.seal()).unwrap();
+
+        assert_eq!(envelope.value, doc);
+        assert_eq!(envelope.version, FORMAT_VERSION);
+        assert!(envelope.created >= before && envelope.created <= SystemTime::now());
+    }
+
+    #[test]
+    fn damage_is_detected() {
+        let sealed = DValue::List(vec![DValue::Number(1.0)]).seal();
+        let with = |i: usize, byte: u8| {
+            let mut data = sealed.clone();
+            data[i] = byte;
+            DValue::unseal(&data)
+        };
+
+        // Every byte is covered, header and checksum included.
+        for (i, byte) in sealed.iter().enumerate() {
+            assert!(with(i, byte ^ 0x10).is_err(), "byte {}", i);
+        }
+        assert_eq!(with(0, b'X'), Err(EnvelopeError::NotSealed));
+        assert_eq!(with(4, 9), Err(EnvelopeError::UnsupportedVersion(9)));
+        assert_eq!(with(4, 0), Err(EnvelopeError::UnsupportedVersion(0)));
+        assert_eq!(
+            DValue::unseal(&sealed[..sealed.len() - 1]),
+            Err(EnvelopeError::Truncated)
+        );
+        assert_eq!(DValue::unseal(b""), Err(EnvelopeError::NotSealed));
+    }
+
+    #[test]
+    fn far_future_time() {
+        let mut sealed = DValue::None.seal();
+        let body = sealed.len() - 32;
+        sealed[5..13].copy_from_slice(&u64::MAX.to_le_bytes());
+        let checksum = Sha256::digest(&sealed[..body]);
+        sealed[body..].copy_from_slice(&checksum);
+
+        // Whether this fits depends on the platform's `SystemTime`, but it
+        // must not panic either way.
+        match DValue::unseal(&sealed) {
+            Ok(envelope) => assert!(envelope.created > SystemTime::now()),
+            Err(e) => assert_eq!(e, EnvelopeError::InvalidTime(u64::MAX)),
+        }
+    }
+}
@@ -0,0 +1,951 @@
+//! A streaming event model of the text format.
+//!
+//! A value is a sequence of `Event`s: scalars are single events, and
+//! containers are a start event, their items (each dict item preceded by a
+//! `Key`) and an end event. `EventReader` pulls events out of text,
+//! `DValue::events` out of a value, and `Builder` and `EventWriter` push
+//! them into a value or text. Together they allow transforms and custom
+//! serializers that never build a `DValue` tree.
+//!
+//! ```
+//! use datastruct::events::{Event, EventReader, EventWriter};
+//!
+//! let mut out = String::new();
+//! let mut writer = EventWriter::new(&mut out);
+//! for event in EventReader::new("{\"name\": \"ada\", \"tags\": [\"x\", 1]}") {
+//!     let event = match event.unwrap() {
+//!         Event::String(s) => Event::String(s.to_uppercase().into()),
+//!         event => event,
+//!     };
+//!     writer.push(event).unwrap();
+//! }
+//!
+//! assert_eq!(out, "{\"name\":\"ADA\",\"tags\":[\"X\",1]}");
+//! ```
+
+use crate::binary_util::Binary;
+use crate::{format, DValue, Map};
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(all(feature = "std", feature = "parser"))]
+use {crate::options::Literals, std::path::PathBuf};
+#[cfg(feature = "parser")]
+use {
+    crate::{ParseError, TextError, ValueParser},
+    alloc::string::ToString,
+    nom::bytes::complete::tag,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'a> {
+    None,
+    String(Cow<'a, str>),
+    Number(f64),
+    Boolean(bool),
+    Binary(Cow<'a, Binary>),
+    StartList,
+    EndList,
+
+    /// Opens a dict, whose entries are each a `Key` followed by a value.
+    StartDict,
+    Key(Cow<'a, str>),
+    EndDict,
+
+    /// Opens a tuple, which holds exactly two values.
+    StartTuple,
+    EndTuple,
+}
+
+impl Event<'_> {
+    /// Copies any borrowed text or binary data, so the event can outlive
+    /// the input it was read from.
+    pub fn into_owned(self) -> Event<'static> {
+        match self {
+            Event::None => Event::None,
+            Event::String(s) => Event::String(Cow::Owned(s.into_owned())),
+            Event::Number(n) => Event::Number(n),
+            Event::Boolean(b) => Event::Boolean(b),
+            Event::Binary(binary) => Event::Binary(Cow::Owned(binary.into_owned())),
+            Event::StartList => Event::StartList,
+            Event::EndList => Event::EndList,
+            Event::StartDict => Event::StartDict,
+            Event::Key(key) => Event::Key(Cow::Owned(key.into_owned())),
+            Event::EndDict => Event::EndDict,
+            Event::StartTuple => Event::StartTuple,
+            Event::EndTuple => Event::EndTuple,
+        }
+    }
+
+    /// The value of a scalar event, or `None` for the others.
+    #[cfg(feature = "std")]
+    pub(crate) fn to_scalar(&self) -> Option<DValue> {
+        Some(match self {
+            Event::None => DValue::None,
+            Event::String(s) => DValue::String(s.clone().into_owned()),
+            Event::Number(n) => DValue::Number(*n),
+            Event::Boolean(b) => DValue::Boolean(*b),
+            Event::Binary(binary) => DValue::BinaryUtil(binary.as_ref().clone()),
+            _ => return None,
+        })
+    }
+
+    /// The bytes an event takes up when a reference copies it.
+    #[cfg(feature = "parser")]
+    fn expanded_bytes(&self) -> usize {
+        core::mem::size_of::<Event>()
+            + match self {
+                Event::String(s) | Event::Key(s) => s.len(),
+                Event::Binary(binary) => binary.size(),
+                _ => 0,
+            }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Event::None => "None",
+            Event::String(_) => "String",
+            Event::Number(_) => "Number",
+            Event::Boolean(_) => "Boolean",
+            Event::Binary(_) => "Binary",
+            Event::StartList => "StartList",
+            Event::EndList => "EndList",
+            Event::StartDict => "StartDict",
+            Event::Key(_) => "Key",
+            Event::EndDict => "EndDict",
+            Event::StartTuple => "StartTuple",
+            Event::EndTuple => "EndTuple",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventError {
+    /// The event can't come at this point, such as an `EndList` inside a
+    /// dict or a third value in a tuple. Holds the event's name.
+    Unexpected(&'static str),
+
+    /// `finish` was called before a whole value was pushed.
+    Incomplete,
+
+    /// The underlying `fmt::Write` failed.
+    Write,
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::Unexpected(name) => write!(f, "unexpected {} event", name),
+            EventError::Incomplete => write!(f, "incomplete value"),
+            EventError::Write => write!(f, "failed to write event"),
+        }
+    }
+}
+
+impl core::error::Error for EventError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Container {
+    List,
+    Dict,
+    Tuple,
+}
+
+/// Tracks open containers so only events forming a single well-formed
+/// value are accepted.
+#[derive(Default)]
+pub(crate) struct Nesting {
+    /// Each open container, the number of items in it so far, and for a
+    /// dict whether a key is waiting for its value.
+    stack: Vec<(Container, usize, bool)>,
+    complete: bool,
+}
+
+impl Nesting {
+    /// Accepts `event`, returning the separator that goes before it in the
+    /// compact text format.
+    pub(crate) fn accept(&mut self, event: &Event) -> Result<&'static str, EventError> {
+        let unexpected = Err(EventError::Unexpected(event.name()));
+        let is_end = matches!(event, Event::EndList | Event::EndDict | Event::EndTuple);
+        let is_key = matches!(event, Event::Key(_));
+
+        let separator = match self.stack.last_mut() {
+            None if self.complete || is_end || is_key => return unexpected,
+            None => "",
+            Some((Container::Dict, items, waiting)) => match (event, *waiting) {
+                (Event::Key(_), false) => {
+                    *waiting = true;
+                    return Ok(if *items > 0 { "," } else { "" });
+                }
+                (Event::EndDict, false) => "",
+                (_, true) if !is_end && !is_key => {
+                    *waiting = false;
+                    *items += 1;
+                    ":"
+                }
+                _ => return unexpected,
+            },
+            Some((Container::List, items, _)) => match event {
+                Event::EndList => "",
+                _ if is_end || is_key => return unexpected,
+                _ => {
+                    *items += 1;
+                    if *items > 1 {
+                        ","
+                    } else {
+                        ""
+                    }
+                }
+            },
+            Some((Container::Tuple, items, _)) => match event {
+                Event::EndTuple if *items == 2 => "",
+                _ if is_end || is_key || *items == 2 => return unexpected,
+                _ => {
+                    *items += 1;
+                    if *items > 1 {
+                        ", "
+                    } else {
+                        ""
+                    }
+                }
+            },
+        };
+
+        match event {
+            Event::StartList => self.stack.push((Container::List, 0, false)),
+            Event::StartDict => self.stack.push((Container::Dict, 0, false)),
+            Event::StartTuple => self.stack.push((Container::Tuple, 0, false)),
+            _ if is_end => {
+                self.stack.pop();
+            }
+            _ => {}
+        }
+        self.complete = self.stack.is_empty();
+        Ok(separator)
+    }
+
+    /// Whether a whole value has been accepted.
+    pub(crate) fn is_complete(&self) -> bool {
+        self.complete
+    }
+}
+
+/// Builds a `DValue` from pushed events.
+///
+/// ```
+/// use datastruct::events::{Builder, Event};
+/// use datastruct::DValue;
+///
+/// let mut builder = Builder::new();
+/// for event in [Event::StartList, Event::Number(1.0), Event::None, Event::EndList] {
+///     builder.push(event).unwrap();
+/// }
+///
+/// assert_eq!(builder.finish().unwrap().to_string(), "[1,none]");
+/// ```
+#[derive(Default)]
+pub struct Builder {
+    nesting: Nesting,
+    stack: Vec<Partial>,
+    root: Option<DValue>,
+}
+
+enum Partial {
+    List(Vec<DValue>),
+    Dict(Map<String, DValue>, Option<String>),
+    Tuple(Vec<DValue>),
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: Event) -> Result<(), EventError> {
+        self.nesting.accept(&event)?;
+
+        let value = match event {
+            Event::None => DValue::None,
+            Event::String(s) => DValue::String(s.into_owned()),
+            Event::Number(n) => DValue::Number(n),
+            Event::Boolean(b) => DValue::Boolean(b),
+            Event::Binary(binary) => DValue::BinaryUtil(binary.into_owned()),
+            Event::StartList => {
+                self.stack.push(Partial::List(Vec::new()));
+                return Ok(());
+            }
+            Event::StartDict => {
+                self.stack.push(Partial::Dict(Map::new(), None));
+                return Ok(());
+            }
+            Event::StartTuple => {
+                self.stack.push(Partial::Tuple(Vec::new()));
+                return Ok(());
+            }
+            Event::Key(key) => {
+                if let Some(Partial::Dict(_, pending)) = self.stack.last_mut() {
+                    *pending = Some(key.into_owned());
+                }
+                return Ok(());
+            }
+            Event::EndList | Event::EndDict | Event::EndTuple => match self.stack.pop() {
+                Some(Partial::List(list)) => DValue::List(list),
+                Some(Partial::Dict(dict, _)) => DValue::Dict(dict),
+                Some(Partial::Tuple(mut pair)) => {
+                    let second = pair.pop().unwrap_or(DValue::None);
+                    let first = pair.pop().unwrap_or(DValue::None);
+                    DValue::Tuple((Box::new(first), Box::new(second)))
+                }
+                None => return Err(EventError::Unexpected(event.name())),
+            },
+        };
+
+        match self.stack.last_mut() {
+            None => self.root = Some(value),
+            Some(Partial::List(items) | Partial::Tuple(items)) => items.push(value),
+            Some(Partial::Dict(dict, pending)) => {
+                dict.insert(pending.take().unwrap_or_default(), value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the value once it is complete.
+    pub fn finish(self) -> Result<DValue, EventError> {
+        self.root.ok_or(EventError::Incomplete)
+    }
+}
+
+/// Writes pushed events as compact text, the same as `to_string` gives for
+/// the value they describe.
+pub struct EventWriter<W> {
+    out: W,
+    nesting: Nesting,
+}
+
+impl<W: fmt::Write> EventWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            nesting: Nesting::default(),
+        }
+    }
+
+    pub fn push(&mut self, event: Event) -> Result<(), EventError> {
+        let separator = self.nesting.accept(&event)?;
+        write_event(&mut self.out, separator, &event).map_err(|_| EventError::Write)
+    }
+
+    /// Returns the writer once a whole value has been written.
+    pub fn finish(self) -> Result<W, EventError> {
+        match self.nesting.is_complete() {
+            true => Ok(self.out),
+            false => Err(EventError::Incomplete),
+        }
+    }
+}
+
+/// Writes `event` and the `separator` before it in the compact text format.
+pub(crate) fn write_event<W: fmt::Write>(
+    out: &mut W,
+    separator: &str,
+    event: &Event,
+) -> fmt::Result {
+    out.write_str(separator)?;
+    match event {
+        Event::None => out.write_str("none"),
+        Event::String(s) => format::write_quoted(out, s, false),
+        Event::Number(n) => write!(out, "{}", n),
+        Event::Boolean(b) => write!(out, "{}", b),
+        Event::Binary(binary) => out.write_str(&binary.to_literal()),
+        Event::StartList => out.write_char('['),
+        Event::EndList => out.write_char(']'),
+        Event::StartDict => out.write_char('{'),
+        Event::Key(key) => format::write_quoted(out, key, false),
+        Event::EndDict => out.write_char('}'),
+        Event::StartTuple => out.write_char('('),
+        Event::EndTuple => out.write_char(')'),
+    }
+}
+
+/// Iterator over the events of a value, returned by `DValue::events`.
+pub struct Events<'a> {
+    stack: Vec<Pending<'a>>,
+}
+
+enum Pending<'a> {
+    Value(&'a DValue),
+    Key(&'a str),
+    End(Event<'static>),
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Event<'a>> {
+        let value = match self.stack.pop()? {
+            Pending::Value(value) => value,
+            Pending::Key(key) => return Some(Event::Key(Cow::Borrowed(key))),
+            Pending::End(event) => return Some(event),
+        };
+
+        Some(match value {
+            DValue::None => Event::None,
+            DValue::String(s) => Event::String(Cow::Borrowed(s)),
+            DValue::Number(n) => Event::Number(*n),
+            DValue::Boolean(b) => Event::Boolean(*b),
+            DValue::BinaryUtil(binary) => Event::Binary(Cow::Borrowed(binary)),
+            DValue::List(list) => {
+                self.stack.push(Pending::End(Event::EndList));
+                self.stack.extend(list.iter().rev().map(Pending::Value));
+                Event::StartList
+            }
+            DValue::Dict(dict) => {
+                self.stack.push(Pending::End(Event::EndDict));
+                let entries: Vec<_> = dict.iter().collect();
+                for (key, value) in entries.into_iter().rev() {
+                    self.stack.push(Pending::Value(value));
+                    self.stack.push(Pending::Key(key));
+                }
+                Event::StartDict
+            }
+            DValue::Tuple(tuple) => {
+                self.stack.push(Pending::End(Event::EndTuple));
+                self.stack.push(Pending::Value(&tuple.1));
+                self.stack.push(Pending::Value(&tuple.0));
+                Event::StartTuple
+            }
+        })
+    }
+}
+
+impl DValue {
+    /// The events describing this value, with dict entries in iteration
+    /// order.
+    ///
+    /// ```
+    /// use datastruct::events::Event;
+    /// use datastruct::DValue;
+    ///
+    /// let value = "[(1, true)]".parse::<DValue>().unwrap();
+    /// let events: Vec<Event> = value.events().collect();
+    ///
+    /// assert_eq!(events.len(), 6);
+    /// assert_eq!(events[2], Event::Number(1.0));
+    /// ```
+    pub fn events(&self) -> Events<'_> {
+        Events {
+            stack: alloc::vec![Pending::Value(self)],
+        }
+    }
+}
+
+/// A pull parser yielding the events of a document in the text format.
+///
+/// Strings and keys without escapes are borrowed from the input. After an
+/// error the iterator ends.
+#[cfg(feature = "parser")]
+pub struct EventReader<'a> {
+    rest: &'a str,
+    len: usize,
+    stack: Vec<(Container, usize, bool)>,
+    started: bool,
+    done: bool,
+    /// Where `binary!(file:...)` literals are read from, if anywhere.
+    #[cfg(feature = "std")]
+    files: Option<(PathBuf, u64)>,
+    #[cfg(feature = "std")]
+    literals: Option<Literals>,
+    anchors: Option<Anchors>,
+    /// The rest of a reference or literal being expanded, in reverse.
+    replay: Vec<Event<'static>>,
+}
+
+/// The most bytes that references may expand to in one document, which
+/// stops a few nested references from growing exponentially. Each event
+/// counts its own size plus any text or binary data it holds.
+#[cfg(feature = "parser")]
+const MAX_EXPANSION: usize = 1 << 26;
+
+/// Anchored values seen so far and the state of expanding references.
+#[cfg(feature = "parser")]
+#[derive(Default)]
+struct Anchors {
+    /// Each anchored value's events and their size in bytes.
+    defined: Map<String, (Vec<Event<'static>>, usize)>,
+    /// Anchored values still being read: the name, the nesting depth within
+    /// the value and its events so far.
+    recording: Vec<(String, usize, Vec<Event<'static>>)>,
+    expanded: usize,
+}
+
+#[cfg(feature = "parser")]
+impl Anchors {
+    /// Adds an event to every anchored value being read, storing those it
+    /// completes.
+    fn record(&mut self, event: &Event) {
+        for (_, depth, events) in &mut self.recording {
+            events.push(event.clone().into_owned());
+            match event {
+                Event::StartList | Event::StartDict | Event::StartTuple => *depth += 1,
+                Event::EndList | Event::EndDict | Event::EndTuple => *depth -= 1,
+                _ => {}
+            }
+        }
+        while let Some((_, 0, _)) = self.recording.last() {
+            let (name, _, events) = self.recording.pop().unwrap_or_default();
+            let bytes = events.iter().map(Event::expanded_bytes).sum();
+            self.defined.insert(name, (events, bytes));
+        }
+    }
+}
+
+#[cfg(feature = "parser")]
+impl<'a> EventReader<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self {
+            rest: text,
+            len: text.len(),
+            stack: Vec::new(),
+            started: false,
+            done: false,
+            #[cfg(feature = "std")]
+            files: None,
+            #[cfg(feature = "std")]
+            literals: None,
+            anchors: None,
+            replay: Vec::new(),
+        }
+    }
+
+    /// Reads `&name value` as `value`, remembering it as `name`, and `*name`
+    /// as the events of the last value anchored as `name`. Names are made of
+    /// ASCII letters, digits, `_` and `-`.
+    ///
+    /// ```
+    /// use datastruct::events::{Builder, EventReader};
+    ///
+    /// let text = "{\"base\": &defaults {\"retries\": 3}, \"jobs\": [*defaults, *defaults]}";
+    /// let mut builder = Builder::new();
+    /// for event in EventReader::new(text).anchors(true) {
+    ///     builder.push(event.unwrap()).unwrap();
+    /// }
+    ///
+    /// let value = builder.finish().unwrap();
+    /// assert_eq!(value.get_path("jobs[1].retries"), value.get_path("base.retries"));
+    /// ```
+    pub fn anchors(mut self, anchors: bool) -> Self {
+        self.anchors = anchors.then(Anchors::default);
+        self
+    }
+
+    /// Reads `binary!(file:...)` literals of up to `limit` bytes from under
+    /// `root`.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_files(mut self, root: Option<PathBuf>, limit: u64) -> Self {
+        self.files = root.map(|root| (root, limit));
+        self
+    }
+
+    /// Reads `name!(...)` literals with the handlers in `literals`.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_literals(mut self, literals: Option<Literals>) -> Self {
+        self.literals = literals;
+        self
+    }
+
+    fn error(&mut self, message: &str) -> ParseError {
+        self.done = true;
+        ParseError {
+            position: self.len - self.rest.len(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Moves past the `,` before the next item, returning `false` if the
+    /// container is closed by `close` instead.
+    fn separator(&mut self, close: char, items: usize) -> Result<bool, ParseError> {
+        if items > 0 {
+            match self.rest.strip_prefix(',') {
+                Some(rest) => self.rest = skip_space(rest),
+                None if self.rest.starts_with(close) => {}
+                None => return Err(self.error(&alloc::format!("expected `,` or `{}`", close))),
+            }
+        }
+        match self.rest.strip_prefix(close) {
+            Some(rest) => {
+                self.rest = rest;
+                self.stack.pop();
+                Ok(false)
+            }
+            None => Ok(true),
+        }
+    }
+
+    /// The name after a `&` or `*`, and the text after it.
+    fn anchor_name(&mut self, text: &'a str) -> Result<(&'a str, &'a str), ParseError> {
+        let end = text
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(text.len());
+        match end {
+            0 => {
+                self.rest = text;
+                Err(self.error("expected an anchor name"))
+            }
+            _ => Ok((&text[..end], &text[end..])),
+        }
+    }
+
+    fn value(&mut self) -> Result<Event<'a>, ParseError> {
+        if let Some(text) = self
+            .rest
+            .strip_prefix('&')
+            .filter(|_| self.anchors.is_some())
+        {
+            let (name, rest) = self.anchor_name(text)?;
+            self.rest = skip_space(rest);
+            if let Some(anchors) = &mut self.anchors {
+                anchors.recording.push((name.to_string(), 0, Vec::new()));
+            }
+        }
+        if let Some(text) = self
+            .rest
+            .strip_prefix('*')
+            .filter(|_| self.anchors.is_some())
+        {
+            return self.reference(text);
+        }
+
+        let msg = self.rest;
+        let (rest, event) = match msg.chars().next() {
+            Some('[') => (&msg[1..], Event::StartList),
+            Some('{') => (&msg[1..], Event::StartDict),
+            Some('(') => (&msg[1..], Event::StartTuple),
+            None => return Err(self.error("unexpected end of input in value")),
+            Some(_) => {
+                if let Ok((rest, _)) = tag::<_, _, TextError>("none")(msg) {
+                    (rest, Event::None)
+                } else if let Ok((rest, n)) = ValueParser::parse_num(msg) {
+                    (rest, Event::Number(n))
+                } else if let Ok((rest, b)) = ValueParser::parse_bool(msg) {
+                    (rest, Event::Boolean(b))
+                } else if let Ok((rest, s)) = ValueParser::parse_string(msg) {
+                    (rest, Event::String(s))
+                } else if let Ok((rest, binary)) = ValueParser::parse_bin(msg) {
+                    (rest, Event::Binary(Cow::Owned(binary)))
+                } else if let Some((rest, binary)) = self.include(msg)? {
+                    (rest, Event::Binary(Cow::Owned(binary)))
+                } else if let Some((rest, value)) = self.literal(msg)? {
+                    let events = value.events().map(Event::into_owned).collect();
+                    return Ok(self.expand(rest, events));
+                } else {
+                    return Err(self.error("invalid value"));
+                }
+            }
+        };
+
+        match event {
+            Event::StartList => self.stack.push((Container::List, 0, false)),
+            Event::StartDict => self.stack.push((Container::Dict, 0, false)),
+            Event::StartTuple => self.stack.push((Container::Tuple, 0, false)),
+            _ => {}
+        }
+        self.rest = rest;
+        Ok(event)
+    }
+
+    /// Starts expanding the reference `*name` at the start of `text`.
+    fn reference(&mut self, text: &'a str) -> Result<Event<'a>, ParseError> {
+        let (name, rest) = self.anchor_name(text)?;
+        let Some(anchors) = &mut self.anchors else {
+            return Err(self.error("invalid value"));
+        };
+        let Some((events, bytes)) = anchors.defined.get(name) else {
+            return Err(self.error(&alloc::format!("unknown anchor `{}`", name)));
+        };
+
+        anchors.expanded = anchors.expanded.saturating_add(*bytes);
+        if anchors.expanded > MAX_EXPANSION {
+            return Err(self.error("references expand to too many values"));
+        }
+        let events = events.clone();
+        Ok(self.expand(rest, events))
+    }
+
+    /// Returns the first of `events`, a whole value, and queues the rest to
+    /// come before anything after `rest`.
+    fn expand(&mut self, rest: &'a str, mut events: Vec<Event<'static>>) -> Event<'a> {
+        events.reverse();
+        let first = events.pop().unwrap_or(Event::None);
+        self.replay = events;
+        self.rest = rest;
+        first
+    }
+
+    /// Reads the file of a `binary!(file:...)` literal, if file includes are
+    /// allowed.
+    #[cfg(feature = "std")]
+    fn include(&mut self, msg: &'a str) -> Result<Option<(&'a str, Binary)>, ParseError> {
+        let Some((root, limit)) = &self.files else {
+            return Ok(None);
+        };
+        let Ok((rest, path)) = ValueParser::parse_file_ref(msg) else {
+            return Ok(None);
+        };
+        match crate::options::read_include(root, path, *limit) {
+            Ok(binary) => Ok(Some((rest, binary))),
+            Err(message) => Err(self.error(&message)),
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn include(&mut self, _: &'a str) -> Result<Option<(&'a str, Binary)>, ParseError> {
+        Ok(None)
+    }
+
+    /// Reads a `name!(...)` literal with its registered handler, if any
+    /// handlers are registered.
+    #[cfg(feature = "std")]
+    fn literal(&mut self, msg: &'a str) -> Result<Option<(&'a str, DValue)>, ParseError> {
+        let Some(literals) = &self.literals else {
+            return Ok(None);
+        };
+        let Some((name, inner, rest)) = crate::options::split_literal(msg) else {
+            return Ok(None);
+        };
+        let Some(handler) = literals.get(name) else {
+            return Err(self.error(&alloc::format!("unknown literal `{}!`", name)));
+        };
+        match handler(inner) {
+            Ok(value) => Ok(Some((rest, value))),
+            Err(message) => Err(self.error(&alloc::format!("invalid {}!: {}", name, message))),
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn literal(&mut self, _: &'a str) -> Result<Option<(&'a str, DValue)>, ParseError> {
+        Ok(None)
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, ParseError> {
+        self.rest = skip_space(self.rest);
+
+        let Some(&(container, items, waiting)) = self.stack.last() else {
+            if !self.started {
+                self.started = true;
+                return self.value().map(Some);
+            }
+            self.done = true;
+            return match self.rest.is_empty() {
+                true => Ok(None),
+                false => Err(self.error("unexpected trailing characters")),
+            };
+        };
+
+        let depth = self.stack.len() - 1;
+        match container {
+            Container::List => {
+                if !self.separator(']', items)? {
+                    return Ok(Some(Event::EndList));
+                }
+                self.stack[depth].1 += 1;
+            }
+            Container::Dict if waiting => {
+                self.rest = match self.rest.strip_prefix(':') {
+                    Some(rest) => skip_space(rest),
+                    None => return Err(self.error("expected `:`")),
+                };
+                self.stack[depth].1 += 1;
+                self.stack[depth].2 = false;
+            }
+            Container::Dict => {
+                if !self.separator('}', items)? {
+                    return Ok(Some(Event::EndDict));
+                }
+                let (rest, key) = ValueParser::parse_string(self.rest)
+                    .map_err(|_| self.error("expected a string key"))?;
+                self.rest = rest;
+                self.stack[depth].2 = true;
+                return Ok(Some(Event::Key(key)));
+            }
+            Container::Tuple => match items {
+                0 => self.stack[depth].1 += 1,
+                1 => {
+                    self.rest = match self.rest.strip_prefix(',') {
+                        Some(rest) => skip_space(rest),
+                        None => return Err(self.error("expected `,` in tuple")),
+                    };
+                    self.stack[depth].1 += 1;
+                }
+                _ => {
+                    self.rest = match self.rest.strip_prefix(')') {
+                        Some(rest) => rest,
+                        None => return Err(self.error("expected `)`")),
+                    };
+                    self.stack.pop();
+                    return Ok(Some(Event::EndTuple));
+                }
+            },
+        }
+        self.value().map(Some)
+    }
+}
+
+/// Skips the whitespace allowed between tokens: the same ASCII characters
+/// `str::parse` skips, and not all of Unicode's.
+#[cfg(feature = "parser")]
+fn skip_space(text: &str) -> &str {
+    text.trim_start_matches([' ', '\t', '\r', '\n'])
+}
+
+#[cfg(feature = "parser")]
+impl<'a> Iterator for EventReader<'a> {
+    type Item = Result<Event<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let event = match self.replay.pop() {
+            Some(event) => Ok(Some(event)),
+            None => self.next_event(),
+        };
+        if let (Ok(Some(event)), Some(anchors)) = (&event, &mut self.anchors) {
+            anchors.record(event);
+        }
+        event.transpose()
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::events::{Builder, Event, EventError, EventReader, EventWriter};
+    use crate::DValue;
+    use alloc::borrow::Cow;
+
+    const DOC: &str = "{ \"a\": [1, \"x\\n\", (none, -inf),], \"b\": binary!(aGk=), \"c\": {}, \"d\": ([], true) }";
+
+    #[test]
+    fn read_events() {
+        let events: Vec<Event> = EventReader::new("[\"plain\", {\"k\\\"\": (1, none)}]")
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![
+                Event::StartList,
+                Event::String(Cow::Borrowed("plain")),
+                Event::StartDict,
+                Event::Key(Cow::Owned("k\"".to_string())),
+                Event::StartTuple,
+                Event::Number(1.0),
+                Event::None,
+                Event::EndTuple,
+                Event::EndDict,
+                Event::EndList,
+            ]
+        );
+        assert!(matches!(events[1], Event::String(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn round_trips() {
+        let value = DOC.parse::<DValue>().unwrap();
+
+        let mut builder = Builder::new();
+        for event in EventReader::new(DOC) {
+            builder.push(event.unwrap()).unwrap();
+        }
+        assert_eq!(builder.finish(), Ok(value.clone()));
+
+        let mut writer = EventWriter::new(String::new());
+        for event in value.events() {
+            writer.push(event).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), value.to_string());
+    }
+
+    #[test]
+    fn read_errors() {
+        let error = |text: &str| {
+            let events: Result<Vec<_>, _> = EventReader::new(text).collect();
+            let error = events.unwrap_err();
+            (error.position, error.message)
+        };
+
+        assert_eq!(error("[1 2]"), (3, "expected `,` or `]`".to_string()));
+        assert_eq!(error("{\"a\" 1}"), (5, "expected `:`".to_string()));
+        assert_eq!(error("{1: 2}"), (1, "expected a string key".to_string()));
+        assert_eq!(error("(1, 2, 3)"), (5, "expected `)`".to_string()));
+        assert_eq!(error("[1, @]"), (4, "invalid value".to_string()));
+        assert_eq!(error("[1"), (2, "expected `,` or `]`".to_string()));
+        assert_eq!(
+            error("1 2"),
+            (2, "unexpected trailing characters".to_string())
+        );
+        assert_eq!(
+            error(""),
+            (0, "unexpected end of input in value".to_string())
+        );
+
+        let mut reader = EventReader::new("[@");
+        assert!(matches!(reader.next(), Some(Ok(Event::StartList))));
+        assert!(matches!(reader.next(), Some(Err(_))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn misplaced_events() {
+        let push_all = |events: Vec<Event>| {
+            let mut builder = Builder::new();
+            for event in events {
+                builder.push(event)?;
+            }
+            builder.finish()
+        };
+
+        assert_eq!(
+            push_all(vec![Event::StartList, Event::EndDict]),
+            Err(EventError::Unexpected("EndDict"))
+        );
+        assert_eq!(
+            push_all(vec![Event::StartDict, Event::None]),
+            Err(EventError::Unexpected("None"))
+        );
+        assert_eq!(
+            push_all(vec![
+                Event::StartDict,
+                Event::Key("a".into()),
+                Event::EndDict
+            ]),
+            Err(EventError::Unexpected("EndDict"))
+        );
+        assert_eq!(
+            push_all(vec![
+                Event::StartTuple,
+                Event::None,
+                Event::None,
+                Event::None
+            ]),
+            Err(EventError::Unexpected("None"))
+        );
+        assert_eq!(
+            push_all(vec![Event::StartTuple, Event::None, Event::EndTuple]),
+            Err(EventError::Unexpected("EndTuple"))
+        );
+        assert_eq!(
+            push_all(vec![Event::None, Event::None]),
+            Err(EventError::Unexpected("None"))
+        );
+        assert_eq!(
+            push_all(vec![Event::StartList]),
+            Err(EventError::Incomplete)
+        );
+
+        let writer = EventWriter::new(String::new());
+        assert_eq!(writer.finish(), Err(EventError::Incomplete));
+    }
+}
@@ -0,0 +1,766 @@
+use crate::path::Segment;
+use crate::DValue;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A compiled expression such as `price * quantity > 100 && status == "open"`.
+///
+/// Names are looked up as keys of the context value and can be followed by
+/// `.key` or `[expr]` to reach deeper; a name that doesn't exist evaluates
+/// to `none`. Literals are numbers, `"strings"`, `true`, `false`, `none`
+/// and `[lists]`.
+///
+/// Operators, from loosest to tightest binding:
+///
+/// | operator                         | meaning                                 |
+/// |----------------------------------|-----------------------------------------|
+/// | `\|\|`                           | logical or, short-circuiting            |
+/// | `&&`                             | logical and, short-circuiting           |
+/// | `==`, `!=`                       | equality of any two values              |
+/// | `<`, `<=`, `>`, `>=`             | numbers, or strings in byte order       |
+/// | `+`, `-`                         | as the `DValue` operators               |
+/// | `*`, `/`, `%`                    | numbers                                 |
+/// | `!`, unary `-`                   | logical not, negation                   |
+///
+/// Arithmetic on the wrong types yields `none`, as the `DValue` operators
+/// do, and ordering two values of different types is `false`. The logical
+/// operators treat `none`, `false`, `0`, NaN and empty strings, lists and
+/// dicts as false and always return a boolean.
+///
+/// Calls such as `len(items)` go to a `Functions` registry.
+///
+/// Parsing fails on expressions nested too deeply: more than 32 brackets
+/// open at once, or more than 128 levels of operators and accesses.
+///
+/// ```
+/// use datastruct::expr::Expr;
+/// use datastruct::DValue;
+///
+/// let order = "{\"price\": 25, \"quantity\": 5, \"status\": \"open\"}".parse::<DValue>().unwrap();
+/// let filter: Expr = "price * quantity > 100 && status == \"open\"".parse().unwrap();
+///
+/// assert_eq!(filter.eval(&order), Ok(DValue::Boolean(true)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Literal(DValue),
+    List(Vec<Node>),
+    Name(String),
+    Field(Box<Node>, String),
+    Index(Box<Node>, Box<Node>),
+    Call(String, Vec<Node>),
+    Not(Box<Node>),
+    Neg(Box<Node>),
+    Binary(Box<Node>, BinOp, Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprError {
+    /// The expression text is malformed.
+    Parse { position: usize, message: String },
+
+    /// No function with this name is registered.
+    UnknownFunction(String),
+
+    /// A function rejected its arguments.
+    Function { name: String, message: String },
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExprError::Parse { position, message } => {
+                write!(f, "invalid expression at {}: {}", position, message)
+            }
+            ExprError::UnknownFunction(name) => write!(f, "unknown function {}", name),
+            ExprError::Function { name, message } => write!(f, "{}: {}", name, message),
+        }
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+type Function = Arc<dyn Fn(&[DValue]) -> Result<DValue, String> + Send + Sync>;
+
+/// The functions an `Expr` can call.
+///
+/// `Functions::new` starts with the built-ins:
+///
+/// - `len(x)`: characters in a string, items in a list, entries in a dict
+/// - `lower(s)`, `upper(s)`
+/// - `contains(x, item)`: substring, list item or dict key
+/// - `starts_with(s, prefix)`, `ends_with(s, suffix)`
+/// - `abs(n)`, `floor(n)`, `ceil(n)`, `round(n)`
+/// - `min(a, b, ...)`, `max(a, b, ...)`: over numbers
+/// - `type(x)`: the `DValue::datatype` name
+///
+/// ```
+/// use datastruct::expr::{Expr, Functions};
+/// use datastruct::DValue;
+///
+/// let mut functions = Functions::new();
+/// functions.register("double", |args| match args {
+///     [DValue::Number(n)] => Ok(DValue::Number(n * 2.0)),
+///     _ => Err("expected one number".to_string()),
+/// });
+///
+/// let expr: Expr = "double(len(tags))".parse().unwrap();
+/// let doc = "{\"tags\": [\"a\", \"b\"]}".parse::<DValue>().unwrap();
+///
+/// assert_eq!(expr.eval_with(&doc, &functions), Ok(DValue::Number(4.0)));
+/// ```
+#[derive(Clone)]
+pub struct Functions {
+    map: HashMap<String, Function>,
+}
+
+impl Functions {
+    /// A registry holding the built-in functions.
+    pub fn new() -> Self {
+        let mut functions = Self::empty();
+
+        functions.register("len", |args| match args {
+            [DValue::String(s)] => Ok(number(s.chars().count())),
+            [DValue::List(list)] => Ok(number(list.len())),
+            [DValue::Dict(dict)] => Ok(number(dict.len())),
+            [other] => Err(format!("{} has no length", other.datatype())),
+            _ => Err(arity(1, args)),
+        });
+        functions.register("lower", |args| match args {
+            [DValue::String(s)] => Ok(DValue::String(s.to_lowercase())),
+            _ => Err("expected a string".to_string()),
+        });
+        functions.register("upper", |args| match args {
+            [DValue::String(s)] => Ok(DValue::String(s.to_uppercase())),
+            _ => Err("expected a string".to_string()),
+        });
+        functions.register("contains", |args| match args {
+            [DValue::String(s), DValue::String(needle)] => {
+                Ok(DValue::Boolean(s.contains(needle.as_str())))
+            }
+            [DValue::List(list), item] => Ok(DValue::Boolean(list.contains(item))),
+            [DValue::Dict(dict), DValue::String(key)] => {
+                Ok(DValue::Boolean(dict.contains_key(key)))
+            }
+            [_, _] => Ok(DValue::Boolean(false)),
+            _ => Err(arity(2, args)),
+        });
+        functions.register("starts_with", |args| match args {
+            [DValue::String(s), DValue::String(prefix)] => {
+                Ok(DValue::Boolean(s.starts_with(prefix.as_str())))
+            }
+            _ => Err("expected two strings".to_string()),
+        });
+        functions.register("ends_with", |args| match args {
+            [DValue::String(s), DValue::String(suffix)] => {
+                Ok(DValue::Boolean(s.ends_with(suffix.as_str())))
+            }
+            _ => Err("expected two strings".to_string()),
+        });
+        functions.register("abs", |args| unary_number(args, f64::abs));
+        functions.register("floor", |args| unary_number(args, f64::floor));
+        functions.register("ceil", |args| unary_number(args, f64::ceil));
+        functions.register("round", |args| unary_number(args, f64::round));
+        functions.register("min", |args| fold_numbers(args, f64::min));
+        functions.register("max", |args| fold_numbers(args, f64::max));
+        functions.register("type", |args| match args {
+            [value] => Ok(DValue::String(value.datatype())),
+            _ => Err(arity(1, args)),
+        });
+
+        functions
+    }
+
+    /// A registry with no functions at all.
+    pub fn empty() -> Self {
+        Self {
+            map: HashMap::new(),
+        }
+    }
+
+    /// Adds a function, replacing any existing one with the same name.
+    /// Returning `Err` fails the evaluation with `ExprError::Function`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        f: impl Fn(&[DValue]) -> Result<DValue, String> + Send + Sync + 'static,
+    ) {
+        self.map.insert(name.to_string(), Arc::new(f));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.map.contains_key(name)
+    }
+}
+
+impl Default for Functions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Functions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names: Vec<&String> = self.map.keys().collect();
+        names.sort();
+        f.debug_set().entries(names).finish()
+    }
+}
+
+fn number(n: usize) -> DValue {
+    DValue::Number(n as f64)
+}
+
+fn arity(expected: usize, args: &[DValue]) -> String {
+    format!("expected {} arguments, got {}", expected, args.len())
+}
+
+fn unary_number(args: &[DValue], f: fn(f64) -> f64) -> Result<DValue, String> {
+    match args {
+        [DValue::Number(n)] => Ok(DValue::Number(f(*n))),
+        _ => Err("expected a number".to_string()),
+    }
+}
+
+fn fold_numbers(args: &[DValue], f: fn(f64, f64) -> f64) -> Result<DValue, String> {
+    let numbers = args
+        .iter()
+        .map(|arg| arg.as_number().ok_or("expected numbers".to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    numbers
+        .into_iter()
+        .reduce(f)
+        .map(DValue::Number)
+        .ok_or_else(|| "expected at least one argument".to_string())
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Self, ExprError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            end: source.len(),
+            depth: 0,
+            brackets: 0,
+        };
+        let node = parser.or()?;
+
+        match parser.peek() {
+            None => Ok(Expr(node)),
+            Some(_) => Err(parser.error("unexpected token")),
+        }
+    }
+
+    /// Evaluates the expression against `context` with the built-in
+    /// functions.
+    pub fn eval(&self, context: &DValue) -> Result<DValue, ExprError> {
+        self.eval_with(context, &Functions::new())
+    }
+
+    pub fn eval_with(&self, context: &DValue, functions: &Functions) -> Result<DValue, ExprError> {
+        Eval { context, functions }.node(&self.0)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ExprError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expr::parse(s)
+    }
+}
+
+impl DValue {
+    /// Parses and evaluates `expr` with this value as its context. See
+    /// `Expr` for the syntax.
+    pub fn eval(&self, expr: &str) -> Result<DValue, ExprError> {
+        Expr::parse(expr)?.eval(self)
+    }
+}
+
+struct Eval<'a> {
+    context: &'a DValue,
+    functions: &'a Functions,
+}
+
+impl Eval<'_> {
+    fn node(&self, node: &Node) -> Result<DValue, ExprError> {
+        Ok(match node {
+            Node::Literal(value) => value.clone(),
+            Node::List(items) => DValue::List(
+                items
+                    .iter()
+                    .map(|item| self.node(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Node::Name(name) => child(self.context, &Segment::Key(name.clone())),
+            Node::Field(target, key) => child(&self.node(target)?, &Segment::Key(key.clone())),
+            Node::Index(target, index) => {
+                let target = self.node(target)?;
+                match self.node(index)? {
+                    DValue::String(key) => child(&target, &Segment::Key(key)),
+                    DValue::Number(n) if n >= 0.0 && n.fract() == 0.0 => {
+                        child(&target, &Segment::Index(n as usize))
+                    }
+                    _ => DValue::None,
+                }
+            }
+            Node::Call(name, args) => {
+                let f = self
+                    .functions
+                    .map
+                    .get(name)
+                    .ok_or_else(|| ExprError::UnknownFunction(name.clone()))?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.node(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                f(&args).map_err(|message| ExprError::Function {
+                    name: name.clone(),
+                    message,
+                })?
+            }
+            Node::Not(inner) => DValue::Boolean(!truthy(&self.node(inner)?)),
+            Node::Neg(inner) => match self.node(inner)? {
+                DValue::Number(n) => DValue::Number(-n),
+                _ => DValue::None,
+            },
+            Node::Binary(left, BinOp::And, right) => {
+                DValue::Boolean(truthy(&self.node(left)?) && truthy(&self.node(right)?))
+            }
+            Node::Binary(left, BinOp::Or, right) => {
+                DValue::Boolean(truthy(&self.node(left)?) || truthy(&self.node(right)?))
+            }
+            Node::Binary(left, op, right) => binary(&self.node(left)?, *op, &self.node(right)?),
+        })
+    }
+}
+
+fn child(value: &DValue, segment: &Segment) -> DValue {
+    value.child(segment).cloned().unwrap_or(DValue::None)
+}
+
+fn truthy(value: &DValue) -> bool {
+    match value {
+        DValue::None => false,
+        DValue::Boolean(b) => *b,
+        DValue::Number(n) => *n != 0.0 && !n.is_nan(),
+        DValue::String(s) => !s.is_empty(),
+        DValue::List(list) => !list.is_empty(),
+        DValue::Dict(dict) => !dict.is_empty(),
+        DValue::Tuple(_) | DValue::BinaryUtil(_) => true,
+    }
+}
+
+fn binary(left: &DValue, op: BinOp, right: &DValue) -> DValue {
+    let order = || match (left, right) {
+        (DValue::Number(a), DValue::Number(b)) => a.partial_cmp(b),
+        (DValue::String(a), DValue::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    match op {
+        BinOp::Eq => DValue::Boolean(left == right),
+        BinOp::Ne => DValue::Boolean(left != right),
+        BinOp::Lt => DValue::Boolean(order() == Some(Ordering::Less)),
+        BinOp::Le => DValue::Boolean(matches!(order(), Some(Ordering::Less | Ordering::Equal))),
+        BinOp::Gt => DValue::Boolean(order() == Some(Ordering::Greater)),
+        BinOp::Ge => DValue::Boolean(matches!(order(), Some(Ordering::Greater | Ordering::Equal))),
+        BinOp::Add => left + right,
+        BinOp::Sub => left - right,
+        BinOp::Mul => left * right,
+        BinOp::Div => left / right,
+        BinOp::Rem => match (left, right) {
+            (DValue::Number(a), DValue::Number(b)) => DValue::Number(a % b),
+            _ => DValue::None,
+        },
+        BinOp::And | BinOp::Or => unreachable!("logical operators short-circuit"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 20] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", ".", ",", "(", ")",
+    "[", "]",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ExprError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    loop {
+        rest = rest.trim_start();
+        let pos = source.len() - rest.len();
+        let Some(c) = rest.chars().next() else {
+            return Ok(tokens);
+        };
+
+        let (token, len) = if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..len]
+                .parse()
+                .map_err(|_| parse_error(pos, "invalid number"))?;
+            (Token::Number(number), len)
+        } else if c == '"' {
+            let (string, len) =
+                string(rest).ok_or_else(|| parse_error(pos, "unterminated string"))?;
+            (Token::String(string), len)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            (Token::Ident(rest[..len].to_string()), len)
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|s| rest.starts_with(**s))
+                .ok_or_else(|| parse_error(pos, "unexpected character"))?;
+            (Token::Symbol(symbol), symbol.len())
+        };
+
+        tokens.push((pos, token));
+        rest = &rest[len..];
+    }
+}
+
+/// Reads a double-quoted string with `\"`, `\\`, `\n` and `\t` escapes,
+/// returning it and the length of its source.
+fn string(source: &str) -> Option<(String, usize)> {
+    let mut out = String::new();
+    let mut chars = source.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, i + 1)),
+            '\\' => out.push(match chars.next()?.1 {
+                'n' => '\n',
+                't' => '\t',
+                other => other,
+            }),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// How deep an expression's tree may get, so that parsing, evaluating and
+/// dropping it can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// How many brackets may be open at once. Each one costs the parser about
+/// ten stack frames, so this is tighter than `MAX_DEPTH`.
+const MAX_BRACKETS: usize = 32;
+
+fn parse_error(position: usize, message: &str) -> ExprError {
+    ExprError::Parse {
+        position,
+        message: message.to_string(),
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+    depth: usize,
+    brackets: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn error(&self, message: &str) -> ExprError {
+        let position = self.tokens.get(self.pos).map_or(self.end, |(pos, _)| *pos);
+        parse_error(position, message)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Goes one level deeper into the tree, failing past `MAX_DEPTH`.
+    fn enter(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        match self.depth > MAX_DEPTH {
+            true => Err(self.error("expression nested too deeply")),
+            false => Ok(()),
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ExprError> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => Err(self.error(&format!("expected '{}'", symbol))),
+        }
+    }
+
+    /// Parses a left-associative chain of the operators in `ops`, with
+    /// operands parsed by `operand`.
+    fn chain(
+        &mut self,
+        ops: &[(&str, BinOp)],
+        operand: fn(&mut Self) -> Result<Node, ExprError>,
+    ) -> Result<Node, ExprError> {
+        let depth = self.depth;
+        let mut node = operand(self)?;
+
+        'outer: loop {
+            for (symbol, op) in ops {
+                if self.eat(symbol) {
+                    self.enter()?;
+                    node = Node::Binary(Box::new(node), *op, Box::new(operand(self)?));
+                    continue 'outer;
+                }
+            }
+            self.depth = depth;
+            return Ok(node);
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, ExprError> {
+        self.chain(&[("||", BinOp::Or)], Self::and)
+    }
+
+    /// Parses an expression inside brackets, failing past `MAX_BRACKETS`.
+    fn bracketed(&mut self) -> Result<Node, ExprError> {
+        self.brackets += 1;
+        if self.brackets > MAX_BRACKETS {
+            return Err(self.error("expression nested too deeply"));
+        }
+        let node = self.or()?;
+        self.brackets -= 1;
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, ExprError> {
+        self.chain(&[("&&", BinOp::And)], Self::equality)
+    }
+
+    fn equality(&mut self) -> Result<Node, ExprError> {
+        self.chain(&[("==", BinOp::Eq), ("!=", BinOp::Ne)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Node, ExprError> {
+        self.chain(
+            &[
+                ("<=", BinOp::Le),
+                (">=", BinOp::Ge),
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Result<Node, ExprError> {
+        self.chain(&[("+", BinOp::Add), ("-", BinOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Node, ExprError> {
+        self.chain(
+            &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Node, ExprError> {
+        self.enter()?;
+        let node = if self.eat("!") {
+            Node::Not(Box::new(self.unary()?))
+        } else if self.eat("-") {
+            Node::Neg(Box::new(self.unary()?))
+        } else {
+            self.postfix()?
+        };
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    fn postfix(&mut self) -> Result<Node, ExprError> {
+        let depth = self.depth;
+        let mut node = self.primary()?;
+
+        loop {
+            if self.eat(".") {
+                self.enter()?;
+                match self.tokens.get(self.pos) {
+                    Some((_, Token::Ident(key))) => {
+                        node = Node::Field(Box::new(node), key.clone());
+                        self.pos += 1;
+                    }
+                    _ => return Err(self.error("expected a key")),
+                }
+            } else if self.eat("[") {
+                self.enter()?;
+                node = Node::Index(Box::new(node), Box::new(self.bracketed()?));
+                self.expect("]")?;
+            } else {
+                self.depth = depth;
+                return Ok(node);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, ExprError> {
+        let Some((_, token)) = self.tokens.get(self.pos).cloned() else {
+            return Err(self.error("expected a value"));
+        };
+        self.pos += 1;
+
+        Ok(match token {
+            Token::Number(n) => Node::Literal(DValue::Number(n)),
+            Token::String(s) => Node::Literal(DValue::String(s)),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Node::Literal(DValue::Boolean(true)),
+                "false" => Node::Literal(DValue::Boolean(false)),
+                "none" => Node::Literal(DValue::None),
+                _ if self.eat("(") => Node::Call(name, self.list(")")?),
+                _ => Node::Name(name),
+            },
+            Token::Symbol("(") => {
+                let node = self.bracketed()?;
+                self.expect(")")?;
+                node
+            }
+            Token::Symbol("[") => Node::List(self.list("]")?),
+            Token::Symbol(_) => {
+                self.pos -= 1;
+                return Err(self.error("expected a value"));
+            }
+        })
+    }
+
+    /// Parses comma-separated expressions up to the closing `end`.
+    fn list(&mut self, end: &str) -> Result<Vec<Node>, ExprError> {
+        let mut items = Vec::new();
+
+        while !self.eat(end) {
+            if !items.is_empty() {
+                self.expect(",")?;
+            }
+            items.push(self.bracketed()?);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::expr::{Expr, ExprError};
+    use crate::DValue;
+
+    #[test]
+    fn operators() {
+        let doc = "{\"a\": 7, \"b\": 2, \"name\": \"Ada\", \"items\": [{\"id\": 1}, {\"id\": 2}], \"empty\": []}".parse::<DValue>().unwrap();
+        let cases = [
+            ("a + b * 3", DValue::Number(13.0)),
+            ("(a + b) * 3", DValue::Number(27.0)),
+            ("a % b - -1", DValue::Number(2.0)),
+            ("a > b && !(a < b) || missing", DValue::Boolean(true)),
+            ("empty || none", DValue::Boolean(false)),
+            ("!(0 / 0) && 0 / 0 != none", DValue::Boolean(true)),
+            ("name + \"!\" == \"Ada!\"", DValue::Boolean(true)),
+            ("name < \"B\"", DValue::Boolean(true)),
+            ("name < 1", DValue::Boolean(false)),
+            ("items[1].id", DValue::Number(2.0)),
+            ("items[b - 1][\"id\"]", DValue::Number(2.0)),
+            ("missing.deeper[0]", DValue::None),
+            ("name * 2", DValue::None),
+            ("[a, \"x\"]", "[7, \"x\"]".parse::<DValue>().unwrap()),
+        ];
+
+        for (expr, expected) in cases {
+            assert_eq!(doc.eval(expr), Ok(expected), "{}", expr);
+        }
+    }
+
+    #[test]
+    fn functions_and_errors() {
+        let doc = "{\"tags\": [\"x\", \"y\"], \"name\": \"Ada\"}"
+            .parse::<DValue>()
+            .unwrap();
+
+        assert_eq!(
+            doc.eval("len(tags) == 2 && contains(tags, \"y\") && lower(name) == \"ada\""),
+            Ok(DValue::Boolean(true))
+        );
+        assert_eq!(doc.eval("max(1, round(2.6), -4)"), Ok(DValue::Number(3.0)));
+        assert_eq!(
+            doc.eval("nope(1)"),
+            Err(ExprError::UnknownFunction("nope".to_string()))
+        );
+        assert!(matches!(
+            doc.eval("len(1)"),
+            Err(ExprError::Function { name, .. }) if name == "len"
+        ));
+        assert!(matches!(
+            Expr::parse("a = 1"),
+            Err(ExprError::Parse { position: 2, .. })
+        ));
+        assert!(matches!(
+            Expr::parse("(a + 1"),
+            Err(ExprError::Parse { position: 6, .. })
+        ));
+
+        let nested = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert!(matches!(
+            Expr::parse(&nested),
+            Err(ExprError::Parse { message, .. }) if message == "expression nested too deeply"
+        ));
+        for deep in [
+            "-".repeat(100_000) + "1",
+            format!("1{}", "+1".repeat(100_000)),
+            format!("a{}", ".b".repeat(100_000)),
+            "[".repeat(100_000),
+        ] {
+            assert!(Expr::parse(&deep).is_err());
+        }
+        let shallow = format!("1{}", "+1".repeat(120));
+        assert_eq!(doc.eval(&shallow), Ok(DValue::Number(121.0)));
+        let negated = "-".repeat(120) + "1";
+        assert_eq!(doc.eval(&negated), Ok(DValue::Number(1.0)));
+        let nested = format!("{}1{}", "[(".repeat(16), ")]".repeat(16));
+        assert!(Expr::parse(&nested).is_ok());
+    }
+}
@@ -0,0 +1,211 @@
+//! C ABI for embedding the parser in non-Rust programs.
+//!
+//! Values are passed around as opaque `DValue` pointers. Every pointer
+//! returned by `datastruct_parse` or `datastruct_get_path` must be released
+//! with `datastruct_free`. Every string returned by `datastruct_to_json`
+//! must be released with `datastruct_string_free`. When a function returns
+//! null, `datastruct_last_error` says why.
+//!
+//! The declarations are in `include/datastruct.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/datastruct.h`. To build
+//! a library to link against:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --lib --crate-type staticlib  # libdatastruct.a
+//! cargo rustc --release --features ffi --lib --crate-type cdylib     # libdatastruct.so
+//! cc main.c -Iinclude target/release/libdatastruct.a -lm -lpthread -ldl
+//! ```
+
+use crate::DValue;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::fmt::Display;
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Parses `input` in the text format and returns a new handle, or null if
+/// `input` is null, not valid UTF-8 or not valid text.
+///
+/// # Safety
+///
+/// `input` must be null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn datastruct_parse(input: *const c_char) -> *mut DValue {
+    let input = match str_arg(input) {
+        Ok(input) => input,
+        Err(error) => return fail(error),
+    };
+
+    match input.parse::<DValue>() {
+        Ok(value) => succeed(value),
+        Err(error) => fail(error),
+    }
+}
+
+/// Looks up `path` (e.g. `a.b[0]`) and returns a new handle holding a copy
+/// of the value found, or null if it doesn't exist.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle, and `path` must be null or point
+/// to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn datastruct_get_path(
+    value: *const DValue,
+    path: *const c_char,
+) -> *mut DValue {
+    let Some(value) = value.as_ref() else {
+        return fail("null handle");
+    };
+    let path = match str_arg(path) {
+        Ok(path) => path,
+        Err(error) => return fail(error),
+    };
+
+    match value.get_path(path) {
+        Some(found) => succeed(found.clone()),
+        None => fail(format_args!("nothing at path `{}`", path)),
+    }
+}
+
+/// Returns the type name of the value, such as `"Dict"`, as a static
+/// string that must not be freed. A null handle reports `"None"`.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn datastruct_type(value: *const DValue) -> *const c_char {
+    let name = match value.as_ref() {
+        None | Some(DValue::None) => c"None",
+        Some(DValue::String(_)) => c"String",
+        Some(DValue::Number(_)) => c"Number",
+        Some(DValue::Boolean(_)) => c"Boolean",
+        Some(DValue::List(_)) => c"List",
+        Some(DValue::Dict(_)) => c"Dict",
+        Some(DValue::Tuple(_)) => c"Tuple",
+        Some(DValue::BinaryUtil(_)) => c"Binary",
+    };
+    name.as_ptr()
+}
+
+/// Serializes the value as JSON. The result must be released with
+/// `datastruct_string_free`; null is returned for a null handle.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn datastruct_to_json(value: *const DValue) -> *mut c_char {
+    let Some(value) = value.as_ref() else {
+        return fail("null handle");
+    };
+
+    set_last_error(None);
+    // JSON escapes control characters, so the output never contains NUL.
+    CString::new(value.to_json())
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Releases a handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `value` must be null or a live handle, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn datastruct_free(value: *mut DValue) {
+    if !value.is_null() {
+        drop(Box::from_raw(value));
+    }
+}
+
+/// Releases a string returned by `datastruct_to_json`. Passing null is a
+/// no-op.
+///
+/// # Safety
+///
+/// `s` must be null or a string from `datastruct_to_json` that hasn't been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn datastruct_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Describes why the last call on this thread that can fail returned null,
+/// or returns null if it succeeded. The string belongs to the library and
+/// stays valid until the next such call on the same thread; it must not be
+/// freed.
+#[no_mangle]
+pub extern "C" fn datastruct_last_error() -> *const c_char {
+    LAST_ERROR.with_borrow(|error| error.as_ref().map_or(ptr::null(), |error| error.as_ptr()))
+}
+
+fn set_last_error(error: Option<CString>) {
+    LAST_ERROR.set(error);
+}
+
+fn succeed(value: DValue) -> *mut DValue {
+    set_last_error(None);
+    Box::into_raw(Box::new(value))
+}
+
+fn fail<T>(error: impl Display) -> *mut T {
+    // Messages quote the input, which can't hold a NUL since it came in as a
+    // C string.
+    set_last_error(CString::new(error.to_string()).ok());
+    ptr::null_mut()
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Result<&'a str, &'static str> {
+    if s.is_null() {
+        return Err("null string");
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| "string is not valid UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ffi::*;
+    use std::ffi::CStr;
+    use std::ptr;
+
+    #[test]
+    fn handle_lifecycle() {
+        unsafe {
+            let doc = datastruct_parse(c"{\"a\": [1, \"x\"]}".as_ptr());
+            assert_eq!(CStr::from_ptr(datastruct_type(doc)), c"Dict");
+
+            let item = datastruct_get_path(doc, c"a[1]".as_ptr());
+            let json = datastruct_to_json(item);
+            assert_eq!(CStr::from_ptr(json), c"{\"String\":\"x\"}");
+
+            assert!(datastruct_last_error().is_null());
+
+            assert!(datastruct_get_path(doc, c"a[5]".as_ptr()).is_null());
+            assert_eq!(
+                CStr::from_ptr(datastruct_last_error()),
+                c"nothing at path `a[5]`"
+            );
+            assert!(datastruct_parse(ptr::null()).is_null());
+            assert_eq!(CStr::from_ptr(datastruct_last_error()), c"null string");
+            assert!(datastruct_parse(c"[1,".as_ptr()).is_null());
+            assert!(!datastruct_last_error().is_null());
+            assert!(datastruct_to_json(ptr::null()).is_null());
+            assert_eq!(CStr::from_ptr(datastruct_last_error()), c"null handle");
+            assert_eq!(CStr::from_ptr(datastruct_type(ptr::null())), c"None");
+
+            datastruct_string_free(json);
+            datastruct_free(item);
+            datastruct_free(doc);
+            datastruct_free(ptr::null_mut());
+        }
+    }
+}
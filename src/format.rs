@@ -0,0 +1,545 @@
+//! Configurable text output.
+//!
+//! `Formatter` writes the same text format as `to_string`, laid out according
+//! to its options. `Formatter::new()` is what `{:#}` uses.
+//!
+//! ```
+//! use datastruct::format::Formatter;
+//! use datastruct::DValue;
+//!
+//! let value = "{\"b\": [1, 2], \"a\": {\"c\": \"é\"}}".parse::<DValue>().unwrap();
+//! let formatter = Formatter::new()
+//!     .indent(4)
+//!     .sort_keys(true)
+//!     .max_inline_width(24)
+//!     .escape_non_ascii(true);
+//!
+//! assert_eq!(
+//!     value.format(&formatter),
+//!     "{\n    \"a\": {\"c\": \"\\u00e9\"},\n    \"b\": [1, 2]\n}"
+//! );
+//! ```
+
+use crate::DValue;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+/// Layout options for text output, set with the builder methods below.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatter {
+    indent: usize,
+    sort_keys: bool,
+    max_inline_width: usize,
+    trailing_commas: bool,
+    escape_non_ascii: bool,
+    color: Color,
+    precision: Precision,
+    scientific_threshold: Option<u32>,
+}
+
+/// How `Formatter` rounds numbers. Rounded numbers are still written in
+/// their shortest form, so `Fixed(2)` writes `1.5` rather than `1.50` and
+/// whole numbers stay integers. Only `Formatter` output is rounded: JSON,
+/// `to_string` and the other writers always keep numbers exact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// No rounding: the shortest text that reads back as the same number.
+    #[default]
+    Shortest,
+
+    /// At most this many digits after the decimal point. More than 1074,
+    /// the most any `f64` has, round nothing.
+    Fixed(usize),
+
+    /// This many significant digits, at least one. More than 17, which is
+    /// enough to tell any two `f64`s apart, round nothing.
+    Significant(usize),
+}
+
+/// When `Formatter` highlights its output with ANSI colors: keys in blue,
+/// strings in green, numbers in cyan, booleans and `none` in yellow, and
+/// binary literals in magenta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Never,
+    Always,
+
+    /// Only when stdout is a terminal and the `NO_COLOR` environment variable
+    /// is unset. Without the `std` feature this never colors.
+    Auto,
+}
+
+const KEY: &str = "\x1b[34m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const LITERAL: &str = "\x1b[33m";
+const BINARY: &str = "\x1b[35m";
+const RESET: &str = "\x1b[0m";
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Color::Never => false,
+            Color::Always => true,
+            #[cfg(feature = "std")]
+            Color::Auto => {
+                use std::io::IsTerminal;
+                std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+            }
+            #[cfg(not(feature = "std"))]
+            Color::Auto => false,
+        }
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Formatter {
+    /// Two-space indentation, every non-empty list and dict spread over
+    /// several lines, and keys in the dict's own order.
+    pub fn new() -> Self {
+        Self {
+            indent: 2,
+            sort_keys: false,
+            max_inline_width: 0,
+            trailing_commas: false,
+            escape_non_ascii: false,
+            color: Color::Never,
+            precision: Precision::Shortest,
+            scientific_threshold: None,
+        }
+    }
+
+    /// Indents nested lines by `width` spaces. With `0`, everything is
+    /// written on one line.
+    pub fn indent(mut self, width: usize) -> Self {
+        self.indent = width;
+        self
+    }
+
+    /// Writes dict entries in key order.
+    pub fn sort_keys(mut self, sort: bool) -> Self {
+        self.sort_keys = sort;
+        self
+    }
+
+    /// Keeps a list or dict on one line when that line, including its
+    /// indentation and key, is at most `width` chars wide.
+    pub fn max_inline_width(mut self, width: usize) -> Self {
+        self.max_inline_width = width;
+        self
+    }
+
+    /// Adds a comma after the last item of lists and dicts spread over
+    /// several lines.
+    pub fn trailing_commas(mut self, trailing: bool) -> Self {
+        self.trailing_commas = trailing;
+        self
+    }
+
+    /// Writes chars outside ASCII in strings and keys as `\uXXXX` escapes.
+    pub fn escape_non_ascii(mut self, escape: bool) -> Self {
+        self.escape_non_ascii = escape;
+        self
+    }
+
+    /// Rounds numbers before writing them.
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    /// Writes numbers of `10^exponent` and above, or below `10^-exponent`,
+    /// in scientific notation such as `1.5e21`. Without this, numbers are
+    /// always written out in full.
+    pub fn scientific_threshold(mut self, exponent: u32) -> Self {
+        self.scientific_threshold = Some(exponent);
+        self
+    }
+
+    /// Highlights the output with ANSI colors.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Writes `value` into `out` according to the options.
+    pub fn write<W: Write>(&self, value: &DValue, out: &mut W) -> fmt::Result {
+        if self.color == Color::Auto {
+            let color = match Color::Auto.enabled() {
+                true => Color::Always,
+                false => Color::Never,
+            };
+            return self.clone().color(color).write(value, out);
+        }
+        self.write_value(value, out, 0, 0, self.indent == 0)
+    }
+
+    /// `column` is where the value starts on its line, used for
+    /// `max_inline_width`. Inside an `inline` value, everything stays on one
+    /// line.
+    fn write_value<W: Write>(
+        &self,
+        value: &DValue,
+        out: &mut W,
+        depth: usize,
+        column: usize,
+        inline: bool,
+    ) -> fmt::Result {
+        let multiline = !inline
+            && match value {
+                DValue::List(list) => !list.is_empty() && !self.fits(value, column),
+                DValue::Dict(dict) => !dict.is_empty() && !self.fits(value, column),
+                _ => false,
+            };
+
+        match value {
+            DValue::String(s) => self.paint(out, STRING, |out| self.write_str(s, out)),
+            DValue::List(list) if multiline => {
+                out.write_char('[')?;
+                for (i, item) in list.iter().enumerate() {
+                    self.item_start(out, i, depth + 1)?;
+                    self.write_value(item, out, depth + 1, (depth + 1) * self.indent, false)?;
+                }
+                self.close(out, depth, ']')
+            }
+            DValue::List(list) => {
+                out.write_char('[')?;
+                for (i, item) in list.iter().enumerate() {
+                    if i > 0 {
+                        out.write_str(", ")?;
+                    }
+                    self.write_value(item, out, depth, 0, true)?;
+                }
+                out.write_char(']')
+            }
+            DValue::Dict(dict) => {
+                let mut entries: Vec<(&String, &DValue)> = dict.iter().collect();
+                if self.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                }
+
+                out.write_char('{')?;
+                for (i, (key, item)) in entries.into_iter().enumerate() {
+                    if multiline {
+                        self.item_start(out, i, depth + 1)?;
+                    } else if i > 0 {
+                        out.write_str(", ")?;
+                    }
+
+                    let mut key_width = Width(0);
+                    self.write_str(key, &mut key_width)?;
+                    self.paint(out, KEY, |out| self.write_str(key, out))?;
+                    out.write_str(": ")?;
+
+                    let column = (depth + 1) * self.indent + key_width.0 + 2;
+                    self.write_value(item, out, depth + 1, column, !multiline)?;
+                }
+                match multiline {
+                    true => self.close(out, depth, '}'),
+                    false => out.write_char('}'),
+                }
+            }
+            DValue::Tuple(pair) => {
+                out.write_char('(')?;
+                self.write_value(&pair.0, out, depth, column + 1, inline)?;
+                out.write_str(", ")?;
+                self.write_value(&pair.1, out, depth, column + 1, inline)?;
+                out.write_char(')')
+            }
+            DValue::Number(n) => self.paint(out, NUMBER, |out| self.write_number(*n, out)),
+            DValue::BinaryUtil(_) => self.paint(out, BINARY, |out| value.write_to(out)),
+            _ => self.paint(out, LITERAL, |out| value.write_to(out)),
+        }
+    }
+
+    fn paint<W: Write>(
+        &self,
+        out: &mut W,
+        style: &str,
+        body: impl FnOnce(&mut W) -> fmt::Result,
+    ) -> fmt::Result {
+        if self.color != Color::Always {
+            return body(out);
+        }
+        out.write_str(style)?;
+        body(out)?;
+        out.write_str(RESET)
+    }
+
+    /// Whether `value` fits on one line starting at `column`.
+    fn fits(&self, value: &DValue, column: usize) -> bool {
+        let budget = match self.max_inline_width.checked_sub(column) {
+            Some(budget) => budget,
+            None => return false,
+        };
+
+        // Give up as soon as the line gets too long instead of rendering it
+        // all, and leave out colors, which take up no room on screen.
+        let mut out = Limited { budget };
+        let plain = self.clone().color(Color::Never);
+        plain.write_value(value, &mut out, 0, 0, true).is_ok()
+    }
+
+    fn item_start<W: Write>(&self, out: &mut W, index: usize, depth: usize) -> fmt::Result {
+        if index > 0 {
+            out.write_char(',')?;
+        }
+        self.newline(out, depth)
+    }
+
+    fn close<W: Write>(&self, out: &mut W, depth: usize, bracket: char) -> fmt::Result {
+        if self.trailing_commas {
+            out.write_char(',')?;
+        }
+        self.newline(out, depth)?;
+        out.write_char(bracket)
+    }
+
+    fn newline<W: Write>(&self, out: &mut W, depth: usize) -> fmt::Result {
+        out.write_char('\n')?;
+        (0..depth * self.indent).try_for_each(|_| out.write_char(' '))
+    }
+
+    fn write_str<W: Write>(&self, s: &str, out: &mut W) -> fmt::Result {
+        write_quoted(out, s, self.escape_non_ascii)
+    }
+
+    fn write_number<W: Write>(&self, n: f64, out: &mut W) -> fmt::Result {
+        if !n.is_finite() {
+            return write!(out, "{}", n);
+        }
+
+        // Round through the standard formatting, which rounds correctly, and
+        // read the result back so it is written in its shortest form below.
+        // Precisions past what an `f64` holds are clamped, as `format!` panics
+        // on ones above `u16::MAX`.
+        let rounded = match self.precision {
+            Precision::Shortest => None,
+            Precision::Fixed(digits) => Some(alloc::format!("{:.*}", digits.min(1074), n)),
+            Precision::Significant(digits) => {
+                Some(alloc::format!("{:.*e}", digits.clamp(1, 17) - 1, n))
+            }
+        };
+        let n = match rounded.and_then(|text| text.parse::<f64>().ok()) {
+            // Don't turn small negative numbers into `-0`.
+            Some(0.0) => 0.0,
+            Some(rounded) => rounded,
+            None => n,
+        };
+
+        match self.scientific_threshold {
+            Some(exponent) if n != 0.0 => {
+                let bound = (0..exponent).fold(1.0, |bound, _| bound * 10.0);
+                if n.abs() >= bound || n.abs() < 1.0 / bound {
+                    write!(out, "{:e}", n)
+                } else {
+                    write!(out, "{}", n)
+                }
+            }
+            _ => write!(out, "{}", n),
+        }
+    }
+}
+
+/// Writes `s` as a quoted string that the parser reads back unchanged,
+/// escaping quotes, backslashes and control characters.
+pub(crate) fn write_quoted<W: Write>(out: &mut W, s: &str, escape_non_ascii: bool) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\u{8}' => out.write_str("\\b")?,
+            '\u{c}' => out.write_str("\\f")?,
+            c if c.is_control() || (escape_non_ascii && !c.is_ascii()) => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Counts the chars written to it.
+struct Width(usize);
+
+impl Write for Width {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+/// Fails once more than `budget` chars have been written to it.
+struct Limited {
+    budget: usize,
+}
+
+impl Write for Limited {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.budget = self
+            .budget
+            .checked_sub(s.chars().count())
+            .ok_or(fmt::Error)?;
+        Ok(())
+    }
+}
+
+impl DValue {
+    /// Formats the value with the layout options of `formatter`.
+    pub fn format(&self, formatter: &Formatter) -> String {
+        let mut out = String::new();
+        // Writing into a String can't fail.
+        let _ = formatter.write(self, &mut out);
+        out
+    }
+
+    /// Formats the value on one line, highlighted with ANSI colors when
+    /// stdout is a terminal; see `Color::Auto`.
+    pub fn to_string_colored(&self) -> String {
+        self.format(&Formatter::new().indent(0).color(Color::Auto))
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use super::{Color, Formatter, Precision};
+    use crate::DValue;
+
+    #[test]
+    fn default_layout() {
+        let value = "[{\"a\": []}, (1, [2])]".parse::<DValue>().unwrap();
+
+        assert_eq!(
+            value.format(&Formatter::new()),
+            "[\n  {\n    \"a\": []\n  },\n  (1, [\n    2\n  ])\n]"
+        );
+        assert_eq!(value.format(&Formatter::new()), format!("{:#}", value));
+    }
+
+    #[test]
+    fn inline_width() {
+        let value = "{\"short\": [1, 2], \"long\": [100000, 200000]}"
+            .parse::<DValue>()
+            .unwrap();
+        let formatter = Formatter::new().sort_keys(true).max_inline_width(18);
+
+        // `  "short": [1, 2]` is 17 chars, `  "long": [100000, 200000]` is 27.
+        assert_eq!(
+            value.format(&formatter),
+            "{\n  \"long\": [\n    100000,\n    200000\n  ],\n  \"short\": [1, 2]\n}"
+        );
+        assert_eq!(
+            value.format(&formatter.clone().max_inline_width(80)),
+            "{\"long\": [100000, 200000], \"short\": [1, 2]}"
+        );
+    }
+
+    #[test]
+    fn trailing_commas_and_compact() {
+        let value = "[1, [2]]".parse::<DValue>().unwrap();
+
+        assert_eq!(
+            value.format(&Formatter::new().trailing_commas(true)),
+            "[\n  1,\n  [\n    2,\n  ],\n]"
+        );
+        assert_eq!(value.format(&Formatter::new().indent(0)), "[1, [2]]");
+        assert_eq!(
+            value
+                .format(&Formatter::new().trailing_commas(true))
+                .parse::<DValue>()
+                .unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn colors() {
+        let value = "{\"k\": [\"s\", 1, true, binary!(aGk=)]}"
+            .parse::<DValue>()
+            .unwrap();
+        let colored = value.format(&Formatter::new().indent(0).color(Color::Always));
+
+        assert_eq!(
+            colored,
+            "{\x1b[34m\"k\"\x1b[0m: [\x1b[32m\"s\"\x1b[0m, \x1b[36m1\x1b[0m, \
+             \x1b[33mtrue\x1b[0m, \x1b[35mbinary!(aGk=)\x1b[0m]}"
+        );
+
+        // Colors don't count towards the inline width.
+        let formatter = Formatter::new().max_inline_width(6).color(Color::Always);
+        assert!(!"[1, 2]"
+            .parse::<DValue>()
+            .unwrap()
+            .format(&formatter)
+            .contains('\n'));
+    }
+
+    #[test]
+    fn number_formats() {
+        let value = "[51.50735091234567, -0.12775829, 42, 1234567.5, 0.00001234]"
+            .parse::<DValue>()
+            .unwrap();
+        let format = |formatter: Formatter| value.format(&formatter.indent(0));
+
+        assert_eq!(
+            format(Formatter::new()),
+            "[51.50735091234567, -0.12775829, 42, 1234567.5, 0.00001234]"
+        );
+        assert_eq!(
+            format(Formatter::new().precision(Precision::Fixed(4))),
+            "[51.5074, -0.1278, 42, 1234567.5, 0]"
+        );
+        assert_eq!(
+            format(Formatter::new().precision(Precision::Significant(3))),
+            "[51.5, -0.128, 42, 1230000, 0.0000123]"
+        );
+        assert_eq!(
+            format(Formatter::new().scientific_threshold(4)),
+            "[51.50735091234567, -0.12775829, 42, 1.2345675e6, 1.234e-5]"
+        );
+        assert_eq!(
+            format(
+                Formatter::new()
+                    .precision(Precision::Significant(2))
+                    .scientific_threshold(3)
+            ),
+            "[52, -0.13, 42, 1.2e6, 1.2e-5]"
+        );
+
+        // Whatever the options, the output reads back.
+        let formatter = Formatter::new().scientific_threshold(1);
+        assert_eq!(value.format(&formatter).parse::<DValue>().unwrap(), value);
+        assert_eq!(
+            DValue::Number(-0.001).format(&Formatter::new().precision(Precision::Fixed(1))),
+            "0"
+        );
+        for precision in [Precision::Fixed(100_000), Precision::Significant(100_000)] {
+            let formatter = Formatter::new().indent(0).precision(precision);
+            assert_eq!(value.format(&formatter), format(Formatter::new()));
+        }
+    }
+
+    #[test]
+    fn escape_non_ascii() {
+        let value = DValue::String("é😀".to_string());
+
+        assert_eq!(
+            value.format(&Formatter::new().escape_non_ascii(true)),
+            "\"\\u00e9\\ud83d\\ude00\""
+        );
+        assert_eq!(value.format(&Formatter::new()), "\"é😀\"");
+    }
+}
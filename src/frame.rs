@@ -0,0 +1,67 @@
+//! Checksummed record framing shared by the on-disk formats.
+//!
+//! A frame is `[len: u32][crc32: u32][body]` with integers in little
+//! endian. A frame cut short by a crash fails to parse, which lets readers
+//! tell a torn tail from a complete record.
+
+use std::io;
+
+/// Appends `body` to `out` as a frame. Fails without writing anything if
+/// `body` is too long for its length to fit in a `u32`.
+pub(crate) fn write(out: &mut Vec<u8>, body: &[u8]) -> io::Result<()> {
+    out.extend(len_prefix(body.len())?);
+    out.extend(crc32fast::hash(body).to_le_bytes());
+    out.extend(body);
+    Ok(())
+}
+
+/// Returns the body of the first frame in `data` and the frame's full
+/// length, or `None` if it is incomplete or fails its checksum.
+pub(crate) fn next(data: &[u8]) -> Option<(&[u8], usize)> {
+    let len = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?) as usize;
+    let crc = u32::from_le_bytes(data.get(4..8)?.try_into().ok()?);
+    let body = data.get(8..8 + len)?;
+
+    (crc32fast::hash(body) == crc).then_some((body, len + 8))
+}
+
+/// Splits a `[len: u32][bytes]` field off the front of `data`.
+pub(crate) fn split_field(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?) as usize;
+    data[4..].split_at_checked(len)
+}
+
+/// Appends `bytes` to `out` as a `[len: u32][bytes]` field, failing as
+/// `write` does.
+pub(crate) fn write_field(out: &mut Vec<u8>, bytes: &[u8]) -> io::Result<()> {
+    out.extend(len_prefix(bytes.len())?);
+    out.extend(bytes);
+    Ok(())
+}
+
+fn len_prefix(len: usize) -> io::Result<[u8; 4]> {
+    u32::try_from(len).map(u32::to_le_bytes).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("record of {} bytes is over the 4 GiB limit", len),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::frame::*;
+
+    #[test]
+    fn length_limit() {
+        assert_eq!(len_prefix(5).unwrap(), [5, 0, 0, 0]);
+        assert_eq!(len_prefix(u32::MAX as usize).unwrap(), [0xff; 4]);
+
+        let err = len_prefix(u32::MAX as usize + 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "record of 4294967296 bytes is over the 4 GiB limit"
+        );
+    }
+}
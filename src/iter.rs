@@ -0,0 +1,104 @@
+use crate::{DValue, Map};
+use std::iter::FromIterator;
+use std::{slice, vec};
+
+/// Consuming iterator over a `DValue`, returned by `DValue::into_iter`.
+///
+/// Lists yield their elements and dicts yield `DValue::Tuple` pairs of a
+/// `DValue::String` key and its value. Other variants yield nothing.
+pub struct IntoIter {
+    inner: Inner,
+}
+
+enum Inner {
+    List(vec::IntoIter<DValue>),
+    Dict(<Map<String, DValue> as IntoIterator>::IntoIter),
+    Empty,
+}
+
+impl Iterator for IntoIter {
+    type Item = DValue;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::List(list) => list.next(),
+            Inner::Dict(dict) => dict
+                .next()
+                .map(|(k, v)| DValue::Tuple((Box::new(DValue::String(k)), Box::new(v)))),
+            Inner::Empty => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            Inner::List(list) => list.size_hint(),
+            Inner::Dict(dict) => dict.size_hint(),
+            Inner::Empty => (0, Some(0)),
+        }
+    }
+}
+
+impl IntoIterator for DValue {
+    type Item = DValue;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inner = match self {
+            DValue::List(list) => Inner::List(list.into_iter()),
+            DValue::Dict(dict) => Inner::Dict(dict.into_iter()),
+            _ => Inner::Empty,
+        };
+        IntoIter { inner }
+    }
+}
+
+impl<'a> IntoIterator for &'a DValue {
+    type Item = &'a DValue;
+    type IntoIter = slice::Iter<'a, DValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            DValue::List(list) => list.iter(),
+            _ => [].iter(),
+        }
+    }
+}
+
+impl FromIterator<DValue> for DValue {
+    fn from_iter<I: IntoIterator<Item = DValue>>(iter: I) -> Self {
+        DValue::List(iter.into_iter().collect())
+    }
+}
+
+impl FromIterator<(String, DValue)> for DValue {
+    fn from_iter<I: IntoIterator<Item = (String, DValue)>>(iter: I) -> Self {
+        DValue::Dict(iter.into_iter().collect::<Map<String, DValue>>())
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::DValue;
+
+    #[test]
+    fn collect_list_and_dict() {
+        let list: DValue = "[1, 2, 3]"
+            .parse::<DValue>()
+            .unwrap()
+            .into_iter()
+            .filter(|v| v.weight() > 1.0)
+            .collect();
+        assert_eq!(list, "[2, 3]".parse::<DValue>().unwrap());
+
+        let dict: DValue = "{\"a\": 1}"
+            .parse::<DValue>()
+            .unwrap()
+            .into_iter()
+            .filter_map(|pair| pair.as_tuple())
+            .map(|(k, v)| (format!("{}!", k.as_string().unwrap()), *v))
+            .collect();
+        assert_eq!(dict, "{\"a!\": 1}".parse::<DValue>().unwrap());
+
+        assert_eq!(DValue::Boolean(true).into_iter().count(), 0);
+    }
+}
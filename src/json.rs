@@ -0,0 +1,359 @@
+use crate::{DValue, NonFinite, NonFiniteError};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Where the writers below put their bytes: a `Vec<u8>`, or with the `std`
+/// feature any `io::Write` wrapped in `Io`.
+pub(crate) trait Write {
+    type Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Self::Error>;
+}
+
+impl Write for Vec<u8> {
+    type Error = Infallible;
+
+    fn write_all(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub(crate) struct Io<W>(pub(crate) W);
+
+#[cfg(feature = "std")]
+impl<W: io::Write> Write for Io<W> {
+    type Error = io::Error;
+
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(bytes)
+    }
+}
+
+/// Escapes for the bytes JSON doesn't allow raw in strings; `u` means `\u00XX`.
+const ESCAPE: [u8; 32] = [
+    b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'b', b't', b'n', b'u', b'f', b'r', b'u', b'u',
+    b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u', b'u',
+];
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+/// 2^53: every whole number up to this size is exact as an `f64`.
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Writes `value` as externally tagged JSON, the layout the serde derive
+/// uses and `try_from_json` reads, without going through a `Serializer`.
+/// Numbers are formatted here rather than by serde_json, so the text can
+/// differ: whole numbers are written as integers. With `sort_keys`, dict
+/// entries are written in key order.
+pub(crate) fn write_value<W: Write>(
+    out: &mut W,
+    value: &DValue,
+    non_finite: NonFinite,
+    sort_keys: bool,
+) -> Result<(), W::Error> {
+    match value {
+        DValue::None => out.write_all(b"\"None\""),
+        DValue::String(s) => {
+            out.write_all(b"{\"String\":")?;
+            write_str(out, s)?;
+            out.write_all(b"}")
+        }
+        DValue::Number(n) => {
+            out.write_all(b"{\"Number\":")?;
+            write_number(out, *n, non_finite)?;
+            out.write_all(b"}")
+        }
+        DValue::Boolean(b) => out.write_all(if *b {
+            b"{\"Boolean\":true}"
+        } else {
+            b"{\"Boolean\":false}"
+        }),
+        DValue::List(items) => {
+            out.write_all(b"{\"List\":[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write_value(out, item, non_finite, sort_keys)?;
+            }
+            out.write_all(b"]}")
+        }
+        DValue::Dict(entries) => {
+            out.write_all(b"{\"Dict\":{")?;
+            if sort_keys {
+                let mut entries: Vec<_> = entries.iter().collect();
+                entries.sort_unstable_by_key(|(k, _)| *k);
+                write_entries(out, entries.into_iter(), non_finite, sort_keys)?;
+            } else {
+                write_entries(out, entries.iter(), non_finite, sort_keys)?;
+            }
+            out.write_all(b"}}")
+        }
+        DValue::Tuple(pair) => {
+            out.write_all(b"{\"Tuple\":[")?;
+            write_value(out, &pair.0, non_finite, sort_keys)?;
+            out.write_all(b",")?;
+            write_value(out, &pair.1, non_finite, sort_keys)?;
+            out.write_all(b"]}")
+        }
+        DValue::BinaryUtil(bin) => {
+            out.write_all(b"{\"BinaryUtil\":{\"data\":[")?;
+            let mut buf = itoa::Buffer::new();
+            for (i, byte) in bin.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                out.write_all(buf.format(*byte).as_bytes())?;
+            }
+            out.write_all(b"]}}")
+        }
+    }
+}
+
+fn write_entries<'a, W: Write>(
+    out: &mut W,
+    entries: impl Iterator<Item = (&'a String, &'a DValue)>,
+    non_finite: NonFinite,
+    sort_keys: bool,
+) -> Result<(), W::Error> {
+    for (i, (k, v)) in entries.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        write_str(out, k)?;
+        out.write_all(b":")?;
+        write_value(out, v, non_finite, sort_keys)?;
+    }
+    Ok(())
+}
+
+/// Writes `value` as plain JSON: `null` for `None`, arrays for lists and
+/// tuples, and base64 strings for binary data.
+#[cfg(feature = "std")]
+pub(crate) fn write_plain<W: Write>(
+    out: &mut W,
+    value: &DValue,
+    non_finite: NonFinite,
+) -> Result<(), W::Error> {
+    match value {
+        DValue::None => out.write_all(b"null"),
+        DValue::String(s) => write_str(out, s),
+        DValue::Number(n) => write_number(out, *n, non_finite),
+        DValue::Boolean(b) => out.write_all(if *b { b"true" } else { b"false" }),
+        DValue::List(items) => {
+            out.write_all(b"[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write_plain(out, item, non_finite)?;
+            }
+            out.write_all(b"]")
+        }
+        DValue::Dict(entries) => {
+            out.write_all(b"{")?;
+            for (i, (k, v)) in entries.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b",")?;
+                }
+                write_str(out, k)?;
+                out.write_all(b":")?;
+                write_plain(out, v, non_finite)?;
+            }
+            out.write_all(b"}")
+        }
+        DValue::Tuple(pair) => {
+            out.write_all(b"[")?;
+            write_plain(out, &pair.0, non_finite)?;
+            out.write_all(b",")?;
+            write_plain(out, &pair.1, non_finite)?;
+            out.write_all(b"]")
+        }
+        DValue::BinaryUtil(bin) => write_str(out, &bin.to_b64()),
+    }
+}
+
+/// Fails on the first non-finite number in `value` if `non_finite` is
+/// `NonFinite::Error`, so the writers never have to.
+pub(crate) fn check_finite(value: &DValue, non_finite: NonFinite) -> Result<(), NonFiniteError> {
+    if non_finite != NonFinite::Error {
+        return Ok(());
+    }
+
+    for (path, value) in value.walk() {
+        if let DValue::Number(n) = value {
+            if !n.is_finite() {
+                return Err(NonFiniteError { path, value: *n });
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_number<W: Write>(out: &mut W, n: f64, non_finite: NonFinite) -> Result<(), W::Error> {
+    let n = match non_finite {
+        _ if n.is_finite() => n,
+        // `check_finite` has already turned these away for `NonFinite::Error`.
+        NonFinite::Null | NonFinite::Error => return out.write_all(b"null"),
+        NonFinite::String if n.is_nan() => return out.write_all(b"\"NaN\""),
+        NonFinite::String if n > 0.0 => return out.write_all(b"\"Infinity\""),
+        NonFinite::String => return out.write_all(b"\"-Infinity\""),
+        NonFinite::Clamp if n.is_nan() => 0.0,
+        NonFinite::Clamp => n.clamp(f64::MIN, f64::MAX),
+    };
+
+    // Whole numbers are written as `42` rather than `42.0`, as the text
+    // format does, so integers in a document survive a round trip as they
+    // were written. `-0.0` keeps its fraction to keep its sign.
+    if n % 1.0 == 0.0 && n.abs() <= MAX_EXACT_INTEGER && !(n == 0.0 && n.is_sign_negative()) {
+        return out.write_all(itoa::Buffer::new().format(n as i64).as_bytes());
+    }
+    out.write_all(ryu::Buffer::new().format_finite(n).as_bytes())
+}
+
+pub(crate) fn write_str<W: Write>(out: &mut W, s: &str) -> Result<(), W::Error> {
+    let bytes = s.as_bytes();
+    out.write_all(b"\"")?;
+
+    // Copy runs of plain bytes in one go and only stop for escapes.
+    let mut start = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let escape = match byte {
+            b'"' => b'"',
+            b'\\' => b'\\',
+            0..=0x1f => ESCAPE[byte as usize],
+            _ => continue,
+        };
+
+        out.write_all(&bytes[start..i])?;
+        if escape == b'u' {
+            out.write_all(&[
+                b'\\',
+                b'u',
+                b'0',
+                b'0',
+                HEX[(byte >> 4) as usize],
+                HEX[(byte & 0xf) as usize],
+            ])?;
+        } else {
+            out.write_all(&[b'\\', escape])?;
+        }
+        start = i + 1;
+    }
+
+    out.write_all(&bytes[start..])?;
+    out.write_all(b"\"")
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::binary_util::Binary;
+    use crate::{DValue, NonFinite};
+
+    #[test]
+    fn matches_serde_except_whole_numbers() {
+        let value = DValue::List(vec![
+            DValue::None,
+            DValue::String("quote \" slash \\ tab \t bell \u{7} é".to_string()),
+            DValue::Number(0.1),
+            // serde_json versions disagree on exponents (`3e300` vs `3e+300`),
+            // so stick to numbers printed without one, and serde writes whole
+            // numbers with a fraction.
+            DValue::Number(-3.5),
+            DValue::Number(f64::NAN),
+            DValue::Boolean(false),
+            DValue::Tuple((
+                Box::new(DValue::BinaryUtil(Binary::new(vec![0, 127, 255]))),
+                Box::new("{\"k\\n\": 1.5}".parse::<DValue>().unwrap()),
+            )),
+        ]);
+
+        assert_eq!(value.to_json(), serde_json::to_string(&value).unwrap());
+    }
+
+    #[test]
+    fn canonical_sorts_nested_keys() {
+        let a = "[{\"z\": 1, \"y\": {\"b\": 2, \"a\": 3}}, ({\"q\": 1, \"p\": 2}, none)]"
+            .parse::<DValue>()
+            .unwrap();
+        let b = "[{\"y\": {\"a\": 3, \"b\": 2}, \"z\": 1}, ({\"p\": 2, \"q\": 1}, none)]"
+            .parse::<DValue>()
+            .unwrap();
+
+        let json = a.to_canonical_json();
+        assert_eq!(json, b.to_canonical_json());
+        assert!(json.find("\"a\"").unwrap() < json.find("\"b\"").unwrap());
+        assert!(json.find("\"p\"").unwrap() < json.find("\"q\"").unwrap());
+        assert_eq!(DValue::try_from_json(&json), Ok(a));
+    }
+
+    #[test]
+    fn whole_numbers_as_integers() {
+        let json = |n: f64| DValue::Number(n).to_json();
+
+        assert_eq!(json(42.0), "{\"Number\":42}");
+        assert_eq!(json(-7.0), "{\"Number\":-7}");
+        assert_eq!(
+            json(9_007_199_254_740_992.0),
+            "{\"Number\":9007199254740992}"
+        );
+        assert_eq!(json(1e300), "{\"Number\":1e300}");
+        assert_eq!(json(-0.0), "{\"Number\":-0.0}");
+        assert_eq!(json(0.5), "{\"Number\":0.5}");
+
+        let value = "[1, 2.5, -3]".parse::<DValue>().unwrap();
+        assert_eq!(DValue::try_from_json(&value.to_json()), Ok(value));
+    }
+
+    #[test]
+    fn non_finite_policies() {
+        let value = DValue::List(vec![
+            DValue::Number(1.5),
+            DValue::Tuple((
+                Box::new(DValue::Number(f64::NEG_INFINITY)),
+                Box::new(DValue::Number(f64::NAN)),
+            )),
+        ]);
+        let pair = |policy| {
+            let json = value.to_json_with(policy).unwrap();
+            let start = json.find("{\"Tuple\":").unwrap();
+            json[start..json.len() - 2].to_string()
+        };
+
+        assert_eq!(
+            value.to_json_with(NonFinite::Null).unwrap(),
+            value.to_json()
+        );
+        assert_eq!(
+            pair(NonFinite::Null),
+            "{\"Tuple\":[{\"Number\":null},{\"Number\":null}]}"
+        );
+        assert_eq!(
+            pair(NonFinite::String),
+            "{\"Tuple\":[{\"Number\":\"-Infinity\"},{\"Number\":\"NaN\"}]}"
+        );
+        assert_eq!(
+            pair(NonFinite::Clamp),
+            "{\"Tuple\":[{\"Number\":-1.7976931348623157e308},{\"Number\":0}]}"
+        );
+
+        let err = value.to_json_with(NonFinite::Error).unwrap_err();
+        assert_eq!(err.path.to_string(), "[1][0]");
+        assert_eq!(err.to_string(), "cannot write -inf as JSON at [1][0]");
+
+        #[cfg(feature = "std")]
+        {
+            let mut out = Vec::new();
+            assert!(value
+                .write_json_to_with(&mut out, NonFinite::Error)
+                .is_err());
+            assert!(out.is_empty());
+        }
+        assert!(DValue::Number(2.0).to_json_with(NonFinite::Error).is_ok());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::binary_util::sync_parent;
+use crate::frame;
+use crate::json::check_finite;
+use crate::{DValue, NonFinite};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+
+const OP_SET: u8 = 1;
+const OP_DELETE: u8 = 2;
+
+/// A persistent `String -> DValue` map backed by a single log file.
+///
+/// Every `set` and `delete` appends a record to the file, and opening the
+/// file replays the records into an in-memory index, so reads never touch
+/// the disk. Each record carries a length and a CRC32 checksum: a record
+/// torn by a crash fails the check and is cut off on the next `open`,
+/// leaving every write before it intact.
+///
+/// Writes reach the operating system immediately; `flush` additionally
+/// waits until they are on disk. Overwritten and deleted entries keep
+/// taking space until `compact` rewrites the file.
+///
+/// ```
+/// use datastruct::kv::KvStore;
+/// use datastruct::DValue;
+///
+/// # let dir = tempfile::tempdir().unwrap();
+/// # let path = dir.path().join("store.log");
+/// let mut kv = KvStore::open(&path).unwrap();
+/// kv.set("user:1", "{\"name\": \"ada\"}".parse::<DValue>().unwrap()).unwrap();
+/// kv.set("user:2", "{\"name\": \"bob\"}".parse::<DValue>().unwrap()).unwrap();
+/// kv.flush().unwrap();
+/// drop(kv);
+///
+/// let kv = KvStore::open(&path).unwrap();
+/// assert_eq!(kv.scan_prefix("user:").count(), 2);
+/// ```
+#[derive(Debug)]
+pub struct KvStore {
+    path: PathBuf,
+    file: File,
+    index: BTreeMap<String, DValue>,
+    /// Records in the file that no longer hold a live entry.
+    stale: usize,
+}
+
+impl KvStore {
+    /// Opens the store at `path`, creating the file if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+
+        let mut index = BTreeMap::new();
+        let mut stale = 0;
+        let mut offset = 0;
+
+        while let Some((record, len)) = frame::next(&data[offset..]) {
+            stale += match decode(record)? {
+                (key, Some(value)) => index.insert(key, value).is_some() as usize,
+                (key, None) => 1 + index.remove(&key).is_some() as usize,
+            };
+            offset += len;
+        }
+
+        if offset < data.len() {
+            file.set_len(offset as u64)?;
+            file.sync_all()?;
+        }
+
+        Ok(Self {
+            path,
+            file,
+            index,
+            stale,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&DValue> {
+        self.index.get(key)
+    }
+
+    /// Stores `value` under `key`. Fails without writing anything if the
+    /// value holds a NaN or infinite number, which JSON can't represent, or
+    /// if the record is over 4 GiB.
+    pub fn set(&mut self, key: &str, value: DValue) -> anyhow::Result<()> {
+        self.append(&encode(key, Some(&value))?)?;
+        if self.index.insert(key.to_string(), value).is_some() {
+            self.stale += 1;
+        }
+        Ok(())
+    }
+
+    /// Removes `key` and returns its value. Nothing is written if the key
+    /// is absent.
+    pub fn delete(&mut self, key: &str) -> anyhow::Result<Option<DValue>> {
+        if !self.index.contains_key(key) {
+            return Ok(None);
+        }
+        self.append(&encode(key, None)?)?;
+        self.stale += 2;
+        Ok(self.index.remove(key))
+    }
+
+    /// Iterates in key order over the entries whose key starts with `prefix`.
+    pub fn scan_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a DValue)> + 'a {
+        self.index
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(move |(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Waits until every write so far is on disk.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        Ok(self.file.sync_data()?)
+    }
+
+    /// Rewrites the file with one record per live entry. The new file is
+    /// written next to the old one and renamed over it, so a crash during
+    /// compaction leaves either the old file or the new one in place.
+    pub fn compact(&mut self) -> anyhow::Result<()> {
+        let mut data = Vec::new();
+        for (key, value) in &self.index {
+            data.extend(encode(key, Some(value))?);
+        }
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".compact");
+        let temp = PathBuf::from(temp);
+
+        let result = File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(&data)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temp, &self.path));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result?;
+        sync_parent(&self.path)?;
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.stale = 0;
+        Ok(())
+    }
+
+    /// The number of records in the file that `compact` would drop.
+    pub fn stale_records(&self) -> usize {
+        self.stale
+    }
+
+    fn append(&mut self, record: &[u8]) -> anyhow::Result<()> {
+        self.file
+            .write_all(record)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+}
+
+/// Encodes a record as a frame holding `[op: u8][key len: u32][key][value]`,
+/// with the value as JSON.
+fn encode(key: &str, value: Option<&DValue>) -> anyhow::Result<Vec<u8>> {
+    let mut body = vec![if value.is_some() { OP_SET } else { OP_DELETE }];
+    frame::write_field(&mut body, key.as_bytes())?;
+    if let Some(value) = value {
+        check_finite(value, NonFinite::Error)?;
+        serde_json::to_writer(&mut body, value)?;
+    }
+
+    let mut record = Vec::with_capacity(body.len() + 8);
+    frame::write(&mut record, &body)?;
+    Ok(record)
+}
+
+fn decode(body: &[u8]) -> anyhow::Result<(String, Option<DValue>)> {
+    let (&op, rest) = body.split_first().context("Empty record")?;
+    let (key, value) = frame::split_field(rest).context("Truncated record")?;
+    let key = String::from_utf8(key.to_vec())?;
+
+    match op {
+        OP_SET => Ok((key, Some(serde_json::from_slice(value)?))),
+        OP_DELETE => Ok((key, None)),
+        _ => anyhow::bail!("Unknown record type {}", op),
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::kv::KvStore;
+    use crate::DValue;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn survives_torn_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.log");
+
+        let mut kv = KvStore::open(&path).unwrap();
+        kv.set("a", DValue::Number(1.0)).unwrap();
+        kv.set("b", "[1, binary!(aGk=)]".parse::<DValue>().unwrap())
+            .unwrap();
+        kv.set("a", DValue::Number(2.0)).unwrap();
+        assert_eq!(
+            kv.delete("b").unwrap(),
+            Some("[1, binary!(aGk=)]".parse::<DValue>().unwrap())
+        );
+        assert_eq!(kv.delete("b").unwrap(), None);
+        assert_eq!(kv.stale_records(), 3);
+        drop(kv);
+
+        // Simulate a crash halfway through appending a record.
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&[20, 0, 0, 0, 1, 2]).unwrap();
+        drop(file);
+
+        let mut kv = KvStore::open(&path).unwrap();
+        assert_eq!(kv.get("a"), Some(&DValue::Number(2.0)));
+        assert_eq!(kv.get("b"), None);
+        kv.set("c", DValue::None).unwrap();
+        kv.compact().unwrap();
+        kv.set("d", DValue::Boolean(true)).unwrap();
+        drop(kv);
+
+        let kv = KvStore::open(&path).unwrap();
+        assert_eq!(kv.stale_records(), 0);
+        assert_eq!(
+            kv.scan_prefix("").map(|(k, _)| k).collect::<Vec<_>>(),
+            vec!["a", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn refuses_non_finite_numbers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("store.log");
+
+        let mut kv = KvStore::open(&path).unwrap();
+        kv.set("a", DValue::Number(1.0)).unwrap();
+        for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(kv.set("n", DValue::Number(n)).is_err());
+            assert!(kv
+                .set("n", DValue::List(vec![DValue::None, DValue::Number(n)]))
+                .is_err());
+        }
+        assert_eq!(kv.get("n"), None);
+        drop(kv);
+
+        let kv = KvStore::open(&path).unwrap();
+        assert_eq!(kv.len(), 1);
+        assert_eq!(kv.get("a"), Some(&DValue::Number(1.0)));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::path::{IntoPath, Segment};
+use crate::{DValue, ValueParser};
+use std::borrow::Cow;
+
+/// A document in the text format that is only parsed where it is accessed.
+///
+/// Navigating with `get`, `index` or `get_path` scans over the raw text of
+/// sibling values without building them, and `materialize` parses just the
+/// selected span. For large documents where only a few keys are read, this
+/// avoids building the rest of the tree.
+///
+/// ```
+/// use datastruct::lazy::LazyDValue;
+/// use datastruct::DValue;
+///
+/// let doc = LazyDValue::new("{\"meta\": {\"id\": 7}, \"rows\": [[1, 2], [3, 4]]}");
+///
+/// assert_eq!(doc.get_path("rows[1]").unwrap().raw(), "[3, 4]");
+/// assert_eq!(doc.get_path("meta.id").unwrap().materialize(), DValue::Number(7.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LazyDValue<'a> {
+    raw: &'a str,
+}
+
+impl<'a> LazyDValue<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { raw: input.trim() }
+    }
+
+    /// The unparsed text of this value.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// Parses this value, returning `DValue::None` if it is malformed.
+    pub fn materialize(&self) -> DValue {
+        match ValueParser::parse(self.raw) {
+            Ok(("", value)) => value,
+            _ => DValue::None,
+        }
+    }
+
+    /// Returns the contents of a string value with escapes decoded.
+    ///
+    /// Strings without escapes are borrowed straight from the input; only
+    /// those containing a backslash escape are copied.
+    ///
+    /// ```
+    /// use datastruct::lazy::LazyDValue;
+    /// use std::borrow::Cow;
+    ///
+    /// let doc = LazyDValue::new("[\"plain\", \"tab\\there\"]");
+    ///
+    /// assert!(matches!(doc.index(0).unwrap().as_str(), Some(Cow::Borrowed("plain"))));
+    /// assert_eq!(doc.index(1).unwrap().as_str().unwrap(), "tab\there");
+    /// ```
+    pub fn as_str(&self) -> Option<Cow<'a, str>> {
+        if skip_string(self.raw)? != self.raw.len() {
+            return None;
+        }
+
+        ValueParser::unescape(&self.raw[1..self.raw.len() - 1])
+    }
+
+    /// Returns the value stored under `key` if this is a dict. Keys are
+    /// compared with their escapes decoded, and a key that appears more than
+    /// once gives its last value, as when the dict is parsed.
+    pub fn get(&self, key: &str) -> Option<LazyDValue<'a>> {
+        let mut rest = self.raw.strip_prefix('{')?;
+        let mut last = None;
+
+        loop {
+            rest = rest.trim_start();
+            if rest.starts_with('}') {
+                return last;
+            }
+
+            let key_len = skip_string(rest)?;
+            let found = ValueParser::unescape(&rest[1..key_len - 1])?;
+
+            rest = rest[key_len..].trim_start().strip_prefix(':')?.trim_start();
+            let value_len = skip_value(rest)?;
+
+            if found == key {
+                last = Some(Self::new(&rest[..value_len]));
+            }
+            rest = &rest[value_len..];
+            match next_element(rest) {
+                Some(next) => rest = next,
+                None if rest.trim_start().starts_with('}') => return last,
+                None => return None,
+            }
+        }
+    }
+
+    /// Returns the element at `index` if this is a list, or `0`/`1` for a tuple.
+    pub fn index(&self, index: usize) -> Option<LazyDValue<'a>> {
+        let mut rest = self
+            .raw
+            .strip_prefix('[')
+            .or_else(|| self.raw.strip_prefix('('))?;
+
+        for i in 0.. {
+            rest = rest.trim_start();
+            if rest.starts_with(']') || rest.starts_with(')') {
+                return None;
+            }
+
+            let value_len = skip_value(rest)?;
+            if i == index {
+                return Some(Self::new(&rest[..value_len]));
+            }
+            rest = next_element(&rest[value_len..])?;
+        }
+        None
+    }
+
+    /// Follows `path` through dicts, lists and tuples.
+    pub fn get_path<P: IntoPath>(&self, path: P) -> Option<LazyDValue<'a>> {
+        let path = path.into_path().ok()?;
+
+        path.segments()
+            .iter()
+            .try_fold(*self, |value, segment| match segment {
+                Segment::Key(key) => value.get(key),
+                Segment::Index(index) => value.index(*index),
+                Segment::Wildcard => None,
+            })
+    }
+}
+
+/// Moves past the `,` that separates elements, or returns `None` at the end.
+fn next_element(rest: &str) -> Option<&str> {
+    rest.trim_start().strip_prefix(',')
+}
+
+/// Returns the length of the quoted string at the start of `s`, quotes included.
+fn skip_string(s: &str) -> Option<usize> {
+    let mut chars = s.char_indices();
+    if chars.next()?.1 != '"' {
+        return None;
+    }
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next()?;
+            }
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the length of the value at the start of `s` without parsing it.
+fn skip_value(s: &str) -> Option<usize> {
+    if s.starts_with('"') {
+        return skip_string(s);
+    }
+
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < s.len() {
+        let c = s[i..].chars().next()?;
+
+        match c {
+            '"' => {
+                i += skip_string(&s[i..])?;
+                continue;
+            }
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' if depth == 0 => break,
+            ']' | '}' | ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            ',' if depth == 0 => break,
+            c if c.is_whitespace() && depth == 0 => break,
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+
+    if depth == 0 && i > 0 {
+        Some(i)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lazy::LazyDValue;
+    use crate::DValue;
+
+    #[test]
+    fn navigate_without_parsing_siblings() {
+        let doc = LazyDValue::new(
+            "{ \"skip\": {\"a\": [\"]\", \"\\\"}\"]}, \"bin\": binary!(aGk=), \"pair\": (1, \"x\"), \"n\": -1.5e3 }",
+        );
+
+        assert_eq!(doc.get("n").unwrap().materialize(), DValue::Number(-1500.0));
+        assert_eq!(doc.get_path("pair[1]").unwrap().raw(), "\"x\"");
+        assert_eq!(doc.get_path("skip.a[1]").unwrap().raw(), "\"\\\"}\"");
+        assert_eq!(doc.get("bin").unwrap().materialize().datatype(), "Binary");
+        assert!(doc.get("missing").is_none());
+        assert!(doc.get_path("pair[2]").is_none());
+        assert_eq!(doc.materialize().as_dict().map(|d| d.len()), Some(4));
+    }
+
+    #[test]
+    fn borrowed_strings() {
+        let doc = LazyDValue::new("{\"a\": \"\\u00e9\\\"\\/\", \"b\": 1, \"c\": \"\\q\"}");
+
+        assert_eq!(doc.get("a").unwrap().as_str().unwrap(), "é\"/");
+        assert_eq!(doc.get("b").unwrap().as_str(), None);
+        assert_eq!(doc.get("c").unwrap().as_str(), None);
+    }
+
+    #[test]
+    fn keys_match_the_parser() {
+        let text = "{\"a\\\"b\": 1, \"d\": 2, \"\\u00e9\": 3, \"d\": 4}";
+        let doc = LazyDValue::new(text);
+        let parsed = text.parse::<DValue>().unwrap();
+
+        for key in ["a\"b", "d", "é", "missing"] {
+            assert_eq!(
+                doc.get(key).map(|value| value.materialize()).as_ref(),
+                parsed.as_dict().unwrap().get(key),
+                "{}",
+                key
+            );
+        }
+        assert_eq!(doc.get("d").unwrap().raw(), "4");
+    }
+}
@@ -1,24 +1,159 @@
+//! Without the default `std` feature the crate is `no_std` and only needs
+//! `alloc`. That keeps `DValue`, `Binary`, the text parser, JSON and paths;
+//! file IO and the remaining modules need `std`.
+//!
+//! The `parser` (text format, via nom), `json` (via serde_json) and `base64`
+//! (decoding binary data) features are on by default and can be turned off
+//! independently. The `DValue` model, its text output and `Binary` are always
+//! available.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod aggregate;
+#[cfg(feature = "arena")]
+pub mod arena;
 pub mod binary_util;
+#[cfg(feature = "std")]
+pub mod cell;
+#[cfg(feature = "columnar")]
+pub mod columnar;
+#[cfg(feature = "std")]
+pub mod compression;
+pub mod convert;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+pub mod de;
+#[cfg(all(feature = "std", feature = "checksum"))]
+pub mod delta;
+#[cfg(feature = "dynamodb")]
+pub mod dynamodb;
+#[cfg(feature = "std")]
+pub mod env;
+#[cfg(all(feature = "std", feature = "parser", feature = "checksum"))]
+pub mod envelope;
+pub mod events;
+#[cfg(feature = "std")]
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+#[cfg(all(feature = "std", feature = "json", feature = "checksum"))]
+mod frame;
+#[cfg(feature = "std")]
+pub mod iter;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "kv")]
+pub mod kv;
+#[cfg(all(feature = "std", feature = "parser"))]
+pub mod lazy;
+#[cfg(feature = "lua")]
+pub mod lua;
+mod merge;
+#[cfg(feature = "std")]
+pub mod migrate;
+mod multi;
+pub mod ops;
+#[cfg(all(feature = "std", feature = "parser"))]
+pub mod options;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod path;
+#[cfg(feature = "persistent")]
+pub mod persistent;
+#[cfg(feature = "polars")]
+pub mod polars;
+#[cfg(feature = "sqlx-postgres")]
+pub mod postgres;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(any(feature = "rand", feature = "arbitrary", feature = "proptest"))]
+pub mod random;
+#[cfg(feature = "parser")]
+mod recover;
+#[cfg(feature = "rhai")]
+pub mod rhai;
+#[cfg(feature = "std")]
+pub mod schema;
+#[cfg(feature = "std")]
+pub mod shared;
+#[cfg(feature = "crypto")]
+pub mod sign;
+#[cfg(feature = "std")]
+pub mod sized;
+pub mod sorted;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod store;
+mod strings;
+#[cfg(any(feature = "columnar", feature = "polars"))]
+mod table;
+#[cfg(all(feature = "std", feature = "json"))]
+pub mod template;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+#[cfg(all(feature = "std", feature = "json", feature = "checksum"))]
+pub mod wal;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod watch;
+#[cfg(feature = "std")]
+pub mod writer;
 
+#[cfg(feature = "parser")]
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "parser")]
 use base64::{engine::general_purpose as base64_engine, Engine as _};
 use binary_util::Binary;
+use core::cmp::{Ord, Ordering, PartialEq, PartialOrd};
+use core::fmt;
+use core::mem;
 use serde::{Deserialize, Serialize};
-use std::cmp::{Ord, Ordering, PartialEq, PartialOrd};
-use std::collections::HashMap;
-use std::string::ToString;
+#[cfg(feature = "checksum")]
+use sha2::{Digest, Sha256};
+#[cfg(all(feature = "std", feature = "json"))]
+use std::io;
 
+#[cfg(feature = "parser")]
 use nom::{
     branch::alt,
     bytes::complete::{escaped, tag, tag_no_case, take_till1, take_while_m_n},
-    character::complete::multispace0,
-    combinator::{map, peek, value as n_value},
-    error::context,
-    multi::separated_list0,
+    character::complete::{digit1, hex_digit1, multispace0, one_of},
+    combinator::{cut, map, map_opt, opt, peek, recognize, value as n_value},
+    error::{context, ContextError, ErrorKind},
+    multi::{many0, separated_list1},
     number::complete::double,
-    sequence::{delimited, preceded, separated_pair},
+    sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
     IResult,
 };
 
+/// The map behind `DValue::Dict`: `hashbrown::HashMap`, whichever features
+/// are enabled. `sorted::SortedDict` keeps entries in key order instead.
+pub type Map<K, V> = hashbrown::HashMap<K, V>;
+
+/// Estimates the bytes `dict` allocates for its entries. The map is a
+/// SwissTable: a power-of-two number of buckets kept at most 7/8 full, plus
+/// a control byte per bucket and a trailing group of 16.
+fn table_usage(dict: &Map<String, DValue>) -> usize {
+    let capacity = dict.capacity();
+    if capacity == 0 {
+        return 0;
+    }
+    let buckets = if capacity < 8 {
+        (capacity + 1).next_power_of_two()
+    } else {
+        (capacity * 8 / 7).next_power_of_two()
+    };
+    buckets * (mem::size_of::<(String, DValue)>() + 1) + 16
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DValue {
     /// None
@@ -64,9 +199,9 @@ pub enum DValue {
     ///
     /// ```
     /// use datastruct::DValue;
-    /// DValue::Dict(std::collections::HashMap::new());
+    /// DValue::Dict(datastruct::Map::new());
     /// ```
-    Dict(HashMap<String, DValue>),
+    Dict(Map<String, DValue>),
 
     /// Tuple
     ///
@@ -99,32 +234,23 @@ pub enum DValue {
     BinaryUtil(Binary),
 }
 
-impl ToString for DValue {
-    fn to_string(&self) -> String {
-        match self {
-            DValue::None => "none".to_string(),
-            DValue::String(str) => format!("\"{}\"", str),
-            DValue::Number(num) => num.to_string(),
-            DValue::Boolean(bool) => match bool {
-                true => "true".to_string(),
-                false => "false".to_string(),
-            },
-            DValue::List(list) => {
-                let elements: Vec<String> = list.iter().map(|v| v.to_string()).collect();
-                format!("[{}]", elements.join(","))
-            }
-            DValue::Dict(dict) => {
-                let entries: Vec<String> = dict
-                    .iter()
-                    .map(|(k, v)| format!("\"{}\":{}", k, v.to_string()))
-                    .collect();
-                format!("{{{}}}", entries.join(","))
-            }
-
-            DValue::Tuple(v) => {
-                format!("({}, {})", v.0.to_string(), v.1.to_string())
-            }
-            DValue::BinaryUtil(val) => val.to_string(),
+/// Formats the value in the text format read by `str::parse`. The
+/// alternate form, `{:#}`, spreads lists and dicts over indented lines.
+///
+/// ```
+/// use datastruct::DValue;
+///
+/// let value = "{\"a\": [1, (true, \"x\")]}".parse::<DValue>().unwrap();
+///
+/// assert_eq!(format!("{}", value), "{\"a\":[1,(true, \"x\")]}");
+/// assert_eq!(format!("{:#}", value), "{\n  \"a\": [\n    1,\n    (true, \"x\")\n  ]\n}");
+/// ```
+impl fmt::Display for DValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            format::Formatter::new().write(self, f)
+        } else {
+            self.write_to(f)
         }
     }
 }
@@ -145,25 +271,186 @@ impl PartialOrd for DValue {
 
 impl PartialEq for DValue {
     fn eq(&self, other: &Self) -> bool {
-        self.to_string() == other.to_string()
+        match (self, other) {
+            (DValue::BinaryUtil(a), DValue::BinaryUtil(b)) => a == b,
+            _ => self.to_canonical_string() == other.to_canonical_string(),
+        }
     }
 }
 
 impl Eq for DValue {}
 
+/// Parses the text format written by `to_string`. Text wrapped as
+/// `b:<base64>:` is decoded first, and error positions then refer to the
+/// decoded text. Besides what `to_string` writes, numbers can be written in
+/// hex, as `0xFF`, and with `_` between digits, as `1_000_000`.
+///
+/// ```
+/// use datastruct::DValue;
+///
+/// let value: DValue = "{\"a\": [1, true], \"max\": 1_000_000, \"mask\": 0xFF_FF}".parse().unwrap();
+/// assert_eq!(value.get_path("a[1]"), Some(&DValue::Boolean(true)));
+/// assert_eq!(value.get_path("max"), Some(&DValue::Number(1e6)));
+/// assert_eq!(value.get_path("mask"), Some(&DValue::Number(65535.0)));
+///
+/// let err = "[1, 2".parse::<DValue>().unwrap_err();
+/// assert_eq!(err.position, 5);
+/// assert_eq!(err.to_string(), "invalid value at 5: unexpected end of input in list");
+/// ```
+#[cfg(feature = "parser")]
+impl core::str::FromStr for DValue {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let data = DValue::unwrap_b64(s)?;
+        let error = |rest: &str, message: String| ParseError {
+            position: data.len() - rest.len(),
+            message,
+        };
+
+        match ValueParser::parse(&data) {
+            Ok(("", value)) => Ok(value),
+            Ok((rest, _)) => Err(error(rest, "unexpected trailing characters".to_string())),
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                let context = e.context.unwrap_or("value");
+                Err(match e.input {
+                    "" => error("", alloc::format!("unexpected end of input in {}", context)),
+                    rest => error(rest, alloc::format!("invalid {}", context)),
+                })
+            }
+            Err(nom::Err::Incomplete(_)) => Err(error("", "unexpected end of input".to_string())),
+        }
+    }
+}
+
+/// Returned when text can't be parsed as a `DValue`.
+#[cfg(feature = "parser")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The byte offset at which the text stopped making sense.
+    pub position: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "parser")]
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid value at {}: {}", self.position, self.message)
+    }
+}
+
+#[cfg(feature = "parser")]
+impl core::error::Error for ParseError {}
+
+/// Returned by `DValue::try_from_json` when the input isn't JSON in the
+/// shape `to_json` writes.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonError {
+    /// The 1-based line of the error, or 0 if it isn't tied to a position.
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for JsonError {
+    fn from(e: serde_json::Error) -> Self {
+        let message = e.to_string();
+        // serde_json appends the position, which has fields of its own here.
+        let position = alloc::format!(" at line {} column {}", e.line(), e.column());
+        JsonError {
+            line: e.line(),
+            column: e.column(),
+            message: message
+                .strip_suffix(&position)
+                .unwrap_or(&message)
+                .to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "invalid JSON: {}", self.message),
+            line => write!(
+                f,
+                "invalid JSON at line {} column {}: {}",
+                line, self.column, self.message
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl core::error::Error for JsonError {}
+
+/// How JSON output writes NaN and infinite numbers, which JSON has no form
+/// for. `to_json` uses `Null`.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinite {
+    /// Fail with `NonFiniteError` before writing anything.
+    Error,
+
+    /// Write `null`.
+    #[default]
+    Null,
+
+    /// Write the strings `"NaN"`, `"Infinity"` and `"-Infinity"`. These
+    /// aren't read back as numbers by `try_from_json`.
+    String,
+
+    /// Write infinities as `f64::MAX` or `f64::MIN`, and NaN as `0`.
+    Clamp,
+}
+
+/// Returned by JSON output using `NonFinite::Error` for the first
+/// non-finite number found, in `walk` order.
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NonFiniteError {
+    pub path: path::Path,
+    pub value: f64,
+}
+
+#[cfg(feature = "json")]
+impl fmt::Display for NonFiniteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot write {} as JSON", self.value)?;
+        if !self.path.is_root() {
+            write!(f, " at {}", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json")]
+impl core::error::Error for NonFiniteError {}
+
+/// Feeds text written to it into a hash or MAC, so the canonical encoding
+/// can be hashed without building it.
+#[cfg(any(feature = "checksum", feature = "crypto"))]
+pub(crate) struct DigestWriter<D>(pub(crate) D);
+
+#[cfg(any(feature = "checksum", feature = "crypto"))]
+impl<D: sha2::digest::Update> fmt::Write for DigestWriter<D> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.update(s.as_bytes());
+        Ok(())
+    }
+}
+
 impl DValue {
+    /// Parses the text format, returning `DValue::None` if it is invalid.
+    /// Trailing text after the first value is ignored.
+    #[cfg(feature = "parser")]
+    #[deprecated(note = "use `str::parse`, which reports why the text is invalid")]
     pub fn from(data: &str) -> Self {
-        let data = if data.starts_with("b:") && data.ends_with(':') {
-            let base64_content = &data[2..data.len() - 1];
-            match base64_engine::STANDARD.decode(base64_content) {
-                Ok(decoded) => match String::from_utf8(decoded) {
-                    Ok(s) => s,
-                    Err(_) => return Self::None,
-                },
-                Err(_) => return Self::None,
-            }
-        } else {
-            data.to_string()
+        let Ok(data) = DValue::unwrap_b64(data) else {
+            return Self::None;
         };
 
         match ValueParser::parse(&data) {
@@ -172,12 +459,241 @@ impl DValue {
         }
     }
 
+    /// Decodes text wrapped as `b:<base64>:`, or borrows it unchanged.
+    #[cfg(feature = "parser")]
+    pub(crate) fn unwrap_b64(data: &str) -> Result<Cow<'_, str>, ParseError> {
+        let Some(encoded) = data.strip_prefix("b:").and_then(|d| d.strip_suffix(':')) else {
+            return Ok(Cow::Borrowed(data));
+        };
+        let invalid = |message: &str| ParseError {
+            position: 2,
+            message: message.to_string(),
+        };
+
+        let decoded = base64_engine::STANDARD
+            .decode(encoded)
+            .map_err(|_| invalid("invalid base64"))?;
+        String::from_utf8(decoded)
+            .map(Cow::Owned)
+            .map_err(|_| invalid("base64 content is not UTF-8"))
+    }
+
+    /// Reads the JSON written by `to_json`, returning `DValue::None` if it is
+    /// invalid. Use `try_from_json` to find out why.
+    #[cfg(feature = "json")]
     pub fn from_json(data: &str) -> Self {
-        serde_json::from_str(data).unwrap_or(Self::None)
+        DValue::try_from_json(data).unwrap_or(Self::None)
     }
 
+    /// Reads the JSON written by `to_json`. Unlike writing, this stays on
+    /// serde_json: its deserializer builds the `DValue` directly, with no
+    /// intermediate `serde_json::Value`, and gives errors their positions.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let value = DValue::try_from_json("{\"List\":[{\"Boolean\":true},\"None\"]}").unwrap();
+    /// assert_eq!(value.to_string(), "[true,none]");
+    ///
+    /// let err = DValue::try_from_json("{\"List\":[{\"Bool\":true}]}").unwrap_err();
+    /// assert_eq!((err.line, err.column), (1, 16));
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn try_from_json(data: &str) -> Result<Self, JsonError> {
+        serde_json::from_str(data).map_err(JsonError::from)
+    }
+
+    /// Writes the value as externally tagged JSON, which `try_from_json`
+    /// reads back. Whole numbers are written as integers. This can't fail:
+    /// non-finite numbers, which JSON has no form for, are written as
+    /// `null`. See `to_json_with` for other options.
+    #[cfg(feature = "json")]
     pub fn to_json(&self) -> String {
-        serde_json::to_string(&self).unwrap_or(String::from("None"))
+        let mut out = Vec::with_capacity(128);
+        let Ok(()) = json::write_value(&mut out, self, NonFinite::Null, false);
+        // The writer only ever emits UTF-8.
+        String::from_utf8(out).unwrap_or_default()
+    }
+
+    /// Like `to_json`, with NaN and infinite numbers written according to
+    /// `non_finite`.
+    ///
+    /// ```
+    /// use datastruct::{DValue, NonFinite};
+    ///
+    /// let value = DValue::List(vec![DValue::Number(f64::INFINITY)]);
+    ///
+    /// assert_eq!(
+    ///     value.to_json_with(NonFinite::String).unwrap(),
+    ///     "{\"List\":[{\"Number\":\"Infinity\"}]}"
+    /// );
+    /// assert_eq!(
+    ///     value.to_json_with(NonFinite::Error).unwrap_err().to_string(),
+    ///     "cannot write inf as JSON at [0]"
+    /// );
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_json_with(&self, non_finite: NonFinite) -> Result<String, NonFiniteError> {
+        json::check_finite(self, non_finite)?;
+        let mut out = Vec::with_capacity(128);
+        let Ok(()) = json::write_value(&mut out, self, non_finite, false);
+        Ok(String::from_utf8(out).unwrap_or_default())
+    }
+
+    /// Like `to_json`, with dict keys sorted, so equal values always produce
+    /// the same JSON. Dicts themselves keep no order, so this is the way to
+    /// get output that diffs cleanly between runs.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let value = "{\"b\": 1, \"a\": {\"d\": none, \"c\": true}}".parse::<DValue>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     value.to_canonical_json(),
+    ///     "{\"Dict\":{\"a\":{\"Dict\":{\"c\":{\"Boolean\":true},\"d\":\"None\"}},\"b\":{\"Number\":1}}}"
+    /// );
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn to_canonical_json(&self) -> String {
+        let mut out = Vec::with_capacity(128);
+        let Ok(()) = json::write_value(&mut out, self, NonFinite::Null, true);
+        String::from_utf8(out).unwrap_or_default()
+    }
+
+    /// Writes the same text as `to_string` straight into any `fmt::Write`,
+    /// such as a `String` being built up.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let mut out = String::from("value = ");
+    /// "[1, (true, \"x\")]".parse::<DValue>().unwrap().write_to(&mut out).unwrap();
+    ///
+    /// assert_eq!(out, "value = [1,(true, \"x\")]");
+    /// ```
+    pub fn write_to<W: fmt::Write>(&self, out: &mut W) -> fmt::Result {
+        self.write_text(out, false)
+    }
+
+    /// Formats the value in its canonical text form: the compact text format
+    /// with dict keys sorted. Every value, including `None`, tuples, binary
+    /// data and non-finite numbers, parses back to an equal value, and equal
+    /// values always produce the same text.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let value = "{\"b\": [none, -inf], \"a\": (\"x\\\"y\", binary!(aGk=))}".parse::<DValue>().unwrap();
+    /// let text = value.to_canonical_string();
+    ///
+    /// assert_eq!(text, "{\"a\":(\"x\\\"y\", binary!(aGk=)),\"b\":[none,-inf]}");
+    /// assert_eq!(text.parse::<DValue>().unwrap(), value);
+    /// ```
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        // Writing into a String can't fail.
+        let _ = self.write_text(&mut out, true);
+        out
+    }
+
+    /// A SHA-256 hash of `to_canonical_string`, computed without building
+    /// the string. Since dict keys are sorted, it doesn't depend on the order
+    /// entries were inserted in, and it stays the same across processes and
+    /// builds, so it can key caches or spot duplicate documents.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let a = "{\"id\": 1, \"tags\": [\"x\"]}".parse::<DValue>().unwrap();
+    /// let b = "{\"tags\": [\"x\"], \"id\": 1}".parse::<DValue>().unwrap();
+    ///
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// assert_ne!(a.fingerprint(), DValue::None.fingerprint());
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut hasher = DigestWriter(Sha256::new());
+        // Hashing can't fail.
+        let _ = self.write_text(&mut hasher, true);
+        hasher.0.finalize().into()
+    }
+
+    fn write_text<W: fmt::Write>(&self, out: &mut W, sort_keys: bool) -> fmt::Result {
+        match self {
+            DValue::None => out.write_str("none"),
+            DValue::String(str) => format::write_quoted(out, str, false),
+            DValue::Number(num) => write!(out, "{}", num),
+            DValue::Boolean(bool) => write!(out, "{}", bool),
+            DValue::List(list) => {
+                out.write_char('[')?;
+                for (i, v) in list.iter().enumerate() {
+                    if i > 0 {
+                        out.write_char(',')?;
+                    }
+                    v.write_text(out, sort_keys)?;
+                }
+                out.write_char(']')
+            }
+            DValue::Dict(dict) => {
+                out.write_char('{')?;
+                if sort_keys {
+                    let mut entries: Vec<(&String, &DValue)> = dict.iter().collect();
+                    entries.sort_unstable_by_key(|(k, _)| *k);
+                    DValue::write_entries(out, entries.into_iter(), sort_keys)?;
+                } else {
+                    DValue::write_entries(out, dict.iter(), sort_keys)?;
+                }
+                out.write_char('}')
+            }
+            DValue::Tuple(v) => {
+                out.write_char('(')?;
+                v.0.write_text(out, sort_keys)?;
+                out.write_str(", ")?;
+                v.1.write_text(out, sort_keys)?;
+                out.write_char(')')
+            }
+            DValue::BinaryUtil(val) => out.write_str(&val.to_literal()),
+        }
+    }
+
+    fn write_entries<'a, W: fmt::Write>(
+        out: &mut W,
+        entries: impl Iterator<Item = (&'a String, &'a DValue)>,
+        sort_keys: bool,
+    ) -> fmt::Result {
+        for (i, (k, v)) in entries.enumerate() {
+            if i > 0 {
+                out.write_char(',')?;
+            }
+            format::write_quoted(out, k, false)?;
+            out.write_char(':')?;
+            v.write_text(out, sort_keys)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the same JSON as `to_json` straight into `out`, such as a file
+    /// or socket, without building the whole document in memory first.
+    #[cfg(all(feature = "std", feature = "json"))]
+    pub fn write_json_to<W: io::Write>(&self, out: W) -> io::Result<()> {
+        self.write_json_to_with(out, NonFinite::Null)
+    }
+
+    /// Like `write_json_to`, with NaN and infinite numbers written according
+    /// to `non_finite`. A `NonFiniteError` is returned as an
+    /// `io::ErrorKind::InvalidData` error before anything is written.
+    #[cfg(all(feature = "std", feature = "json"))]
+    pub fn write_json_to_with<W: io::Write>(
+        &self,
+        out: W,
+        non_finite: NonFinite,
+    ) -> io::Result<()> {
+        json::check_finite(self, non_finite)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut out = json::Io(io::BufWriter::new(out));
+        json::write_value(&mut out, self, non_finite, false)?;
+        io::Write::flush(&mut out.0)
     }
 
     pub fn weight(&self) -> f64 {
@@ -216,6 +732,38 @@ impl DValue {
         }
     }
 
+    /// The number of elements: items of a list, entries of a dict, chars of
+    /// a string, bytes of binary data and 2 for a tuple. Other values have
+    /// no length.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let value = "{\"name\": \"é\", \"tags\": [1, 2, 3]}".parse::<DValue>().unwrap();
+    ///
+    /// assert_eq!(value.len(), Some(2));
+    /// assert_eq!(value.get_path("name").unwrap().len(), Some(1));
+    /// assert_eq!(DValue::Number(7.0).len(), None);
+    /// ```
+    pub fn len(&self) -> Option<usize> {
+        match self {
+            DValue::String(str) => Some(str.chars().count()),
+            DValue::List(list) => Some(list.len()),
+            DValue::Dict(dict) => Some(dict.len()),
+            DValue::Tuple(_) => Some(2),
+            DValue::BinaryUtil(bin) => Some(bin.size()),
+            DValue::None | DValue::Number(_) | DValue::Boolean(_) => None,
+        }
+    }
+
+    /// Whether `len` is `Some(0)`. Values without a length aren't empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// The number of bytes of data in the value: string bytes, 8 per number,
+    /// 1 per boolean and binary bytes, summed over containers. For the number
+    /// of elements, see `len`.
     pub fn size(&self) -> usize {
         match self {
             DValue::None => 0,
@@ -243,73 +791,210 @@ impl DValue {
         }
     }
 
-    pub fn datatype(&self) -> String {
-        return match self {
-            DValue::None => "None",
-            DValue::String(_) => "String",
-            DValue::Number(_) => "Number",
-            DValue::Boolean(_) => "Boolean",
-            DValue::List(_) => "List",
-            DValue::Dict(_) => "Dict",
-            DValue::Tuple(_) => "Tuple",
-            DValue::BinaryUtil(_) => "Binary",
+    /// Estimates the memory the value takes up in bytes, including the
+    /// `DValue` itself. Unlike `size`, this counts spare `Vec` and `String`
+    /// capacity, dict keys, hash table buckets and the boxes of a tuple.
+    /// Allocator overhead isn't included and the hash table layout is
+    /// approximated, so treat the result as a lower bound.
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<DValue>() + self.heap_usage()
+    }
+
+    /// The heap memory owned by the value, not counting the value itself.
+    fn heap_usage(&self) -> usize {
+        match self {
+            DValue::None | DValue::Number(_) | DValue::Boolean(_) => 0,
+            DValue::String(str) => str.capacity(),
+            DValue::List(list) => {
+                list.capacity() * mem::size_of::<DValue>()
+                    + list.iter().map(DValue::heap_usage).sum::<usize>()
+            }
+            DValue::Dict(dict) => {
+                table_usage(dict)
+                    + dict
+                        .iter()
+                        .map(|(key, value)| key.capacity() + value.heap_usage())
+                        .sum::<usize>()
+            }
+            DValue::Tuple(tuple) => {
+                2 * mem::size_of::<DValue>() + tuple.0.heap_usage() + tuple.1.heap_usage()
+            }
+            DValue::BinaryUtil(bin) => bin.heap_usage(),
         }
-        .to_string();
+    }
+
+    pub fn datatype(&self) -> String {
+        self.dtype().to_string()
     }
 
     pub fn as_string(&self) -> Option<String> {
-        return match self {
+        match self {
             DValue::String(val) => Some(val.to_string()),
             _ => None,
-        };
+        }
     }
 
     pub fn as_number(&self) -> Option<f64> {
-        return match self {
+        match self {
             DValue::Number(val) => Some(*val),
             _ => None,
-        };
+        }
     }
 
     pub fn as_bool(&self) -> Option<bool> {
-        return match self {
+        match self {
             DValue::Boolean(val) => Some(*val),
             _ => None,
-        };
+        }
     }
 
     pub fn as_tuple(&self) -> Option<(Box<DValue>, Box<DValue>)> {
-        return match self {
+        match self {
             DValue::Tuple(val) => Some(val.clone()),
             _ => None,
-        };
+        }
     }
 
     pub fn as_list(&self) -> Option<Vec<DValue>> {
-        return match self {
+        match self {
             DValue::List(val) => Some(val.clone()),
             _ => None,
-        };
+        }
     }
 
-    pub fn as_dict(&self) -> Option<HashMap<String, DValue>> {
-        return match self {
+    pub fn as_dict(&self) -> Option<Map<String, DValue>> {
+        match self {
             DValue::Dict(val) => Some(val.clone()),
             _ => None,
-        };
+        }
+    }
+
+    /// Iterates over the elements of a list. Other variants yield nothing.
+    pub fn iter(&self) -> impl Iterator<Item = &DValue> {
+        match self {
+            DValue::List(list) => list.iter(),
+            _ => [].iter(),
+        }
+    }
+
+    /// Mutably iterates over the elements of a list. Other variants yield nothing.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut DValue> {
+        match self {
+            DValue::List(list) => list.iter_mut(),
+            _ => [].iter_mut(),
+        }
+    }
+
+    /// Iterates over the entries of a dict. Other variants yield nothing.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &DValue)> {
+        self.dict_ref()
+            .into_iter()
+            .flatten()
+            .map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Iterates over the keys of a dict. Other variants yield nothing.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.dict_ref()
+            .into_iter()
+            .flat_map(|d| d.keys())
+            .map(|k| k.as_str())
+    }
+
+    /// Iterates over the values of a dict. Other variants yield nothing.
+    pub fn values(&self) -> impl Iterator<Item = &DValue> {
+        self.dict_ref().into_iter().flat_map(|d| d.values())
+    }
+
+    /// Creates an empty list with room for `capacity` elements.
+    pub fn list_with_capacity(capacity: usize) -> Self {
+        DValue::List(Vec::with_capacity(capacity))
+    }
+
+    /// Creates an empty dict with room for `capacity` entries.
+    pub fn dict_with_capacity(capacity: usize) -> Self {
+        DValue::Dict(Map::with_capacity(capacity))
+    }
+
+    /// Reserves room for at least `additional` more elements in a list or
+    /// entries in a dict. Other variants are left untouched.
+    pub fn reserve(&mut self, additional: usize) {
+        match self {
+            DValue::List(list) => list.reserve(additional),
+            DValue::Dict(dict) => dict.reserve(additional),
+            _ => {}
+        }
+    }
+
+    /// Returns how many elements or entries a list or dict can hold without
+    /// reallocating, or `0` for other variants.
+    pub fn capacity(&self) -> usize {
+        match self {
+            DValue::List(list) => list.capacity(),
+            DValue::Dict(dict) => dict.capacity(),
+            _ => 0,
+        }
+    }
+
+    pub(crate) fn dict_ref(&self) -> Option<&Map<String, DValue>> {
+        match self {
+            DValue::Dict(val) => Some(val),
+            _ => None,
+        }
     }
 }
 
+#[cfg(feature = "parser")]
 struct ValueParser {}
 
+/// The nom error used by `ValueParser`. Of the branches that failed it keeps
+/// the one that got furthest into the input, which is where the text most
+/// likely went wrong, along with the innermost context it failed in.
+#[cfg(feature = "parser")]
+#[derive(Debug, Clone, PartialEq)]
+struct TextError<'a> {
+    input: &'a str,
+    context: Option<&'static str>,
+}
+
+#[cfg(feature = "parser")]
+impl<'a> nom::error::ParseError<&'a str> for TextError<'a> {
+    fn from_error_kind(input: &'a str, _kind: ErrorKind) -> Self {
+        TextError {
+            input,
+            context: None,
+        }
+    }
+
+    fn append(_input: &'a str, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn or(self, other: Self) -> Self {
+        match other.input.len() < self.input.len() {
+            true => other,
+            false => self,
+        }
+    }
+}
+
+#[cfg(feature = "parser")]
+impl<'a> ContextError<&'a str> for TextError<'a> {
+    fn add_context(_input: &'a str, context: &'static str, mut other: Self) -> Self {
+        other.context.get_or_insert(context);
+        other
+    }
+}
+
+#[cfg(feature = "parser")]
 impl ValueParser {
-    fn normal(msg: &str) -> IResult<&str, &str> {
+    fn normal(msg: &str) -> IResult<&str, &str, TextError<'_>> {
         take_till1(|c: char| c == '\\' || c == '"' || c.is_ascii_control())(msg)
     }
 
-    fn escapable(i: &str) -> IResult<&str, &str> {
+    fn escapable(i: &str) -> IResult<&str, &str, TextError<'_>> {
         context(
-            "escaped",
+            "escape",
             alt((
                 tag("\""),
                 tag("\\"),
@@ -324,11 +1009,11 @@ impl ValueParser {
         )(i)
     }
 
-    fn string_format(msg: &str) -> IResult<&str, &str> {
+    fn string_format(msg: &str) -> IResult<&str, &str, TextError<'_>> {
         escaped(ValueParser::normal, '\\', ValueParser::escapable)(msg)
     }
 
-    fn parse_hex(msg: &str) -> IResult<&str, &str> {
+    fn parse_hex(msg: &str) -> IResult<&str, &str, TextError<'_>> {
         context(
             "hex string",
             preceded(
@@ -338,77 +1023,208 @@ impl ValueParser {
         )(msg)
     }
 
-    fn parse_str(msg: &str) -> IResult<&str, &str> {
+    fn parse_str(msg: &str) -> IResult<&str, &str, TextError<'_>> {
         context(
             "string",
             alt((
-                tag("\"\""),
+                n_value("", tag("\"\"")),
                 delimited(tag("\""), ValueParser::string_format, tag("\"")),
             )),
         )(msg)
     }
 
-    fn parse_bin(msg: &str) -> IResult<&str, Binary> {
-        let result: (&str, &str) = context(
+    /// Parses a quoted string and decodes its escapes.
+    fn parse_string(msg: &str) -> IResult<&str, Cow<'_, str>, TextError<'_>> {
+        map_opt(ValueParser::parse_str, ValueParser::unescape)(msg)
+    }
+
+    /// Decodes the escapes accepted inside strings, borrowing `s` when it has
+    /// none. `\uXXXX` surrogate pairs are joined into one char.
+    fn unescape(s: &str) -> Option<Cow<'_, str>> {
+        if !s.contains('\\') {
+            return Some(Cow::Borrowed(s));
+        }
+
+        let mut out = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        let hex = |chars: &mut core::str::Chars| {
+            let digits = chars.as_str().get(..4)?;
+            let unit = u32::from_str_radix(digits, 16).ok()?;
+            chars.nth(3);
+            Some(unit)
+        };
+
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+
+            out.push(match chars.next()? {
+                '"' => '"',
+                '\\' => '\\',
+                '/' => '/',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'u' => match hex(&mut chars)? {
+                    high @ 0xD800..=0xDBFF => {
+                        chars.as_str().starts_with("\\u").then_some(())?;
+                        chars.nth(1);
+                        let low = hex(&mut chars).filter(|u| (0xDC00..=0xDFFF).contains(u))?;
+                        char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))?
+                    }
+                    unit => char::from_u32(unit)?,
+                },
+                _ => return None,
+            });
+        }
+        Some(Cow::Owned(out))
+    }
+
+    /// The text between the parentheses of a `binary!(...)` literal.
+    fn bin_literal(msg: &str) -> IResult<&str, &str, TextError<'_>> {
+        // `binary util!(...)` is what older versions printed, keep reading it.
+        context(
             "binary",
-            alt((
-                tag("binary!()"),
-                delimited(
-                    tag("binary!("),
-                    take_till1(|c: char| c == '\\' || c == ')' || c.is_ascii_control()),
-                    tag(")"),
-                ),
-            )),
-        )(msg)?;
+            preceded(
+                alt((tag("binary!"), tag("binary util!"))),
+                alt((
+                    n_value("", tag("()")),
+                    delimited(
+                        tag("("),
+                        take_till1(|c: char| c == '\\' || c == ')' || c.is_ascii_control()),
+                        tag(")"),
+                    ),
+                )),
+            ),
+        )(msg)
+    }
+
+    /// A `binary!(<base64>)` literal. `binary!(file:<path>)` is only read by
+    /// `ParserOptions` with file includes allowed.
+    fn parse_bin(msg: &str) -> IResult<&str, Binary, TextError<'_>> {
+        let (rest, data) = ValueParser::bin_literal(msg)?;
+        if data.starts_with("file:") {
+            return Err(nom::Err::Error(TextError {
+                input: msg,
+                context: Some("binary"),
+            }));
+        }
 
         Ok((
-            result.0,
-            Binary::from_b64(result.1.to_string()).unwrap_or(Binary::new(vec![])),
+            rest,
+            Binary::from_b64(data.to_string()).unwrap_or(Binary::new(Vec::new())),
         ))
     }
 
-    fn parse_num(msg: &str) -> IResult<&str, f64> {
-        double(msg)
+    /// The path of a `binary!(file:<path>)` literal.
+    #[cfg(feature = "std")]
+    fn parse_file_ref(msg: &str) -> IResult<&str, &str, TextError<'_>> {
+        map_opt(ValueParser::bin_literal, |data| data.strip_prefix("file:"))(msg)
+    }
+
+    fn parse_num(msg: &str) -> IResult<&str, f64, TextError<'_>> {
+        // `double` reads `inf` and `nan` but not a negative infinity.
+        let neg_inf = alt((tag_no_case("-infinity"), tag_no_case("-inf")));
+        alt((
+            ValueParser::parse_hex_num,
+            ValueParser::parse_grouped,
+            double,
+            n_value(f64::NEG_INFINITY, neg_inf),
+        ))(msg)
+    }
+
+    /// `0xFF` or `0xFF_FF`, optionally signed.
+    fn parse_hex_num(msg: &str) -> IResult<&str, f64, TextError<'_>> {
+        let digits = recognize(pair(hex_digit1, many0(pair(tag("_"), hex_digit1))));
+        map(
+            tuple((opt(one_of("+-")), tag_no_case("0x"), digits)),
+            |(sign, _, digits): (_, _, &str)| {
+                let n = digits
+                    .chars()
+                    .filter_map(|c| c.to_digit(16))
+                    .fold(0.0, |n, d| n * 16.0 + d as f64);
+                if sign == Some('-') {
+                    -n
+                } else {
+                    n
+                }
+            },
+        )(msg)
+    }
+
+    /// A decimal number with `_` between digits, such as `1_000_000` or
+    /// `3.141_592`. Numbers without one are left to `double`.
+    fn parse_grouped(msg: &str) -> IResult<&str, f64, TextError<'_>> {
+        let digits = || recognize(pair(digit1, many0(pair(tag("_"), digit1))));
+        let number = recognize(tuple((
+            opt(one_of("+-")),
+            digits(),
+            opt(pair(tag("."), digits())),
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digits()))),
+        )));
+        map_opt(number, |text: &str| {
+            if !text.contains('_') {
+                return None;
+            }
+            text.replace('_', "").parse().ok()
+        })(msg)
     }
 
-    fn parse_bool(msg: &str) -> IResult<&str, bool> {
+    fn parse_bool(msg: &str) -> IResult<&str, bool, TextError<'_>> {
         let true_parser = n_value(true, tag_no_case("true"));
         let false_parser = n_value(false, tag_no_case("false"));
         alt((true_parser, false_parser))(msg)
     }
 
-    fn parse_list(msg: &str) -> IResult<&str, Vec<DValue>> {
+    fn parse_list(msg: &str) -> IResult<&str, Vec<DValue>, TextError<'_>> {
         context(
             "list",
             delimited(
                 tag("["),
-                separated_list0(
-                    tag(","),
-                    delimited(multispace0, ValueParser::parse, multispace0),
-                ),
+                alt((
+                    terminated(
+                        separated_list1(
+                            tag(","),
+                            delimited(multispace0, ValueParser::parse, multispace0),
+                        ),
+                        ValueParser::trailing_comma,
+                    ),
+                    n_value(Vec::new(), multispace0),
+                )),
                 tag("]"),
             ),
         )(msg)
     }
 
-    fn parse_dict(msg: &str) -> IResult<&str, HashMap<String, DValue>> {
+    fn parse_dict(msg: &str) -> IResult<&str, Map<String, DValue>, TextError<'_>> {
         context(
             "object",
             delimited(
                 tag("{"),
                 map(
-                    separated_list0(
-                        tag(","),
-                        separated_pair(
-                            delimited(multispace0, ValueParser::parse_str, multispace0),
-                            tag(":"),
-                            delimited(multispace0, ValueParser::parse, multispace0),
+                    alt((
+                        terminated(
+                            separated_list1(
+                                tag(","),
+                                // Once a key is read, the rest of the entry must follow.
+                                separated_pair(
+                                    delimited(multispace0, ValueParser::parse_string, multispace0),
+                                    cut(tag(":")),
+                                    cut(delimited(multispace0, ValueParser::parse, multispace0)),
+                                ),
+                            ),
+                            ValueParser::trailing_comma,
                         ),
-                    ),
-                    |tuple_vec: Vec<(&str, DValue)>| {
+                        n_value(Vec::new(), multispace0),
+                    )),
+                    |tuple_vec: Vec<(Cow<str>, DValue)>| {
                         tuple_vec
                             .into_iter()
-                            .map(|(k, v)| (String::from(k), v))
+                            .map(|(k, v)| (k.into_owned(), v))
                             .collect()
                     },
                 ),
@@ -417,7 +1233,12 @@ impl ValueParser {
         )(msg)
     }
 
-    fn parse_tuple(msg: &str) -> IResult<&str, (Box<DValue>, Box<DValue>)> {
+    /// An optional comma after the last item of a non-empty list or dict.
+    fn trailing_comma(msg: &str) -> IResult<&str, Option<&str>, TextError<'_>> {
+        opt(terminated(tag(","), multispace0))(msg)
+    }
+
+    fn parse_tuple(msg: &str) -> IResult<&str, (Box<DValue>, Box<DValue>), TextError<'_>> {
         context(
             "tuple",
             delimited(
@@ -435,15 +1256,18 @@ impl ValueParser {
         )(msg)
     }
 
-    fn parse(msg: &str) -> IResult<&str, DValue> {
+    fn parse(msg: &str) -> IResult<&str, DValue, TextError<'_>> {
         context(
             "value",
             delimited(
                 multispace0,
                 alt((
+                    n_value(DValue::None, tag("none")),
                     map(ValueParser::parse_num, DValue::Number),
                     map(ValueParser::parse_bool, DValue::Boolean),
-                    map(ValueParser::parse_str, |s| DValue::String(String::from(s))),
+                    map(ValueParser::parse_string, |s| {
+                        DValue::String(s.into_owned())
+                    }),
                     map(ValueParser::parse_list, DValue::List),
                     map(ValueParser::parse_dict, DValue::Dict),
                     map(ValueParser::parse_tuple, DValue::Tuple),
@@ -451,14 +1275,14 @@ impl ValueParser {
                 )),
                 multispace0,
             ),
-        )(&msg)
+        )(msg)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "parser"))]
 mod test {
 
-    use crate::{binary_util::Binary, DValue, ValueParser};
+    use crate::{binary_util::Binary, DValue, Map, ValueParser};
 
     #[test]
     fn parse_list() {
@@ -491,6 +1315,29 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_separated_numbers() {
+        let num = |text: &str| match text.parse::<DValue>() {
+            Ok(DValue::Number(n)) => Some(n),
+            _ => None,
+        };
+
+        assert_eq!(num("1_000_000"), Some(1_000_000.0));
+        assert_eq!(num("-1_000.000_5e1_0"), Some(-1_000.000_5e10));
+        assert_eq!(num("0.1_5"), Some(0.15));
+        assert_eq!(num("0xFF_FF"), Some(65535.0));
+        assert_eq!(num("-0x1f"), Some(-31.0));
+        assert_eq!(num("0XdeadBEEF"), Some(3_735_928_559.0));
+        assert_eq!(num("1.5e3"), Some(1500.0));
+        for bad in ["1__000", "_1", "1_", "1_.5", "0x", "0x_1", "0xG"] {
+            assert_eq!(num(bad), None, "{}", bad);
+        }
+        assert_eq!(
+            "[0x10, 1_0]".parse::<DValue>().unwrap().to_string(),
+            "[16,10]"
+        );
+    }
+
     #[test]
     fn parse_binary() {
         let message = "binary!(bWVtZW50byBtb3Jp)";
@@ -504,6 +1351,248 @@ mod test {
             ))
         )
     }
+
+    #[test]
+    fn binary_round_trip() {
+        let value = DValue::List(vec![
+            DValue::BinaryUtil(Binary::new(b"Hello World".to_vec())),
+            DValue::BinaryUtil(Binary::new(vec![])),
+        ]);
+
+        assert_eq!(value.to_string(), "[binary!(SGVsbG8gV29ybGQ=),binary!()]");
+        assert_eq!(value.to_string().parse::<DValue>().unwrap(), value);
+        assert_eq!(
+            "binary util!(SGVsbG8gV29ybGQ=)".parse::<DValue>().unwrap(),
+            DValue::BinaryUtil(Binary::new(b"Hello World".to_vec()))
+        );
+    }
+
+    #[test]
+    fn pretty_display() {
+        let value = DValue::List(vec![
+            "{\"a\": []}".parse::<DValue>().unwrap(),
+            DValue::Tuple((
+                Box::new("[1]".parse::<DValue>().unwrap()),
+                Box::new(DValue::BinaryUtil(Binary::new(b"hi".to_vec()))),
+            )),
+        ]);
+
+        assert_eq!(format!("{}", value), value.to_string());
+        assert_eq!(
+            format!("{:#}", value),
+            "[\n  {\n    \"a\": []\n  },\n  ([\n    1\n  ], binary!(aGk=))\n]"
+        );
+        assert_eq!(format!("{:#}", value).parse::<DValue>().unwrap(), value);
+    }
+
+    #[test]
+    fn escaped_strings() {
+        let text = "he said \"hi\"\\\n\t\r\u{8}\u{c}\u{1}é😀";
+        let mut dict = Map::new();
+        dict.insert(text.to_string(), DValue::String(text.to_string()));
+        let value = DValue::Dict(dict);
+
+        assert_eq!(
+            DValue::String("a\"b\\c\n".to_string()).to_string(),
+            "\"a\\\"b\\\\c\\n\""
+        );
+        assert_eq!(
+            DValue::String("\u{1}".to_string()).to_string(),
+            "\"\\u0001\""
+        );
+        assert_eq!(
+            value.to_string().parse::<DValue>().unwrap().as_dict(),
+            value.as_dict()
+        );
+        assert_eq!(
+            format!("{:#}", value).parse::<DValue>().unwrap().as_dict(),
+            value.as_dict()
+        );
+        assert_eq!(
+            "\"\\ud83d\\ude00 \\u00e9\"".parse::<DValue>().unwrap(),
+            DValue::String("😀 é".to_string())
+        );
+        assert_eq!(
+            "\"\"".parse::<DValue>().unwrap(),
+            DValue::String(String::new())
+        );
+    }
+
+    #[test]
+    fn from_str_errors() {
+        let error = |text: &str| {
+            let e = text.parse::<DValue>().unwrap_err();
+            (e.position, e.message)
+        };
+
+        assert_eq!(
+            "b:WzEsIDJd:".parse::<DValue>(),
+            Ok(DValue::List(vec![DValue::Number(1.0), DValue::Number(2.0)]))
+        );
+        assert_eq!(
+            error(""),
+            (0, "unexpected end of input in value".to_string())
+        );
+        assert_eq!(
+            error("[1, 2] x"),
+            (7, "unexpected trailing characters".to_string())
+        );
+        assert_eq!(error("{\"a\" 1}"), (5, "invalid object".to_string()));
+        assert_eq!(error("[,]"), (1, "invalid list".to_string()));
+        assert_eq!("{ }".parse::<DValue>(), Ok(DValue::Dict(Map::new())));
+        assert_eq!(error("\"a\\q\""), (3, "invalid escape".to_string()));
+        assert_eq!(error("b:!!:"), (2, "invalid base64".to_string()));
+    }
+
+    #[test]
+    fn canonical_round_trip() {
+        let mut dict = Map::new();
+        for (i, key) in ["z", "a", "m\"", ""].into_iter().enumerate() {
+            dict.insert(key.to_string(), DValue::Number(i as f64));
+        }
+        let values = [
+            DValue::None,
+            DValue::Number(f64::NAN),
+            DValue::Number(f64::INFINITY),
+            DValue::Number(f64::NEG_INFINITY),
+            DValue::Number(-0.0),
+            DValue::Number(5e-324),
+            DValue::Number(1.7976931348623157e308),
+            DValue::String("\u{7f}\u{85}\u{2028}".to_string()),
+            DValue::List(vec![DValue::None, DValue::List(vec![])]),
+            DValue::Dict(dict),
+            DValue::Tuple((Box::new(DValue::None), Box::new(DValue::Dict(Map::new())))),
+            DValue::BinaryUtil(Binary::new(Vec::new())),
+            DValue::BinaryUtil(Binary::new(vec![0, 255, 7])),
+        ];
+
+        for value in values {
+            let text = value.to_canonical_string();
+            let parsed = text.parse::<DValue>().unwrap();
+
+            assert_eq!(parsed.to_canonical_string(), text);
+            assert_eq!(parsed, value, "{}", text);
+        }
+        assert_eq!(
+            "{\"b\": 1, \"a\": 2}"
+                .parse::<DValue>()
+                .unwrap()
+                .to_canonical_string(),
+            "{\"a\":2,\"b\":1}"
+        );
+    }
+
+    #[test]
+    fn len_and_is_empty() {
+        let values = [
+            (DValue::String("añb".to_string()), Some(3)),
+            (DValue::List(vec![DValue::None]), Some(1)),
+            (DValue::Dict(Map::new()), Some(0)),
+            (
+                DValue::Tuple((Box::new(DValue::None), Box::new(DValue::None))),
+                Some(2),
+            ),
+            (DValue::BinaryUtil(Binary::new(vec![1, 2])), Some(2)),
+            (DValue::None, None),
+            (DValue::Number(0.0), None),
+            (DValue::Boolean(false), None),
+        ];
+
+        for (value, len) in values {
+            assert_eq!(value.len(), len, "{}", value);
+            assert_eq!(value.is_empty(), len == Some(0));
+        }
+        assert!(DValue::String(String::new()).is_empty());
+        assert!(!DValue::None.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "checksum")]
+    fn fingerprint() {
+        use sha2::{Digest, Sha256};
+
+        let mut a = Map::new();
+        let mut b = Map::new();
+        for (i, key) in ["z", "a", "m"].into_iter().enumerate() {
+            a.insert(key.to_string(), DValue::Number(i as f64));
+        }
+        for (i, key) in ["m", "a", "z"].into_iter().enumerate().rev() {
+            b.insert(key.to_string(), DValue::Number((2 - i) as f64));
+        }
+        let (a, b) = (DValue::Dict(a), DValue::Dict(b));
+
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        let digest: [u8; 32] = Sha256::digest(a.to_canonical_string()).into();
+        assert_eq!(a.fingerprint(), digest);
+
+        let list = DValue::List(vec![DValue::String("a".to_string())]);
+        assert_ne!(
+            list.fingerprint(),
+            DValue::String("a".to_string()).fingerprint()
+        );
+        assert_ne!(list.fingerprint(), a.fingerprint());
+    }
+
+    #[test]
+    fn iter_list() {
+        let mut value = "[1, 2, 3]".parse::<DValue>().unwrap();
+
+        for item in value.iter_mut() {
+            *item = DValue::Number(item.as_number().unwrap() * 2.0);
+        }
+
+        assert_eq!(
+            value
+                .iter()
+                .filter_map(|v| v.as_number())
+                .collect::<Vec<f64>>(),
+            vec![2.0, 4.0, 6.0]
+        );
+        assert_eq!(DValue::Number(1.0).iter().count(), 0);
+    }
+
+    #[test]
+    fn iter_dict() {
+        let value = "{\"a\": 1, \"b\": 2}".parse::<DValue>().unwrap();
+
+        let mut keys: Vec<&str> = value.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b"]);
+        assert_eq!(value.values().map(|v| v.weight()).sum::<f64>(), 3.0);
+        assert!(value
+            .entries()
+            .any(|(k, v)| k == "b" && *v == DValue::Number(2.0)));
+        assert_eq!("[1]".parse::<DValue>().unwrap().entries().count(), 0);
+    }
+
+    #[test]
+    fn with_capacity() {
+        let mut list = DValue::list_with_capacity(16);
+        assert!(list.capacity() >= 16);
+        assert_eq!(list.to_string(), "[]");
+
+        let mut dict = DValue::dict_with_capacity(4);
+        dict.reserve(64);
+        assert!(dict.capacity() >= 64);
+
+        list.reserve(100);
+        assert!(list.capacity() >= 100);
+        assert_eq!(DValue::Number(1.0).capacity(), 0);
+    }
+
+    #[cfg(all(feature = "std", feature = "json"))]
+    #[test]
+    fn write_json_to_matches_to_json() {
+        let value = "[1, \"a\", (true, binary!(aGk=))]"
+            .parse::<DValue>()
+            .unwrap();
+        let mut out = Vec::new();
+        value.write_json_to(&mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), value.to_json());
+    }
+
+    #[cfg(feature = "json")]
     #[test]
     fn parse_to_json() {
         let value = DValue::List(vec![
@@ -511,7 +1600,56 @@ mod test {
             DValue::Number(6.0),
             DValue::Number(9.0),
         ]);
-        let expected_json = r#"{"List":[{"Number":3.0},{"Number":6.0},{"Number":9.0}]}"#;
+        let expected_json = r#"{"List":[{"Number":3},{"Number":6},{"Number":9}]}"#;
         assert_eq!(value.to_json(), expected_json);
     }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn try_from_json_errors() {
+        let value = "{\"a\": [1, (true, binary!(aGk=))]}"
+            .parse::<DValue>()
+            .unwrap();
+        assert_eq!(DValue::try_from_json(&value.to_json()), Ok(value));
+
+        let err = DValue::try_from_json("{\"List\":[\"None\",\n\"None\",]}").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 8));
+        assert_eq!(err.message, "trailing comma");
+        assert_eq!(
+            err.to_string(),
+            "invalid JSON at line 2 column 8: trailing comma"
+        );
+        assert!(DValue::try_from_json("").is_err());
+        assert_eq!(DValue::from_json("[1]"), DValue::None);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let value_size = core::mem::size_of::<DValue>();
+
+        assert_eq!(DValue::Number(1.0).memory_usage(), value_size);
+        assert_eq!(
+            DValue::String(String::with_capacity(64)).memory_usage(),
+            value_size + 64
+        );
+
+        let mut items = Vec::with_capacity(10);
+        items.push(DValue::String("abc".to_string()));
+        assert_eq!(
+            DValue::List(items).memory_usage(),
+            value_size + 10 * value_size + 3
+        );
+
+        let tuple = DValue::Tuple((
+            Box::new(DValue::None),
+            Box::new(DValue::BinaryUtil(Binary::new(vec![0; 5]))),
+        ));
+        assert_eq!(tuple.memory_usage(), 3 * value_size + 5);
+
+        // Keys and table overhead count, unlike in `size`.
+        let dict = "{\"key\": 1}".parse::<DValue>().unwrap();
+        assert_eq!(dict.size(), 8);
+        assert!(dict.memory_usage() > value_size + 3 + 2 * value_size);
+        assert_eq!(DValue::Dict(Map::new()).memory_usage(), value_size);
+    }
 }
@@ -0,0 +1,231 @@
+use crate::binary_util::Binary;
+use crate::{DValue, Map};
+use mlua::{Error, FromLua, IntoLua, Lua, Result, Table, Value};
+use std::ffi::c_void;
+
+/// How deeply tables may nest when converting from Lua.
+const MAX_DEPTH: usize = 128;
+
+/// Converts to a Lua value. Lists and tuples become sequences, dicts become
+/// tables with string keys, and binary data becomes a Lua string holding
+/// the raw bytes.
+///
+/// `None` becomes `nil` on its own, but `mlua::Value::NULL` inside a list,
+/// dict or tuple, since a `nil` there would leave a hole in the sequence or
+/// drop the entry. Both convert back to `None`.
+impl IntoLua for &DValue {
+    fn into_lua(self, lua: &Lua) -> Result<Value> {
+        Ok(match self {
+            DValue::None => Value::Nil,
+            DValue::String(s) => Value::String(lua.create_string(s)?),
+            DValue::Number(n) => Value::Number(*n),
+            DValue::Boolean(b) => Value::Boolean(*b),
+            DValue::List(items) => Value::Table(
+                lua.create_sequence_from(
+                    items
+                        .iter()
+                        .map(|item| element(item, lua))
+                        .collect::<Result<Vec<_>>>()?,
+                )?,
+            ),
+            DValue::Dict(entries) => Value::Table(
+                lua.create_table_from(
+                    entries
+                        .iter()
+                        .map(|(k, v)| Ok((k.as_str(), element(v, lua)?)))
+                        .collect::<Result<Vec<_>>>()?,
+                )?,
+            ),
+            DValue::Tuple(pair) => Value::Table(
+                lua.create_sequence_from([element(&pair.0, lua)?, element(&pair.1, lua)?])?,
+            ),
+            DValue::BinaryUtil(bin) => Value::String(lua.create_string(bin.as_bytes())?),
+        })
+    }
+}
+
+/// Converts a value held in a table, where `None` must not become `nil`.
+fn element(value: &DValue, lua: &Lua) -> Result<Value> {
+    match value {
+        DValue::None => Ok(Value::NULL),
+        value => value.into_lua(lua),
+    }
+}
+
+impl IntoLua for DValue {
+    fn into_lua(self, lua: &Lua) -> Result<Value> {
+        (&self).into_lua(lua)
+    }
+}
+
+/// Converts from a Lua value. A table whose keys are exactly `1..=n` becomes
+/// a list, and any other table becomes a dict with string keys; an empty
+/// table becomes an empty list. Strings that aren't valid UTF-8 become
+/// binary data.
+///
+/// Fails on a table that contains itself, or on tables nested more than 128
+/// deep.
+impl FromLua for DValue {
+    fn from_lua(value: Value, _: &Lua) -> Result<Self> {
+        from_value(value, &mut Vec::new())
+    }
+}
+
+/// Converts `value`, with `parents` holding the tables it is nested in.
+fn from_value(value: Value, parents: &mut Vec<*const c_void>) -> Result<DValue> {
+    match value {
+        Value::Nil => Ok(DValue::None),
+        Value::LightUserData(ud) if ud.0.is_null() => Ok(DValue::None),
+        Value::Boolean(b) => Ok(DValue::Boolean(b)),
+        Value::Integer(n) => Ok(DValue::Number(n as f64)),
+        Value::Number(n) => Ok(DValue::Number(n)),
+        Value::String(s) => {
+            let bytes = s.as_bytes().to_vec();
+            Ok(match String::from_utf8(bytes) {
+                Ok(s) => DValue::String(s),
+                Err(err) => DValue::BinaryUtil(Binary::new(err.into_bytes())),
+            })
+        }
+        Value::Table(table) => {
+            let pointer = table.to_pointer();
+            let message = if parents.contains(&pointer) {
+                "table contains itself"
+            } else if parents.len() >= MAX_DEPTH {
+                "tables nested too deeply"
+            } else {
+                parents.push(pointer);
+                let value = from_table(table, parents);
+                parents.pop();
+                return value;
+            };
+            Err(Error::FromLuaConversionError {
+                from: "table",
+                to: "DValue".to_string(),
+                message: Some(message.to_string()),
+            })
+        }
+        other => Err(Error::FromLuaConversionError {
+            from: other.type_name(),
+            to: "DValue".to_string(),
+            message: None,
+        }),
+    }
+}
+
+fn from_table(table: Table, parents: &mut Vec<*const c_void>) -> Result<DValue> {
+    let len = table.raw_len();
+    let mut count = 0;
+    let mut sequence = true;
+
+    for pair in table.pairs::<Value, Value>() {
+        let (key, _) = pair?;
+        count += 1;
+        sequence &= matches!(key, Value::Integer(i) if i >= 1 && i as usize <= len);
+    }
+
+    if sequence && count == len {
+        return table
+            .sequence_values::<Value>()
+            .map(|value| from_value(value?, parents))
+            .collect::<Result<_>>()
+            .map(DValue::List);
+    }
+
+    table
+        .pairs::<Value, Value>()
+        .map(|pair| {
+            let (key, value) = pair?;
+            let value = from_value(value, parents)?;
+            let key = match key {
+                Value::String(s) => s.to_str()?.to_string(),
+                Value::Integer(i) => i.to_string(),
+                other => {
+                    return Err(Error::FromLuaConversionError {
+                        from: other.type_name(),
+                        to: "String".to_string(),
+                        message: Some("dict keys must be strings or integers".to_string()),
+                    })
+                }
+            };
+            Ok((key, value))
+        })
+        .collect::<Result<Map<_, _>>>()
+        .map(DValue::Dict)
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::DValue;
+    use mlua::Lua;
+
+    #[test]
+    fn script_transforms_document() {
+        let lua = Lua::new();
+        let doc = "{\"items\": [1, 2, 3], \"blob\": binary!(/w==)}"
+            .parse::<DValue>()
+            .unwrap();
+        lua.globals().set("doc", &doc).unwrap();
+
+        let result: DValue = lua
+            .load(
+                r#"
+                local total = 0
+                for _, n in ipairs(doc.items) do total = total + n end
+                return { total = total, size = #doc.blob, empty = {}, sparse = { [1] = "a", [3] = "c" } }
+                "#,
+            )
+            .eval()
+            .unwrap();
+
+        assert_eq!(result.get_path("total"), Some(&DValue::Number(6.0)));
+        assert_eq!(result.get_path("size"), Some(&DValue::Number(1.0)));
+        assert_eq!(result.get_path("empty"), Some(&DValue::List(vec![])));
+        assert_eq!(
+            result.get_path("sparse.3"),
+            Some(&DValue::String("c".to_string()))
+        );
+
+        let blob: DValue = lua.load("return doc.blob").eval().unwrap();
+        assert_eq!(blob, doc.get_path("blob").unwrap().clone());
+        assert!(lua.load("return print").eval::<DValue>().is_err());
+    }
+
+    #[test]
+    fn none_survives_the_round_trip() {
+        let lua = Lua::new();
+        let doc = "{\"items\": [1, none, 3], \"gone\": none, \"pair\": (none, 2)}"
+            .parse::<DValue>()
+            .unwrap();
+        lua.globals().set("doc", &doc).unwrap();
+
+        let back: DValue = lua.load("return doc").eval().unwrap();
+        assert_eq!(back.get_path("items"), doc.get_path("items"));
+        assert_eq!(back.get_path("gone"), Some(&DValue::None));
+        assert_eq!(back.get_path("pair[0]"), Some(&DValue::None));
+        assert_eq!(lua.load("return #doc.items").eval::<i64>().unwrap(), 3);
+    }
+
+    #[test]
+    fn refuses_cycles_and_deep_nesting() {
+        let lua = Lua::new();
+
+        let cycle = lua
+            .load("local t = {} t.self = t return t")
+            .eval::<DValue>();
+        assert!(cycle
+            .unwrap_err()
+            .to_string()
+            .contains("table contains itself"));
+        let deep = lua
+            .load("local t = {} for i = 1, 100000 do t = {t} end return t")
+            .eval::<DValue>();
+        assert!(deep.unwrap_err().to_string().contains("nested too deeply"));
+
+        // The same table twice is fine as long as it isn't inside itself.
+        let shared: DValue = lua
+            .load("local t = {1} return {a = t, b = {t}}")
+            .eval()
+            .unwrap();
+        assert_eq!(shared.get_path("b[0][0]"), Some(&DValue::Number(1.0)));
+    }
+}
@@ -0,0 +1,128 @@
+use crate::path::Path;
+use crate::DValue;
+use core::mem;
+
+impl DValue {
+    /// Merges `other` into this value: dicts are merged key by key, and
+    /// anywhere else `other` wins.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let mut config = "{\"db\": {\"host\": \"a\", \"port\": 1}, \"debug\": false}".parse::<DValue>().unwrap();
+    /// config.merge("{\"db\": {\"port\": 2}, \"debug\": true}".parse().unwrap());
+    ///
+    /// assert_eq!(config.to_canonical_string(), "{\"db\":{\"host\":\"a\",\"port\":2},\"debug\":true}");
+    /// ```
+    pub fn merge(&mut self, other: DValue) {
+        self.merge_with(other, |_, _, right| right);
+    }
+
+    /// Like `merge`, but where the two sides differ and aren't both dicts,
+    /// `resolve` is called with the path of the conflict and the current and
+    /// incoming values, and its result is kept. Keys on one side only are
+    /// kept without asking, and equal values aren't conflicts.
+    ///
+    /// ```
+    /// use datastruct::path::Segment;
+    /// use datastruct::DValue;
+    ///
+    /// let mut local = "{\"name\": \"a\", \"seen\": 9, \"tags\": [1]}".parse::<DValue>().unwrap();
+    /// let remote = "{\"name\": \"b\", \"seen\": 4, \"tags\": [2]}".parse::<DValue>().unwrap();
+    ///
+    /// // Timestamps only move forward; anything else takes the remote side.
+    /// local.merge_with(remote, |path, left, right| match path.last() {
+    ///     Some(Segment::Key(key)) if key == "seen" => {
+    ///         DValue::Number(left.as_number().unwrap().max(right.as_number().unwrap()))
+    ///     }
+    ///     _ => right,
+    /// });
+    ///
+    /// assert_eq!(local.to_canonical_string(), "{\"name\":\"b\",\"seen\":9,\"tags\":[2]}");
+    /// ```
+    pub fn merge_with(
+        &mut self,
+        other: DValue,
+        mut resolve: impl FnMut(&Path, DValue, DValue) -> DValue,
+    ) {
+        merge(self, other, &Path::root(), &mut resolve);
+    }
+}
+
+fn merge(
+    target: &mut DValue,
+    other: DValue,
+    path: &Path,
+    resolve: &mut impl FnMut(&Path, DValue, DValue) -> DValue,
+) {
+    match (target, other) {
+        (DValue::Dict(target), DValue::Dict(other)) => {
+            for (key, value) in other {
+                match target.get_mut(&key) {
+                    Some(existing) => merge(existing, value, &path.key(&key), resolve),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, other) if *target == other => {}
+        (target, other) => {
+            let current = mem::replace(target, DValue::None);
+            *target = resolve(path, current, other);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::path::Path;
+    use crate::DValue;
+
+    #[test]
+    fn resolver_sees_conflicts_only() {
+        let mut left = "{\"a\": {\"x\": 1, \"y\": 2}, \"b\": [1], \"c\": true}"
+            .parse::<DValue>()
+            .unwrap();
+        let right = "{\"a\": {\"x\": 1, \"y\": 3, \"z\": 4}, \"b\": none, \"d\": 5}"
+            .parse::<DValue>()
+            .unwrap();
+
+        let mut conflicts = Vec::new();
+        left.merge_with(right, |path, left, right| {
+            conflicts.push((path.to_string(), left.clone(), right));
+            left
+        });
+
+        // Dict order isn't fixed, so neither is the order of the calls.
+        conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            conflicts,
+            [
+                ("a.y".to_string(), DValue::Number(2.0), DValue::Number(3.0)),
+                (
+                    "b".to_string(),
+                    DValue::List(vec![DValue::Number(1.0)]),
+                    DValue::None
+                ),
+            ]
+        );
+        assert_eq!(
+            left.to_canonical_string(),
+            "{\"a\":{\"x\":1,\"y\":2,\"z\":4},\"b\":[1],\"c\":true,\"d\":5}"
+        );
+    }
+
+    #[test]
+    fn root_conflict() {
+        let mut value = DValue::Number(1.0);
+        value.merge_with(DValue::Number(2.0), |path, left, right| {
+            assert_eq!(path, &Path::root());
+            left + right
+        });
+        assert_eq!(value, DValue::Number(3.0));
+
+        value.merge(DValue::String("x".to_string()));
+        assert_eq!(value, DValue::String("x".to_string()));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::DValue;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MigrationError {
+    /// No migration is registered from this version to the next.
+    MissingStep(u32),
+
+    /// The document is at a later version than the one requested, and
+    /// migrations only run forwards.
+    Downgrade { from: u32, to: u32 },
+
+    /// A migration rejected the document.
+    Failed { from: u32, message: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::MissingStep(from) => match from.checked_add(1) {
+                Some(to) => write!(f, "no migration from version {} to {}", from, to),
+                None => write!(f, "no migration from version {}", from),
+            },
+            MigrationError::Downgrade { from, to } => {
+                write!(f, "cannot migrate back from version {} to {}", from, to)
+            }
+            MigrationError::Failed { from, message } => {
+                write!(f, "migration from version {} failed: {}", from, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+type Migration = Arc<dyn Fn(DValue) -> Result<DValue, String> + Send + Sync>;
+
+/// Versioned migrations for stored documents.
+///
+/// Each migration takes a document from one version to the next, and
+/// `migrate_to_latest` runs every step from the document's version up to
+/// the latest one in order.
+///
+/// ```
+/// use datastruct::migrate::Migrations;
+/// use datastruct::DValue;
+///
+/// let mut migrations = Migrations::new();
+/// // v1 -> v2: rename `name` to `full_name`.
+/// migrations.register(1, |mut doc| {
+///     if let DValue::Dict(dict) = &mut doc {
+///         let name = dict.remove("name").ok_or("missing name")?;
+///         dict.insert("full_name".to_string(), name);
+///     }
+///     Ok(doc)
+/// });
+/// // v2 -> v3: add `tags`.
+/// migrations.register(2, |mut doc| {
+///     if let DValue::Dict(dict) = &mut doc {
+///         dict.insert("tags".to_string(), DValue::List(vec![]));
+///     }
+///     Ok(doc)
+/// });
+///
+/// let stored = "{\"name\": \"ada\"}".parse::<DValue>().unwrap();
+/// let (doc, version) = migrations.migrate_to_latest(stored, 1).unwrap();
+///
+/// assert_eq!(version, 3);
+/// assert_eq!(doc, "{\"full_name\": \"ada\", \"tags\": []}".parse::<DValue>().unwrap());
+/// ```
+#[derive(Clone, Default)]
+pub struct Migrations {
+    steps: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the migration from version `from` to `from + 1`, replacing any
+    /// existing one. Returning `Err` stops the run with
+    /// `MigrationError::Failed`.
+    ///
+    /// # Panics
+    ///
+    /// If `from` is `u32::MAX`, as there is no version after it.
+    pub fn register(
+        &mut self,
+        from: u32,
+        f: impl Fn(DValue) -> Result<DValue, String> + Send + Sync + 'static,
+    ) {
+        assert!(from < u32::MAX, "no version after {}", from);
+        self.steps.insert(from, Arc::new(f));
+    }
+
+    /// The version the last registered migration leads to, or `None` if
+    /// there are no migrations.
+    pub fn latest(&self) -> Option<u32> {
+        self.steps.keys().next_back().map(|from| from + 1)
+    }
+
+    /// Migrates `value` from `current_version` to `latest`, returning the
+    /// document and the version it is now at. A document that is already
+    /// at the latest version, or that no migrations are registered for, is
+    /// returned as it is.
+    pub fn migrate_to_latest(
+        &self,
+        value: DValue,
+        current_version: u32,
+    ) -> Result<(DValue, u32), MigrationError> {
+        let latest = self.latest().unwrap_or(current_version);
+        self.migrate(value, current_version, latest)
+            .map(|value| (value, latest))
+    }
+
+    /// Migrates `value` from version `from` to version `to`, one step at a
+    /// time.
+    pub fn migrate(&self, mut value: DValue, from: u32, to: u32) -> Result<DValue, MigrationError> {
+        if from > to {
+            return Err(MigrationError::Downgrade { from, to });
+        }
+
+        for version in from..to {
+            let step = self
+                .steps
+                .get(&version)
+                .ok_or(MigrationError::MissingStep(version))?;
+            value = step(value).map_err(|message| MigrationError::Failed {
+                from: version,
+                message,
+            })?;
+        }
+        Ok(value)
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.steps.keys()).finish()
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::migrate::{MigrationError, Migrations};
+    use crate::DValue;
+
+    fn counter() -> Migrations {
+        let mut migrations = Migrations::new();
+        for from in [1, 2, 3] {
+            migrations.register(from, move |mut doc| {
+                doc.incr("steps", 1.0).map_err(|e| e.to_string())?;
+                *doc.entry_path(&"version".parse().unwrap())
+                    .map_err(|e| e.to_string())? = DValue::Number(f64::from(from + 1));
+                Ok(doc)
+            });
+        }
+        migrations
+    }
+
+    #[test]
+    fn runs_steps_in_order() {
+        let migrations = counter();
+        assert_eq!(migrations.latest(), Some(4));
+
+        let (doc, version) = migrations
+            .migrate_to_latest("{}".parse().unwrap(), 2)
+            .unwrap();
+        assert_eq!(version, 4);
+        assert_eq!(
+            doc,
+            "{\"steps\": 2, \"version\": 4}".parse::<DValue>().unwrap()
+        );
+
+        let (doc, version) = migrations.migrate_to_latest(DValue::None, 4).unwrap();
+        assert_eq!((doc, version), (DValue::None, 4));
+        assert_eq!(
+            Migrations::new().migrate_to_latest(DValue::None, 7),
+            Ok((DValue::None, 7))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut migrations = counter();
+
+        assert_eq!(
+            migrations.migrate_to_latest(DValue::None, 0),
+            Err(MigrationError::MissingStep(0))
+        );
+        assert_eq!(
+            migrations.migrate_to_latest(DValue::None, 5),
+            Err(MigrationError::Downgrade { from: 5, to: 4 })
+        );
+
+        migrations.register(2, |_| Err("bad input".to_string()));
+        let err = migrations
+            .migrate_to_latest("{}".parse().unwrap(), 1)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "migration from version 2 failed: bad input"
+        );
+
+        assert_eq!(
+            MigrationError::MissingStep(u32::MAX).to_string(),
+            "no migration from version 4294967295"
+        );
+        let result = std::panic::catch_unwind(|| {
+            Migrations::new().register(u32::MAX, Ok);
+        });
+        assert!(result.is_err());
+
+        let mut migrations = Migrations::new();
+        migrations.register(u32::MAX - 1, Ok);
+        assert_eq!(migrations.latest(), Some(u32::MAX));
+        assert_eq!(
+            migrations.migrate_to_latest(DValue::None, u32::MAX - 1),
+            Ok((DValue::None, u32::MAX))
+        );
+    }
+}
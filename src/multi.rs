@@ -0,0 +1,100 @@
+use crate::path::{AccessError, Path};
+use crate::{DValue, Map};
+use alloc::string::ToString;
+use alloc::vec;
+use core::slice;
+
+impl DValue {
+    /// Adds `value` under `key`, keeping what is already there: a second
+    /// value turns the entry into a list of both, and later values are
+    /// appended to that list. `DValue::None` becomes an empty dict first.
+    ///
+    /// Since an entry holding a list is extended, a first value that is
+    /// itself a list gets later values added to it.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let mut query = DValue::None;
+    /// for (key, value) in [("tag", "a"), ("page", "2"), ("tag", "b")] {
+    ///     query.insert_multi(key, DValue::String(value.to_string())).unwrap();
+    /// }
+    ///
+    /// assert_eq!(query.to_canonical_string(), "{\"page\":\"2\",\"tag\":[\"a\",\"b\"]}");
+    /// assert_eq!(query.get_all("tag").len(), 2);
+    /// assert_eq!(query.get_all("page"), [DValue::String("2".to_string())]);
+    /// ```
+    pub fn insert_multi(&mut self, key: &str, value: DValue) -> Result<(), AccessError> {
+        if let DValue::None = self {
+            *self = DValue::Dict(Map::new());
+        }
+        let DValue::Dict(dict) = self else {
+            return Err(AccessError::TypeMismatch {
+                path: Path::root(),
+                expected: "Dict".to_string(),
+                found: self.datatype(),
+            });
+        };
+
+        match dict.get_mut(key) {
+            None => {
+                dict.insert(key.to_string(), value);
+            }
+            Some(DValue::List(items)) => items.push(value),
+            Some(existing) => {
+                let first = core::mem::replace(existing, DValue::None);
+                *existing = DValue::List(vec![first, value]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns every value under `key`: the items of a list, a single other
+    /// value, or nothing if the key is missing or this isn't a dict.
+    pub fn get_all(&self, key: &str) -> &[DValue] {
+        match self.dict_ref().and_then(|dict| dict.get(key)) {
+            None => &[],
+            Some(DValue::List(items)) => items,
+            Some(value) => slice::from_ref(value),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::path::AccessError;
+    use crate::DValue;
+
+    #[test]
+    fn promote_on_collision() {
+        let mut headers = "{\"accept\": \"text/html\"}".parse::<DValue>().unwrap();
+
+        headers
+            .insert_multi("accept", DValue::String("*/*".to_string()))
+            .unwrap();
+        headers
+            .insert_multi("accept", DValue::String("x/y".to_string()))
+            .unwrap();
+        headers.insert_multi("host", DValue::None).unwrap();
+
+        assert_eq!(
+            headers,
+            "{\"accept\": [\"text/html\", \"*/*\", \"x/y\"], \"host\": none}"
+                .parse::<DValue>()
+                .unwrap()
+        );
+        assert_eq!(headers.get_all("accept").len(), 3);
+        assert_eq!(headers.get_all("host"), [DValue::None]);
+        assert!(headers.get_all("missing").is_empty());
+        assert!(DValue::Number(1.0).get_all("a").is_empty());
+    }
+
+    #[test]
+    fn not_a_dict() {
+        let mut value = DValue::List(vec![]);
+        assert!(matches!(
+            value.insert_multi("a", DValue::None),
+            Err(AccessError::TypeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+//! Arithmetic operators on `DValue`.
+//!
+//! All four operators work on two numbers. `+` also concatenates two strings
+//! or two lists. Any other combination produces `DValue::None`.
+//!
+//! ```
+//! use datastruct::DValue;
+//!
+//! assert_eq!(DValue::Number(2.0) * DValue::Number(3.0), DValue::Number(6.0));
+//! assert_eq!("[1]".parse::<DValue>().unwrap() + "[2]".parse::<DValue>().unwrap(), "[1, 2]".parse::<DValue>().unwrap());
+//! assert_eq!(DValue::Number(1.0) + DValue::Boolean(true), DValue::None);
+//! ```
+
+use crate::DValue;
+use alloc::format;
+use core::ops::{Add, Div, Mul, Sub};
+
+impl Add for DValue {
+    type Output = DValue;
+
+    fn add(self, rhs: DValue) -> DValue {
+        match (self, rhs) {
+            (DValue::Number(a), DValue::Number(b)) => DValue::Number(a + b),
+            (DValue::String(a), DValue::String(b)) => DValue::String(a + b.as_str()),
+            (DValue::List(mut a), DValue::List(b)) => {
+                a.extend(b);
+                DValue::List(a)
+            }
+            _ => DValue::None,
+        }
+    }
+}
+
+impl Add for &DValue {
+    type Output = DValue;
+
+    fn add(self, rhs: &DValue) -> DValue {
+        match (self, rhs) {
+            (DValue::Number(a), DValue::Number(b)) => DValue::Number(a + b),
+            (DValue::String(a), DValue::String(b)) => DValue::String(format!("{}{}", a, b)),
+            (DValue::List(a), DValue::List(b)) => {
+                DValue::List(a.iter().chain(b).cloned().collect())
+            }
+            _ => DValue::None,
+        }
+    }
+}
+
+macro_rules! numeric_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl $trait for DValue {
+            type Output = DValue;
+
+            fn $method(self, rhs: DValue) -> DValue {
+                (&self).$method(&rhs)
+            }
+        }
+
+        impl $trait for &DValue {
+            type Output = DValue;
+
+            fn $method(self, rhs: &DValue) -> DValue {
+                match (self, rhs) {
+                    (DValue::Number(a), DValue::Number(b)) => DValue::Number(a $op b),
+                    _ => DValue::None,
+                }
+            }
+        }
+    };
+}
+
+numeric_op!(Sub, sub, -);
+numeric_op!(Mul, mul, *);
+numeric_op!(Div, div, /);
+
+#[cfg(test)]
+mod test {
+    use crate::DValue;
+
+    #[test]
+    fn arithmetic() {
+        let a = DValue::Number(9.0);
+        let b = DValue::Number(3.0);
+
+        assert_eq!(&a + &b, DValue::Number(12.0));
+        assert_eq!(&a - &b, DValue::Number(6.0));
+        assert_eq!(&a / &b, DValue::Number(3.0));
+        assert_eq!(
+            DValue::String("foo".to_string()) + DValue::String("bar".to_string()),
+            DValue::String("foobar".to_string())
+        );
+        assert_eq!(&DValue::String("foo".to_string()) * &b, DValue::None);
+    }
+}
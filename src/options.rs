@@ -0,0 +1,442 @@
+//! Parsing with options beyond what `str::parse` offers.
+
+use crate::binary_util::Binary;
+use crate::events::{Builder, EventReader};
+use crate::{DValue, ParseError};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+type Literal = Arc<dyn Fn(&str) -> Result<DValue, String> + Send + Sync>;
+
+/// The largest file `include_files` reads unless `max_include_size` says
+/// otherwise.
+pub const DEFAULT_MAX_INCLUDE_SIZE: u64 = 16 << 20;
+
+/// The registered `name!(...)` handlers, by name.
+pub(crate) type Literals = Arc<HashMap<String, Literal>>;
+
+/// Options for parsing the text format, set with the builder methods
+/// below. `ParserOptions::new()` accepts the same text as `str::parse` and
+/// reads it to the same values, though its error messages differ.
+///
+/// ```no_run
+/// use datastruct::options::ParserOptions;
+///
+/// let template = "{\"name\": \"logo\", \"png\": binary!(file:assets/logo.png)}";
+/// let doc = ParserOptions::new().include_files("./templates").parse(template).unwrap();
+///
+/// println!("{} bytes", doc.get_path("png").unwrap().size());
+/// ```
+#[derive(Clone)]
+pub struct ParserOptions {
+    file_root: Option<PathBuf>,
+    max_include_size: u64,
+    anchors: bool,
+    literals: Option<Literals>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            file_root: None,
+            max_include_size: DEFAULT_MAX_INCLUDE_SIZE,
+            anchors: false,
+            literals: None,
+        }
+    }
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `binary!(file:<path>)` literals as the contents of the file at
+    /// `<path>`, relative to `root`. Files outside `root`, whether reached
+    /// through `..`, an absolute path or a symlink, are refused. Without
+    /// this, such literals are an error.
+    pub fn include_files(mut self, root: impl Into<PathBuf>) -> Self {
+        self.file_root = Some(root.into());
+        self
+    }
+
+    /// The largest file `include_files` will read, in bytes. Larger files
+    /// are an error. Defaults to `DEFAULT_MAX_INCLUDE_SIZE`.
+    pub fn max_include_size(mut self, bytes: u64) -> Self {
+        self.max_include_size = bytes;
+        self
+    }
+
+    /// Lets a value be named with `&name value` and repeated with `*name`,
+    /// as in YAML. References are expanded while parsing, so the result is
+    /// an ordinary tree with a copy of the value at each reference.
+    ///
+    /// ```
+    /// use datastruct::options::ParserOptions;
+    ///
+    /// let text = "{\"admin\": &perms [\"read\", \"write\"], \"ops\": *perms}";
+    /// let doc = ParserOptions::new().anchors(true).parse(text).unwrap();
+    ///
+    /// assert_eq!(doc.get_path("ops[1]"), doc.get_path("admin[1]"));
+    /// assert!(ParserOptions::new().parse(text).is_err());
+    /// ```
+    pub fn anchors(mut self, anchors: bool) -> Self {
+        self.anchors = anchors;
+        self
+    }
+
+    /// Reads `name!(...)` literals with `handler`, which gets the text
+    /// between the parentheses and returns the value it stands for. The text
+    /// runs to the matching `)`, so parentheses in it must be balanced.
+    /// `binary!` is built in and can't be replaced. Only `ParserOptions`
+    /// reads these literals; `str::parse` has no handlers and rejects them.
+    ///
+    /// # Panics
+    ///
+    /// If `name` is empty, is `binary`, or has characters other than ASCII
+    /// letters, digits and `_`.
+    ///
+    /// ```
+    /// use datastruct::options::ParserOptions;
+    /// use datastruct::DValue;
+    /// use std::net::IpAddr;
+    ///
+    /// let options = ParserOptions::new().literal("ip", |text| {
+    ///     let ip: IpAddr = text.parse().map_err(|e| format!("{}", e))?;
+    ///     Ok(DValue::String(ip.to_string()))
+    /// });
+    ///
+    /// let doc = options.parse("{\"hosts\": [ip!(10.0.0.1), ip!(::1)]}").unwrap();
+    /// assert_eq!(doc.get_path("hosts[1]"), Some(&DValue::String("::1".to_string())));
+    ///
+    /// let err = options.parse("ip!(10.0.0)").unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid value at 0: invalid ip!: invalid IP address syntax");
+    /// ```
+    pub fn literal(
+        mut self,
+        name: &str,
+        handler: impl Fn(&str) -> Result<DValue, String> + Send + Sync + 'static,
+    ) -> Self {
+        assert!(
+            !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+            "invalid literal name {:?}",
+            name
+        );
+        assert_ne!(name, "binary", "binary! can't be replaced");
+        let literals = self.literals.get_or_insert_with(Default::default);
+        Arc::make_mut(literals).insert(name.to_string(), Arc::new(handler));
+        self
+    }
+
+    /// Parses a whole document, as `str::parse` does.
+    pub fn parse(&self, text: &str) -> Result<DValue, ParseError> {
+        let data = DValue::unwrap_b64(text)?;
+        let mut builder = Builder::new();
+        for event in self.events(&data) {
+            builder
+                .push(event?)
+                .expect("the reader only yields well-formed documents");
+        }
+        Ok(builder
+            .finish()
+            .expect("the reader only ends without an error after a whole value"))
+    }
+
+    /// A pull parser over `text` using these options.
+    pub fn events<'a>(&self, text: &'a str) -> EventReader<'a> {
+        EventReader::new(text)
+            .with_files(self.file_root.clone(), self.max_include_size)
+            .with_literals(self.literals.clone())
+            .anchors(self.anchors)
+    }
+}
+
+impl fmt::Debug for ParserOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut literals: Vec<_> = self.literals.iter().flat_map(|l| l.keys()).collect();
+        literals.sort();
+        f.debug_struct("ParserOptions")
+            .field("file_root", &self.file_root)
+            .field("max_include_size", &self.max_include_size)
+            .field("anchors", &self.anchors)
+            .field("literals", &literals)
+            .finish()
+    }
+}
+
+/// Splits a `name!(...)` literal at the start of `text` into its name, the
+/// text between its parentheses and the text after it.
+pub(crate) fn split_literal(text: &str) -> Option<(&str, &str, &str)> {
+    let end = text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+    let (name, rest) = text.split_at(end);
+    let inner = rest.strip_prefix("!(").filter(|_| !name.is_empty())?;
+
+    let mut depth = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some((name, &inner[..i], &inner[i + 1..])),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads the file at `path` under `root`, describing any failure. Its real
+/// path is checked both before and after opening it, and the open file
+/// checked to be the one at that path, so a symlink swapped in meanwhile
+/// can't lead outside `root`.
+pub(crate) fn read_include(root: &Path, path: &str, limit: u64) -> Result<Binary, String> {
+    let cannot = |e: &dyn fmt::Display| format!("cannot include {}: {}", path, e);
+    let root = root
+        .canonicalize()
+        .map_err(|e| format!("cannot open include root {}: {}", root.display(), e))?;
+    let inside = |path: &Path| -> Result<PathBuf, String> {
+        let full = path.canonicalize().map_err(|e| cannot(&e))?;
+        match full.starts_with(&root) {
+            true => Ok(full),
+            false => Err(cannot(&"outside the include root")),
+        }
+    };
+
+    let full = inside(&root.join(path))?;
+    let file = File::open(&full).map_err(|e| cannot(&e))?;
+    if inside(&full)? != full || !same_file(&file, &full) {
+        return Err(cannot(&"outside the include root"));
+    }
+
+    Binary::from_reader_limited(file, limit).map_err(|e| cannot(&e))
+}
+
+/// Whether `file` is the file at `path`. Only Unix has a file identity to
+/// compare, so elsewhere the path is trusted.
+#[cfg(unix)]
+fn same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(a), Ok(b)) => (a.dev(), a.ino()) == (b.dev(), b.ino()),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(_: &File, _: &Path) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use crate::binary_util::Binary;
+    use crate::options::ParserOptions;
+    use crate::DValue;
+    use std::fs;
+
+    #[test]
+    fn includes_files_under_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let root = dir.join("root");
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::write(root.join("assets/logo.png"), b"\x89PNG").unwrap();
+        fs::write(dir.join("secret"), b"key").unwrap();
+
+        let options = ParserOptions::new().include_files(&root);
+        let doc = options
+            .parse("[binary!(file:assets/logo.png), binary!(aGk=)]")
+            .unwrap();
+        assert_eq!(
+            doc,
+            DValue::List(vec![
+                DValue::BinaryUtil(Binary::new(b"\x89PNG".to_vec())),
+                DValue::BinaryUtil(Binary::new(b"hi".to_vec())),
+            ])
+        );
+
+        let error = options.parse("[1, binary!(file:../secret)]").unwrap_err();
+        assert_eq!(error.position, 4);
+        assert_eq!(
+            error.message,
+            "cannot include ../secret: outside the include root"
+        );
+        let absolute = format!("binary!(file:{})", dir.join("secret").display());
+        assert!(options.parse(&absolute).is_err());
+        assert!(options.parse("binary!(file:missing.png)").is_err());
+
+        // Without a root, file literals are refused rather than read as empty.
+        assert!(ParserOptions::new()
+            .parse("binary!(file:assets/logo.png)")
+            .is_err());
+        assert!("binary!(file:assets/logo.png)".parse::<DValue>().is_err());
+        assert_eq!(
+            ParserOptions::new().parse("{\"a\": [1, (none, true)]}"),
+            "{\"a\": [1, (none, true)]}".parse::<DValue>()
+        );
+
+        let error = options
+            .max_include_size(3)
+            .parse("binary!(file:assets/logo.png)")
+            .unwrap_err();
+        assert_eq!(
+            error.message,
+            "cannot include assets/logo.png: Input exceeds the limit of 3 bytes"
+        );
+    }
+
+    #[test]
+    fn anchors() {
+        let options = ParserOptions::new().anchors(true);
+        let parse = |text: &str| options.parse(text).map(|v| v.to_canonical_string());
+        let message = |text: &str| {
+            let e = options.parse(text).unwrap_err();
+            (e.position, e.message)
+        };
+
+        assert_eq!(
+            parse("[&a {\"k\": (1, &b \"x\")}, *b, *a, &a 2, *a]"),
+            Ok("[{\"k\":(1, \"x\")},\"x\",{\"k\":(1, \"x\")},2,2]".to_string())
+        );
+        // An anchor can hold a reference, and be the whole document.
+        assert_eq!(
+            parse("&root [&x 1, &y [*x, *x]]"),
+            Ok("[1,[1,1]]".to_string())
+        );
+        assert_eq!(message("[*a, &a 1]"), (1, "unknown anchor `a`".to_string()));
+        assert_eq!(message("[&a [*a]]"), (5, "unknown anchor `a`".to_string()));
+        assert_eq!(message("[& 1]"), (2, "expected an anchor name".to_string()));
+
+        // Each level doubles the size of the last.
+        let mut bomb = "{\"v\": [&l0 [1, 1]".to_string();
+        for i in 1..30 {
+            bomb.push_str(&format!(", &l{} [*l{}, *l{}]", i, i - 1, i - 1));
+        }
+        bomb.push_str("]}");
+        assert_eq!(
+            options.parse(&bomb).unwrap_err().message,
+            "references expand to too many values"
+        );
+
+        // Few events, but each copies a long string.
+        let mut strings = format!("[&s0 \"{}\"", "x".repeat(100_000));
+        for i in 1..12 {
+            strings.push_str(&format!(", &s{} [*s{}, *s{}]", i, i - 1, i - 1));
+        }
+        strings.push(']');
+        assert_eq!(
+            options.parse(&strings).unwrap_err().message,
+            "references expand to too many values"
+        );
+    }
+
+    #[test]
+    fn literals() {
+        let options = ParserOptions::new()
+            .literal("money", |text| {
+                let (amount, currency) = text.split_once(' ').ok_or("expected a currency")?;
+                let amount = amount.parse::<f64>().map_err(|e| e.to_string())?;
+                Ok(DValue::Tuple((
+                    Box::new(DValue::Number(amount)),
+                    Box::new(DValue::String(currency.to_string())),
+                )))
+            })
+            .literal("re", |text| Ok(DValue::String(text.to_string())))
+            .anchors(true);
+        let parse = |text: &str| options.parse(text).map(|v| v.to_canonical_string());
+
+        assert_eq!(
+            parse("{\"price\": &p money!(12.5 EUR), \"again\": *p, \"re\": re!(^a(b|c)$)}"),
+            Ok(
+                "{\"again\":(12.5, \"EUR\"),\"price\":(12.5, \"EUR\"),\"re\":\"^a(b|c)$\"}"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            options.parse("[1, money!(12)]").unwrap_err().to_string(),
+            "invalid value at 4: invalid money!: expected a currency"
+        );
+        assert_eq!(
+            options.parse("[ip!(::1)]").unwrap_err().message,
+            "unknown literal `ip!`"
+        );
+        assert!(options.parse("re!(a(b)").is_err());
+        assert!("re!(a)".parse::<DValue>().is_err());
+        assert!(ParserOptions::new().parse("re!(a)").is_err());
+        assert_eq!(
+            format!("{:?}", options),
+            "ParserOptions { file_root: None, max_include_size: 16777216, anchors: true, literals: [\"money\", \"re\"] }"
+        );
+    }
+
+    #[test]
+    fn literal_names() {
+        for name in ["", "my-lit", "a b", "binary"] {
+            let result = std::panic::catch_unwind(|| {
+                ParserOptions::new().literal(name, |_| Ok(DValue::None))
+            });
+            assert!(result.is_err(), "{:?}", name);
+        }
+        ParserOptions::new().literal("Ok_2", |_| Ok(DValue::None));
+    }
+
+    /// `ParserOptions::new()` and `str::parse` must accept the same text and
+    /// read it the same way. Each document is checked as is and with every
+    /// one-character edit from a set of tokens that tend to trip parsers.
+    #[test]
+    fn matches_str_parse() {
+        let docs = [
+            "none",
+            "-1.5e3",
+            "0x1F",
+            "1_000",
+            "-inf",
+            "TRUE",
+            "\"a\\\"b\\u00e9\"",
+            "binary!(aGk=)",
+            "b:WzEsIDJd:",
+            "[]",
+            "[ ]",
+            "[,]",
+            "[1,]",
+            "[1 , 2 ,\n]",
+            "{}",
+            "{ }",
+            "{,}",
+            "{\"a\": 1, \"a\": 2,}",
+            "{\"k\": [(1, none), {\"x\": \"\"}]}",
+            "( true ,[ ] )",
+            " \t\r\n[1]\n",
+            "\u{a0}1",
+            "\u{c}[1]",
+        ];
+        let edits = [' ', ',', ':', '[', ']', '{', '}', '(', ')', '"', '\u{a0}'];
+
+        let check = |text: &str| {
+            assert_eq!(
+                ParserOptions::new().parse(text).ok(),
+                text.parse::<DValue>().ok(),
+                "{:?}",
+                text
+            );
+        };
+        for doc in docs {
+            check(doc);
+            let chars: Vec<char> = doc.chars().collect();
+            for i in 0..=chars.len() {
+                for edit in edits {
+                    let mut inserted = chars.clone();
+                    inserted.insert(i, edit);
+                    check(&inserted.iter().collect::<String>());
+                }
+                if i < chars.len() {
+                    let mut removed = chars.clone();
+                    removed.remove(i);
+                    check(&removed.iter().collect::<String>());
+                }
+            }
+        }
+    }
+}
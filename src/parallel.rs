@@ -0,0 +1,95 @@
+use crate::path::Path;
+use crate::DValue;
+use rayon::prelude::*;
+
+impl DValue {
+    /// Visits the value and all of its descendants, processing the elements
+    /// of lists and dicts in parallel. Nodes are visited in no particular order.
+    pub fn par_walk(&self, f: impl Fn(&Path, &DValue) + Sync + Send) {
+        par_walk(self, &Path::root(), &f);
+    }
+
+    /// Parallel version of `walk_mut`. A node is still visited before its
+    /// children, but siblings are processed in parallel.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let mut value: DValue = (0..1000).map(|n| DValue::Number(n as f64)).collect();
+    ///
+    /// value.par_transform(|_, v| {
+    ///     if let DValue::Number(n) = v {
+    ///         *n *= 2.0;
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(value.weight(), 999_000.0);
+    /// ```
+    pub fn par_transform(&mut self, f: impl Fn(&Path, &mut DValue) + Sync + Send) {
+        par_transform(self, &Path::root(), &f);
+    }
+}
+
+fn par_walk<F>(value: &DValue, path: &Path, f: &F)
+where
+    F: Fn(&Path, &DValue) + Sync + Send,
+{
+    f(path, value);
+
+    match value {
+        DValue::List(list) => list
+            .par_iter()
+            .enumerate()
+            .for_each(|(i, item)| par_walk(item, &path.index(i), f)),
+        DValue::Dict(dict) => dict
+            .par_iter()
+            .for_each(|(key, item)| par_walk(item, &path.key(key), f)),
+        DValue::Tuple(tuple) => {
+            par_walk(&tuple.0, &path.index(0), f);
+            par_walk(&tuple.1, &path.index(1), f);
+        }
+        _ => {}
+    }
+}
+
+fn par_transform<F>(value: &mut DValue, path: &Path, f: &F)
+where
+    F: Fn(&Path, &mut DValue) + Sync + Send,
+{
+    f(path, value);
+
+    match value {
+        DValue::List(list) => list
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(i, item)| par_transform(item, &path.index(i), f)),
+        DValue::Dict(dict) => dict
+            .par_iter_mut()
+            .for_each(|(key, item)| par_transform(item, &path.key(key), f)),
+        DValue::Tuple(tuple) => {
+            par_transform(&mut tuple.0, &path.index(0), f);
+            par_transform(&mut tuple.1, &path.index(1), f);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::DValue;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn par_walk_visits_every_node() {
+        let value = "{\"a\": [1, 2, (3, 4)], \"b\": {\"c\": true}}"
+            .parse::<DValue>()
+            .unwrap();
+        let count = AtomicUsize::new(0);
+
+        value.par_walk(|_, _| {
+            count.fetch_add(1, Ordering::Relaxed);
+        });
+
+        assert_eq!(count.into_inner(), value.walk().count());
+    }
+}
@@ -0,0 +1,819 @@
+use crate::DValue;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// A location inside a `DValue`, written as `users[0].name`.
+///
+/// Keys are separated by `.`, list and tuple positions are written as `[n]`,
+/// and keys containing special characters can be quoted as `["a.b"]`. The
+/// empty path refers to the value itself.
+///
+/// ```
+/// use datastruct::path::Path;
+/// use datastruct::DValue;
+///
+/// let value = "{\"users\": [{\"name\": \"ada\"}]}".parse::<DValue>().unwrap();
+/// let path: Path = "users[0].name".parse().unwrap();
+///
+/// assert_eq!(value.get_path(&path), Some(&DValue::String("ada".to_string())));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Segment {
+    /// A dict key.
+    Key(String),
+
+    /// A list index, or `0`/`1` for the elements of a tuple.
+    Index(usize),
+
+    /// `[*]`: every item of a list or tuple, or every value of a dict. Only
+    /// `DValue::update_path` expands it; lookups find nothing there.
+    Wildcard,
+}
+
+/// Returned when a path string is malformed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathError {
+    pub position: usize,
+    pub message: String,
+}
+
+/// Returned by path-based mutations such as `DValue::incr`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessError {
+    /// The path string could not be parsed.
+    InvalidPath(PathError),
+
+    /// Nothing exists at the path and it cannot be created.
+    NotFound(Path),
+
+    /// The value at the path has the wrong type for the operation.
+    TypeMismatch {
+        path: Path,
+        expected: String,
+        found: String,
+    },
+}
+
+/// Conversion into a `Path`, so path-based methods accept both `&str` and `Path`.
+pub trait IntoPath {
+    fn into_path(self) -> Result<Path, PathError>;
+}
+
+impl Path {
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(path: &str) -> Result<Self, PathError> {
+        let chars: Vec<char> = path.chars().collect();
+        let mut segments = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            match chars[i] {
+                '[' => {
+                    let (segment, next) = parse_bracket(&chars, i)?;
+                    segments.push(segment);
+                    i = next;
+                }
+                '.' if segments.is_empty() => return Err(error(i, "unexpected '.'")),
+                c => {
+                    if c == '.' {
+                        i += 1;
+                    }
+
+                    let start = i;
+                    while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                        i += 1;
+                    }
+
+                    if start == i {
+                        return Err(error(start, "expected a key"));
+                    }
+                    segments.push(Segment::Key(chars[start..i].iter().collect()));
+                }
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn last(&self) -> Option<&Segment> {
+        self.segments.last()
+    }
+
+    pub fn parent(&self) -> Option<Path> {
+        let (_, parent) = self.segments.split_last()?;
+        Some(Self {
+            segments: parent.to_vec(),
+        })
+    }
+
+    pub fn push(&mut self, segment: Segment) {
+        self.segments.push(segment);
+    }
+
+    /// Returns a new path extended by the dict key `key`.
+    pub fn key(&self, key: &str) -> Path {
+        self.child(Segment::Key(key.to_string()))
+    }
+
+    /// Returns a new path extended by the list index `index`.
+    pub fn index(&self, index: usize) -> Path {
+        self.child(Segment::Index(index))
+    }
+
+    fn child(&self, segment: Segment) -> Path {
+        let mut path = self.clone();
+        path.push(segment);
+        path
+    }
+}
+
+fn parse_bracket(chars: &[char], start: usize) -> Result<(Segment, usize), PathError> {
+    let mut i = start + 1;
+
+    let segment = if chars.get(i) == Some(&'*') {
+        i += 1;
+        Segment::Wildcard
+    } else if chars.get(i) == Some(&'"') {
+        let mut key = String::new();
+        i += 1;
+
+        loop {
+            match chars.get(i) {
+                Some('"') => break,
+                Some('\\') => match chars.get(i + 1) {
+                    Some(c) => {
+                        key.push(*c);
+                        i += 2;
+                    }
+                    None => return Err(error(i, "unterminated escape")),
+                },
+                Some(c) => {
+                    key.push(*c);
+                    i += 1;
+                }
+                None => return Err(error(start, "unterminated quoted key")),
+            }
+        }
+        i += 1;
+        Segment::Key(key)
+    } else {
+        let digits = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+
+        let index: String = chars[digits..i].iter().collect();
+        match index.parse() {
+            Ok(index) => Segment::Index(index),
+            Err(_) => return Err(error(digits, "expected an index or a quoted key")),
+        }
+    };
+
+    if chars.get(i) != Some(&']') {
+        return Err(error(i, "expected ']'"));
+    }
+    Ok((segment, i + 1))
+}
+
+fn error(position: usize, message: &str) -> PathError {
+    PathError {
+        position,
+        message: message.to_string(),
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+                Segment::Wildcard => f.write_str("[*]")?,
+                Segment::Key(key) if key.is_empty() || key.contains(['.', '[', ']', '"']) => {
+                    write!(
+                        f,
+                        "[\"{}\"]",
+                        key.replace('\\', "\\\\").replace('"', "\\\"")
+                    )?
+                }
+                Segment::Key(key) if i == 0 => write!(f, "{}", key)?,
+                Segment::Key(key) => write!(f, ".{}", key)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Path {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Path::parse(s)
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid path at {}: {}", self.position, self.message)
+    }
+}
+
+impl core::error::Error for PathError {}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessError::InvalidPath(e) => write!(f, "{}", e),
+            AccessError::NotFound(path) => write!(f, "no value at \"{}\"", path),
+            AccessError::TypeMismatch {
+                path,
+                expected,
+                found,
+            } => write!(f, "expected {} at \"{}\", found {}", expected, path, found),
+        }
+    }
+}
+
+impl core::error::Error for AccessError {}
+
+impl From<PathError> for AccessError {
+    fn from(e: PathError) -> Self {
+        AccessError::InvalidPath(e)
+    }
+}
+
+impl IntoPath for Path {
+    fn into_path(self) -> Result<Path, PathError> {
+        Ok(self)
+    }
+}
+
+impl IntoPath for &Path {
+    fn into_path(self) -> Result<Path, PathError> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoPath for &str {
+    fn into_path(self) -> Result<Path, PathError> {
+        Path::parse(self)
+    }
+}
+
+impl IntoPath for &String {
+    fn into_path(self) -> Result<Path, PathError> {
+        Path::parse(self)
+    }
+}
+
+/// Depth-first iterator over every node of a `DValue`, returned by `DValue::walk`.
+pub struct Walk<'a> {
+    stack: Vec<(Path, &'a DValue)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (Path, &'a DValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (path, value) = self.stack.pop()?;
+
+        // Children are pushed in reverse so they are visited in order.
+        match value {
+            DValue::List(list) => {
+                for (i, item) in list.iter().enumerate().rev() {
+                    self.stack.push((path.index(i), item));
+                }
+            }
+            DValue::Dict(dict) => {
+                let mut keys: Vec<&String> = dict.keys().collect();
+                keys.sort();
+
+                for key in keys.into_iter().rev() {
+                    self.stack.push((path.key(key), &dict[key]));
+                }
+            }
+            DValue::Tuple(tuple) => {
+                self.stack.push((path.index(1), &tuple.1));
+                self.stack.push((path.index(0), &tuple.0));
+            }
+            _ => {}
+        }
+
+        Some((path, value))
+    }
+}
+
+impl DValue {
+    /// Returns the value at `path`, or `None` if it does not exist or the
+    /// path is malformed.
+    pub fn get_path<P: IntoPath>(&self, path: P) -> Option<&DValue> {
+        let path = path.into_path().ok()?;
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| value.child(segment))
+    }
+
+    /// Mutable version of `get_path`.
+    pub fn get_path_mut<P: IntoPath>(&mut self, path: P) -> Option<&mut DValue> {
+        let path = path.into_path().ok()?;
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| value.child_mut(segment))
+    }
+
+    /// Whether this is a dict with an entry under `key`.
+    pub fn has_key(&self, key: &str) -> bool {
+        self.dict_ref().is_some_and(|dict| dict.contains_key(key))
+    }
+
+    /// Whether a value exists at `path`, which is `false` for a malformed
+    /// path.
+    pub fn has_path<P: IntoPath>(&self, path: P) -> bool {
+        self.get_path(path).is_some()
+    }
+
+    /// Whether `value` is an item of this list, a value of this dict or an
+    /// element of this tuple, or is found the same way inside one of them at
+    /// any depth. Values are compared with `==`.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let doc = "{\"tags\": [\"a\", [1, 2]], \"owner\": {\"id\": 7}}".parse::<DValue>().unwrap();
+    ///
+    /// assert!(doc.has_path("owner.id"));
+    /// assert!(doc.get_path("tags").unwrap().contains(&"[1, 2]".parse().unwrap()));
+    /// assert!(doc.get_path("tags").unwrap().contains(&DValue::Number(1.0)));
+    /// assert!(doc.contains(&DValue::Number(7.0)));
+    /// assert!(!doc.contains(&DValue::String("id".to_string())));
+    /// ```
+    pub fn contains(&self, value: &DValue) -> bool {
+        let found = |child: &DValue| child == value || child.contains(value);
+
+        match self {
+            DValue::List(list) => list.iter().any(found),
+            DValue::Dict(dict) => dict.values().any(found),
+            DValue::Tuple(tuple) => found(&tuple.0) || found(&tuple.1),
+            _ => false,
+        }
+    }
+
+    /// Returns the value of the dict entry whose key matches `key` ignoring
+    /// case, or `None` if this isn't a dict or there is no such entry.
+    ///
+    /// An exact match wins. Otherwise, if several keys differ from `key`
+    /// only in case, the least of them is used, so the result doesn't depend
+    /// on the dict's order.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let headers = "{\"Content-Type\": \"text/html\"}".parse::<DValue>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     headers.get_ignore_case("content-type"),
+    ///     Some(&DValue::String("text/html".to_string()))
+    /// );
+    /// assert_eq!(headers.get_path("content-type"), None);
+    /// ```
+    pub fn get_ignore_case(&self, key: &str) -> Option<&DValue> {
+        let DValue::Dict(dict) = self else {
+            return None;
+        };
+        dict.get(key).or_else(|| {
+            dict.iter()
+                .filter(|(k, _)| eq_ignore_case(k, key))
+                .min_by(|a, b| a.0.cmp(b.0))
+                .map(|(_, v)| v)
+        })
+    }
+
+    /// Like `get_path`, with every key along the path matched as by
+    /// `get_ignore_case`.
+    pub fn get_path_ignore_case<P: IntoPath>(&self, path: P) -> Option<&DValue> {
+        let path = path.into_path().ok()?;
+        path.segments
+            .iter()
+            .try_fold(self, |value, segment| match segment {
+                Segment::Key(key) => value.get_ignore_case(key),
+                segment => value.child(segment),
+            })
+    }
+
+    /// Iterates depth-first over the value and all of its descendants, along
+    /// with their paths. Dict entries are visited in key order.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let value = "{\"a\": [1, 2]}".parse::<DValue>().unwrap();
+    /// let paths: Vec<String> = value.walk().map(|(p, _)| p.to_string()).collect();
+    ///
+    /// assert_eq!(paths, vec!["", "a", "a[0]", "a[1]"]);
+    /// ```
+    pub fn walk(&self) -> Walk<'_> {
+        Walk {
+            stack: vec![(Path::root(), self)],
+        }
+    }
+
+    /// Visits the value and all of its descendants depth-first, allowing each
+    /// node to be modified in place. A node is visited before its children,
+    /// so children replaced by `f` are the ones descended into.
+    pub fn walk_mut(&mut self, mut f: impl FnMut(&Path, &mut DValue)) {
+        walk_mut(self, &mut Path::root(), &mut f);
+    }
+
+    /// Calls `f` on every value matching `path`, where `[*]` segments match
+    /// all children of a list, dict or tuple, and returns how many values
+    /// `f` changed. Paths that lead nowhere match nothing.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let mut value = "{\"users\": [{\"active\": false}, {\"active\": true}, {}]}"
+    ///     .parse::<DValue>()
+    ///     .unwrap();
+    ///
+    /// let changed = value.update_path("users[*].active", |v| *v = DValue::Boolean(true));
+    /// assert_eq!(changed, Ok(1));
+    /// assert_eq!(value.get_path("users[0].active"), Some(&DValue::Boolean(true)));
+    /// ```
+    pub fn update_path<P: IntoPath>(
+        &mut self,
+        path: P,
+        mut f: impl FnMut(&mut DValue),
+    ) -> Result<usize, AccessError> {
+        let path = path.into_path()?;
+        Ok(update_matches(self, &path.segments, &mut f))
+    }
+
+    /// Adds `delta` to the number at `path` and returns the new value.
+    ///
+    /// A missing dict key is created with a starting value of `0`, along with
+    /// any missing dicts leading up to it.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let mut value = "{}".parse::<DValue>().unwrap();
+    ///
+    /// value.incr("stats.hits", 1.0).unwrap();
+    /// assert_eq!(value.incr("stats.hits", 2.0), Ok(3.0));
+    /// ```
+    pub fn incr<P: IntoPath>(&mut self, path: P, delta: f64) -> Result<f64, AccessError> {
+        let path = path.into_path()?;
+        let target = self.entry_path(&path)?;
+
+        match target {
+            DValue::None => {
+                *target = DValue::Number(delta);
+                Ok(delta)
+            }
+            DValue::Number(n) => {
+                *n += delta;
+                Ok(*n)
+            }
+            other => Err(AccessError::TypeMismatch {
+                path,
+                expected: "Number".to_string(),
+                found: other.datatype(),
+            }),
+        }
+    }
+
+    /// Resolves `path` for writing. Missing dict keys are inserted as
+    /// `DValue::None`, and `None` values on the way are turned into dicts.
+    /// Nothing is inserted unless the whole path resolves.
+    pub(crate) fn entry_path(&mut self, path: &Path) -> Result<&mut DValue, AccessError> {
+        self.check_entry_path(path)?;
+        let mut value = self;
+
+        for (i, segment) in path.segments.iter().enumerate() {
+            if let (DValue::None, Segment::Key(_)) = (&value, segment) {
+                *value = DValue::Dict(Default::default());
+            }
+
+            value = match (value, segment) {
+                (DValue::Dict(dict), Segment::Key(key)) => {
+                    dict.entry(key.clone()).or_insert(DValue::None)
+                }
+                (value, segment) => match value.child_mut(segment) {
+                    Some(child) => child,
+                    None => {
+                        return Err(AccessError::NotFound(Path {
+                            segments: path.segments[..=i].to_vec(),
+                        }))
+                    }
+                },
+            };
+        }
+
+        Ok(value)
+    }
+
+    /// Checks that `entry_path` would succeed, without changing anything.
+    fn check_entry_path(&self, path: &Path) -> Result<(), AccessError> {
+        // `None` once the path has gone past what exists, where only keys
+        // of dicts still to be created can follow.
+        let mut value = Some(self);
+
+        for (i, segment) in path.segments.iter().enumerate() {
+            value = match (value, segment) {
+                (None | Some(DValue::None), Segment::Key(_)) => None,
+                (Some(DValue::Dict(dict)), Segment::Key(key)) => dict.get(key),
+                (Some(value), segment) if value.child(segment).is_some() => value.child(segment),
+                _ => {
+                    return Err(AccessError::NotFound(Path {
+                        segments: path.segments[..=i].to_vec(),
+                    }))
+                }
+            };
+        }
+        Ok(())
+    }
+
+    /// Removes the dict entry or list item at `path` and returns it.
+    #[cfg(feature = "std")]
+    pub(crate) fn remove_path(&mut self, path: &Path) -> Result<DValue, AccessError> {
+        let not_found = || AccessError::NotFound(path.clone());
+        let parent = path
+            .parent()
+            .and_then(|parent| self.get_path_mut(&parent))
+            .ok_or_else(not_found)?;
+
+        match (parent, path.last()) {
+            (DValue::Dict(dict), Some(Segment::Key(key))) => dict.remove(key).ok_or_else(not_found),
+            (DValue::List(list), Some(Segment::Index(index))) if *index < list.len() => {
+                Ok(list.remove(*index))
+            }
+            _ => Err(not_found()),
+        }
+    }
+
+    pub(crate) fn child(&self, segment: &Segment) -> Option<&DValue> {
+        match (self, segment) {
+            (DValue::Dict(dict), Segment::Key(key)) => dict.get(key),
+            (DValue::List(list), Segment::Index(index)) => list.get(*index),
+            (DValue::Tuple(tuple), Segment::Index(0)) => Some(&tuple.0),
+            (DValue::Tuple(tuple), Segment::Index(1)) => Some(&tuple.1),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn child_mut(&mut self, segment: &Segment) -> Option<&mut DValue> {
+        match (self, segment) {
+            (DValue::Dict(dict), Segment::Key(key)) => dict.get_mut(key),
+            (DValue::List(list), Segment::Index(index)) => list.get_mut(*index),
+            (DValue::Tuple(tuple), Segment::Index(0)) => Some(&mut tuple.0),
+            (DValue::Tuple(tuple), Segment::Index(1)) => Some(&mut tuple.1),
+            _ => None,
+        }
+    }
+}
+
+/// Compares by Unicode lowercasing, not only ASCII, so `"Ä"` matches `"ä"`.
+fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.chars()
+        .flat_map(char::to_lowercase)
+        .eq(b.chars().flat_map(char::to_lowercase))
+}
+
+fn update_matches(
+    value: &mut DValue,
+    segments: &[Segment],
+    f: &mut impl FnMut(&mut DValue),
+) -> usize {
+    let Some((segment, rest)) = segments.split_first() else {
+        let before = value.clone();
+        f(value);
+        return usize::from(*value != before);
+    };
+
+    match (value, segment) {
+        (DValue::List(list), Segment::Wildcard) => list
+            .iter_mut()
+            .map(|item| update_matches(item, rest, f))
+            .sum(),
+        (DValue::Dict(dict), Segment::Wildcard) => dict
+            .values_mut()
+            .map(|item| update_matches(item, rest, f))
+            .sum(),
+        (DValue::Tuple(tuple), Segment::Wildcard) => {
+            update_matches(&mut tuple.0, rest, f) + update_matches(&mut tuple.1, rest, f)
+        }
+        (value, segment) => match value.child_mut(segment) {
+            Some(child) => update_matches(child, rest, f),
+            None => 0,
+        },
+    }
+}
+
+fn walk_mut(value: &mut DValue, path: &mut Path, f: &mut impl FnMut(&Path, &mut DValue)) {
+    f(path, value);
+
+    match value {
+        DValue::List(list) => {
+            for (i, item) in list.iter_mut().enumerate() {
+                path.push(Segment::Index(i));
+                walk_mut(item, path, f);
+                path.segments.pop();
+            }
+        }
+        DValue::Dict(dict) => {
+            let mut entries: Vec<(&String, &mut DValue)> = dict.iter_mut().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+
+            for (key, item) in entries {
+                path.push(Segment::Key(key.clone()));
+                walk_mut(item, path, f);
+                path.segments.pop();
+            }
+        }
+        DValue::Tuple(tuple) => {
+            path.push(Segment::Index(0));
+            walk_mut(&mut tuple.0, path, f);
+            path.segments.pop();
+
+            path.push(Segment::Index(1));
+            walk_mut(&mut tuple.1, path, f);
+            path.segments.pop();
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::path::{Path, Segment};
+    #[cfg(feature = "parser")]
+    use crate::{path::AccessError, DValue};
+
+    #[test]
+    fn parse_and_display() {
+        let path = Path::parse("a.b[2][\"c.d\"].e").unwrap();
+
+        assert_eq!(
+            path.segments(),
+            &[
+                Segment::Key("a".to_string()),
+                Segment::Key("b".to_string()),
+                Segment::Index(2),
+                Segment::Key("c.d".to_string()),
+                Segment::Key("e".to_string()),
+            ]
+        );
+        assert_eq!(path.to_string(), "a.b[2][\"c.d\"].e");
+        assert!(Path::parse("a..b").is_err());
+        assert!(Path::parse("a[x]").is_err());
+        assert!(Path::parse("a[**]").is_err());
+        assert_eq!(Path::parse("a[*].b").unwrap().to_string(), "a[*].b");
+        assert!(Path::parse("").unwrap().is_root());
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn walk_mut_edits_in_place() {
+        let mut value = "{\"a\": [1, 2], \"b\": (3, \"x\")}"
+            .parse::<DValue>()
+            .unwrap();
+
+        value.walk_mut(|_, v| {
+            if let DValue::Number(n) = v {
+                *n *= 10.0;
+            }
+        });
+
+        assert_eq!(value.get_path("a[1]"), Some(&DValue::Number(20.0)));
+        assert_eq!(value.get_path("b[0]"), Some(&DValue::Number(30.0)));
+        assert_eq!(value.walk().count(), 7);
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn update_wildcard_matches() {
+        let mut value = "{\"users\": [{\"n\": 1}, {\"n\": 2}, {\"m\": 3}], \
+                         \"pairs\": {\"a\": (1, 2), \"b\": (3, 4)}}"
+            .parse::<DValue>()
+            .unwrap();
+
+        let double = |v: &mut DValue| {
+            if let DValue::Number(n) = v {
+                *n *= 2.0;
+            }
+        };
+        assert_eq!(value.update_path("users[*].n", double), Ok(2));
+        assert_eq!(value.update_path("pairs[*][*]", double), Ok(4));
+        assert_eq!(value.get_path("users[1].n"), Some(&DValue::Number(4.0)));
+        assert_eq!(value.get_path("pairs.b[1]"), Some(&DValue::Number(8.0)));
+
+        // Only values `f` actually changes are counted.
+        assert_eq!(value.update_path("users[*].n", |_| {}), Ok(0));
+        assert_eq!(value.update_path("missing[*]", double), Ok(0));
+        assert_eq!(value.update_path("users[0]", double), Ok(0));
+        assert!(value.update_path("users[", double).is_err());
+        assert_eq!(value.get_path("users[*].n"), None);
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn ignore_case_lookups() {
+        let value = "{\"Headers\": {\"X-ID\": 1, \"x-id\": 2, \"X-Id\": 3, \"ÄNDERUNG\": 4}, \
+                     \"list\": [{\"Name\": \"a\"}]}"
+            .parse::<DValue>()
+            .unwrap();
+
+        assert_eq!(
+            value.get_path_ignore_case("headers.x-id"),
+            Some(&DValue::Number(2.0))
+        );
+        assert_eq!(
+            value.get_path_ignore_case("HEADERS.x-iD"),
+            Some(&DValue::Number(1.0))
+        );
+        assert_eq!(
+            value.get_path_ignore_case("headers.änderung"),
+            Some(&DValue::Number(4.0))
+        );
+        assert_eq!(
+            value.get_path_ignore_case("LIST[0].name"),
+            Some(&DValue::String("a".to_string()))
+        );
+        assert_eq!(value.get_path_ignore_case("headers.missing"), None);
+        assert_eq!(DValue::Number(1.0).get_ignore_case("a"), None);
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn existence_checks() {
+        let value = "{\"a\": {\"b\": [none, (1, \"x\")]}, \"n\": 1}"
+            .parse::<DValue>()
+            .unwrap();
+
+        assert!(value.has_key("a") && !value.has_key("b"));
+        assert!(!DValue::List(vec![]).has_key("a"));
+        assert!(value.has_path("a.b[0]"));
+        assert!(value.has_path("a.b[1][1]"));
+        assert!(!value.has_path("a.b[2]"));
+        assert!(!value.has_path("a..b"));
+
+        let list = value.get_path("a.b").unwrap();
+        assert!(list.contains(&DValue::None));
+        assert!(list.contains(&"(1, \"x\")".parse().unwrap()));
+        assert!(list.contains(&DValue::Number(1.0)));
+        assert!(!list.contains(&DValue::Number(2.0)));
+        assert!(value
+            .get_path("a.b[1]")
+            .unwrap()
+            .contains(&DValue::String("x".to_string())));
+        assert!(value.contains(&DValue::String("x".to_string())));
+        assert!(value.contains(&list.clone()));
+        assert!(!value.contains(&value));
+        assert!(!DValue::String("abc".to_string()).contains(&DValue::String("a".to_string())));
+    }
+
+    #[cfg(feature = "parser")]
+    #[test]
+    fn incr_counters() {
+        let mut value = "{\"hits\": [5], \"name\": \"x\"}"
+            .parse::<DValue>()
+            .unwrap();
+
+        assert_eq!(value.incr("hits[0]", -2.0), Ok(3.0));
+        assert_eq!(value.incr("a.b.c", 1.0), Ok(1.0));
+        assert_eq!(value.get_path("a.b.c"), Some(&DValue::Number(1.0)));
+        assert!(matches!(
+            value.incr("name", 1.0),
+            Err(AccessError::TypeMismatch { .. })
+        ));
+        assert_eq!(
+            value.incr("hits[3]", 1.0),
+            Err(AccessError::NotFound(Path::parse("hits[3]").unwrap()))
+        );
+
+        // A path that fails partway leaves nothing behind.
+        let before = value.clone();
+        for path in ["new[0]", "new.deeper[0].x", "a.b.c[0]", "hits[0].x"] {
+            assert!(value.incr(path, 1.0).is_err(), "{}", path);
+            assert_eq!(value, before, "{}", path);
+        }
+    }
+}
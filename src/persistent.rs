@@ -0,0 +1,394 @@
+use crate::binary_util::Binary;
+use crate::path::{AccessError, IntoPath, Path, Segment};
+use crate::DValue;
+use imbl::{HashMap, Vector};
+use std::collections::HashMap as StdHashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+/// An immutable `DValue` whose updates return a new version.
+///
+/// Lists and dicts are persistent collections, so a new version shares
+/// every subtree that the update didn't touch with the old one. Cloning is
+/// O(1), and keeping many versions costs memory only for what changed
+/// between them.
+///
+/// ```
+/// use datastruct::persistent::PDValue;
+/// use datastruct::DValue;
+///
+/// let v1 = PDValue::from(&"{\"users\": [\"ada\"], \"config\": {\"debug\": false}}".parse::<DValue>().unwrap());
+/// let v2 = v1.set_path("config.debug", PDValue::Boolean(true)).unwrap();
+///
+/// assert_eq!(v1.get_path("config.debug"), Some(&PDValue::Boolean(false)));
+/// assert_eq!(v2.get_path("config.debug"), Some(&PDValue::Boolean(true)));
+/// assert!(PDValue::ptr_eq(v1.get_path("users").unwrap(), v2.get_path("users").unwrap()));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum PDValue {
+    None,
+    String(String),
+    Number(f64),
+    Boolean(bool),
+    List(Vector<PDValue>),
+    Dict(HashMap<String, PDValue>),
+    Tuple(Arc<(PDValue, PDValue)>),
+    BinaryUtil(Binary),
+}
+
+impl PDValue {
+    /// Returns the value at `path`, or `None` if it does not exist or the
+    /// path is malformed.
+    pub fn get_path<P: IntoPath>(&self, path: P) -> Option<&PDValue> {
+        let path = path.into_path().ok()?;
+        path.segments()
+            .iter()
+            .try_fold(self, |value, segment| value.child(segment))
+    }
+
+    /// Returns a new version with the value at `path` replaced.
+    ///
+    /// Missing dict keys are created, along with any missing dicts leading
+    /// up to them, as `DValue::incr` does.
+    pub fn set_path<P: IntoPath>(&self, path: P, value: PDValue) -> Result<PDValue, AccessError> {
+        let path = path.into_path()?;
+        set_in(self, path.segments(), value, &mut Path::root())
+    }
+
+    /// Returns a new version without the dict entry or list item at `path`.
+    pub fn remove_path<P: IntoPath>(&self, path: P) -> Result<PDValue, AccessError> {
+        let path = path.into_path()?;
+        remove_in(self, path.segments(), &mut Path::root())
+    }
+
+    /// Returns `true` if both values are the same shared collection, which
+    /// means one was derived from the other without touching it.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        match (a, b) {
+            (PDValue::List(a), PDValue::List(b)) => a.ptr_eq(b),
+            (PDValue::Dict(a), PDValue::Dict(b)) => a.ptr_eq(b),
+            (PDValue::Tuple(a), PDValue::Tuple(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn datatype(&self) -> String {
+        match self {
+            PDValue::None => "None",
+            PDValue::String(_) => "String",
+            PDValue::Number(_) => "Number",
+            PDValue::Boolean(_) => "Boolean",
+            PDValue::List(_) => "List",
+            PDValue::Dict(_) => "Dict",
+            PDValue::Tuple(_) => "Tuple",
+            PDValue::BinaryUtil(_) => "Binary",
+        }
+        .to_string()
+    }
+
+    /// Copies the value into a regular `DValue`.
+    pub fn to_dvalue(&self) -> DValue {
+        match self {
+            PDValue::None => DValue::None,
+            PDValue::String(s) => DValue::String(s.clone()),
+            PDValue::Number(n) => DValue::Number(*n),
+            PDValue::Boolean(b) => DValue::Boolean(*b),
+            PDValue::List(items) => DValue::List(items.iter().map(PDValue::to_dvalue).collect()),
+            PDValue::Dict(entries) => DValue::Dict(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.to_dvalue()))
+                    .collect(),
+            ),
+            PDValue::Tuple(pair) => {
+                DValue::Tuple((Box::new(pair.0.to_dvalue()), Box::new(pair.1.to_dvalue())))
+            }
+            PDValue::BinaryUtil(bin) => DValue::BinaryUtil(bin.clone()),
+        }
+    }
+
+    fn child(&self, segment: &Segment) -> Option<&PDValue> {
+        match (self, segment) {
+            (PDValue::Dict(dict), Segment::Key(key)) => dict.get(key),
+            (PDValue::List(list), Segment::Index(index)) => list.get(*index),
+            (PDValue::Tuple(tuple), Segment::Index(0)) => Some(&tuple.0),
+            (PDValue::Tuple(tuple), Segment::Index(1)) => Some(&tuple.1),
+            _ => None,
+        }
+    }
+}
+
+fn set_in(
+    node: &PDValue,
+    segments: &[Segment],
+    value: PDValue,
+    path: &mut Path,
+) -> Result<PDValue, AccessError> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Ok(value);
+    };
+    path.push(segment.clone());
+
+    Ok(match (node, segment) {
+        (PDValue::None, Segment::Key(key)) => PDValue::Dict(HashMap::unit(
+            key.clone(),
+            set_in(&PDValue::None, rest, value, path)?,
+        )),
+        (PDValue::Dict(dict), Segment::Key(key)) => {
+            let child = dict.get(key).unwrap_or(&PDValue::None);
+            PDValue::Dict(dict.update(key.clone(), set_in(child, rest, value, path)?))
+        }
+        (PDValue::List(list), Segment::Index(index)) if *index < list.len() => {
+            PDValue::List(list.update(*index, set_in(&list[*index], rest, value, path)?))
+        }
+        (PDValue::Tuple(tuple), Segment::Index(0)) => PDValue::Tuple(Arc::new((
+            set_in(&tuple.0, rest, value, path)?,
+            tuple.1.clone(),
+        ))),
+        (PDValue::Tuple(tuple), Segment::Index(1)) => PDValue::Tuple(Arc::new((
+            tuple.0.clone(),
+            set_in(&tuple.1, rest, value, path)?,
+        ))),
+        _ => return Err(AccessError::NotFound(path.clone())),
+    })
+}
+
+fn remove_in(
+    node: &PDValue,
+    segments: &[Segment],
+    path: &mut Path,
+) -> Result<PDValue, AccessError> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Err(AccessError::NotFound(path.clone()));
+    };
+    path.push(segment.clone());
+
+    Ok(match (node, segment, rest.is_empty()) {
+        (PDValue::Dict(dict), Segment::Key(key), true) if dict.contains_key(key) => {
+            PDValue::Dict(dict.without(key))
+        }
+        (PDValue::List(list), Segment::Index(index), true) if *index < list.len() => {
+            let mut list = list.clone();
+            list.remove(*index);
+            PDValue::List(list)
+        }
+        (_, _, false) => {
+            let child = node
+                .child(segment)
+                .ok_or_else(|| AccessError::NotFound(path.clone()))?;
+            set_in(
+                node,
+                std::slice::from_ref(segment),
+                remove_in(child, rest, path)?,
+                &mut Path::root(),
+            )?
+        }
+        _ => return Err(AccessError::NotFound(path.clone())),
+    })
+}
+
+/// The result of `DValue::dedupe_subtrees`.
+#[derive(Debug, Clone)]
+pub struct Deduped {
+    pub value: PDValue,
+
+    /// The heap memory, as `DValue::memory_usage` estimates it, that the
+    /// repeated subtrees would have taken as separate copies.
+    pub bytes_saved: usize,
+}
+
+impl DValue {
+    /// Converts the value into a `PDValue` in which identical lists, dicts
+    /// and tuples are stored once and shared, however many times and
+    /// wherever they occur.
+    ///
+    /// Each subtree is hashed from its scalars and the ids of its already
+    /// deduplicated children, so the pass is linear in the size of the
+    /// document. Strings and other scalars aren't shared on their own.
+    ///
+    /// ```
+    /// use datastruct::persistent::PDValue;
+    /// use datastruct::DValue;
+    ///
+    /// let row = "{\"status\": \"active\", \"roles\": [\"read\", \"write\"]}";
+    /// let doc = format!("[{}, {}, {}]", row, row, row).parse::<DValue>().unwrap();
+    ///
+    /// let deduped = doc.dedupe_subtrees();
+    /// let (first, last) = (deduped.value.get_path("[0]").unwrap(), deduped.value.get_path("[2]").unwrap());
+    /// assert!(PDValue::ptr_eq(first, last));
+    /// assert!(deduped.bytes_saved > 0);
+    /// assert_eq!(deduped.value.to_dvalue(), doc);
+    /// ```
+    pub fn dedupe_subtrees(&self) -> Deduped {
+        let mut dedupe = Dedupe::default();
+        let (_, value, bytes_saved) = dedupe.node(self);
+        Deduped { value, bytes_saved }
+    }
+}
+
+/// Maps the key of each distinct subtree seen so far to its id and shared
+/// copy.
+#[derive(Default)]
+struct Dedupe {
+    seen: StdHashMap<String, (usize, PDValue)>,
+}
+
+impl Dedupe {
+    /// Returns the key that identifies `value` inside its parent's key, its
+    /// shared copy and the bytes saved within it.
+    fn node(&mut self, value: &DValue) -> (String, PDValue, usize) {
+        let mut key = String::new();
+        let mut saved = 0;
+        let copy = match value {
+            DValue::List(items) => {
+                key.push('[');
+                let mut list = Vector::new();
+                for item in items {
+                    let (item_key, item, item_saved) = self.node(item);
+                    key.push_str(&item_key);
+                    key.push(',');
+                    list.push_back(item);
+                    saved += item_saved;
+                }
+                PDValue::List(list)
+            }
+            DValue::Dict(entries) => {
+                key.push('{');
+                let mut entries: Vec<_> = entries.iter().collect();
+                entries.sort_unstable_by_key(|(k, _)| *k);
+                let mut dict = HashMap::new();
+                for (k, v) in entries {
+                    let (item_key, item, item_saved) = self.node(v);
+                    // Writing into a String can't fail.
+                    let _ = write!(key, "{:?}:{},", k, item_key);
+                    dict.insert(k.clone(), item);
+                    saved += item_saved;
+                }
+                PDValue::Dict(dict)
+            }
+            DValue::Tuple(pair) => {
+                let (a_key, a, a_saved) = self.node(&pair.0);
+                let (b_key, b, b_saved) = self.node(&pair.1);
+                let _ = write!(key, "({},{})", a_key, b_key);
+                saved += a_saved + b_saved;
+                PDValue::Tuple(Arc::new((a, b)))
+            }
+            scalar => return (scalar.to_canonical_string(), PDValue::from(scalar), 0),
+        };
+
+        let next_id = self.seen.len();
+        match self.seen.get(&key) {
+            // The whole subtree is a repeat, which covers any repeats in it.
+            Some((id, shared)) => (format!("#{}", id), shared.clone(), value.heap_usage()),
+            None => {
+                self.seen.insert(key, (next_id, copy.clone()));
+                (format!("#{}", next_id), copy, saved)
+            }
+        }
+    }
+}
+
+impl From<&DValue> for PDValue {
+    fn from(value: &DValue) -> Self {
+        match value {
+            DValue::None => PDValue::None,
+            DValue::String(s) => PDValue::String(s.clone()),
+            DValue::Number(n) => PDValue::Number(*n),
+            DValue::Boolean(b) => PDValue::Boolean(*b),
+            DValue::List(items) => PDValue::List(items.iter().map(PDValue::from).collect()),
+            DValue::Dict(entries) => PDValue::Dict(
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.clone(), PDValue::from(v)))
+                    .collect(),
+            ),
+            DValue::Tuple(pair) => {
+                PDValue::Tuple(Arc::new((PDValue::from(&*pair.0), PDValue::from(&*pair.1))))
+            }
+            DValue::BinaryUtil(bin) => PDValue::BinaryUtil(bin.clone()),
+        }
+    }
+}
+
+impl From<DValue> for PDValue {
+    fn from(value: DValue) -> Self {
+        PDValue::from(&value)
+    }
+}
+
+impl From<&PDValue> for DValue {
+    fn from(value: &PDValue) -> Self {
+        value.to_dvalue()
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::path::AccessError;
+    use crate::persistent::PDValue;
+    use crate::DValue;
+
+    #[test]
+    fn versions_share_structure() {
+        let v1 = PDValue::from(
+            "{\"a\": {\"list\": [1, 2, 3]}, \"b\": {\"x\": (1, 2)}}"
+                .parse::<DValue>()
+                .unwrap(),
+        );
+        let v2 = v1.set_path("a.list[1]", PDValue::Number(20.0)).unwrap();
+        let v3 = v2.remove_path("a.list[0]").unwrap();
+        let v4 = v3.set_path("c.d", PDValue::Boolean(true)).unwrap();
+
+        assert_eq!(v1.get_path("a.list[1]"), Some(&PDValue::Number(2.0)));
+        assert_eq!(
+            v3.get_path("a.list").unwrap().to_dvalue(),
+            "[20, 3]".parse::<DValue>().unwrap()
+        );
+        assert_eq!(v4.get_path("c.d"), Some(&PDValue::Boolean(true)));
+        assert_eq!(v3.get_path("c"), None);
+        assert!(PDValue::ptr_eq(
+            v1.get_path("b").unwrap(),
+            v4.get_path("b").unwrap()
+        ));
+
+        assert!(matches!(
+            v1.set_path("a.list[9]", PDValue::None),
+            Err(AccessError::NotFound(path)) if path.to_string() == "a.list[9]"
+        ));
+        assert!(v1.remove_path("b.y").is_err());
+        assert_eq!(
+            v1.set_path("b.x[1]", PDValue::None)
+                .unwrap()
+                .to_dvalue()
+                .get_path("b.x[1]"),
+            Some(&DValue::None)
+        );
+    }
+
+    #[test]
+    fn dedupe_shares_repeats() {
+        let doc = "{\"a\": [{\"x\": [1, 2]}, ({\"x\": [1, 2]}, \"s\")], \"b\": [{\"x\": [1, 2]}, ({\"x\": [1, 2]}, \"s\")], \"c\": [[1, 2], [1, \"2\"]]}"
+            .parse::<DValue>()
+            .unwrap();
+        let deduped = doc.dedupe_subtrees();
+        let value = &deduped.value;
+        let at = |path| value.get_path(path).unwrap();
+
+        assert_eq!(value.to_dvalue(), doc);
+        assert!(PDValue::ptr_eq(at("a"), at("b")));
+        assert!(PDValue::ptr_eq(at("a[0]"), at("a[1][0]")));
+        assert!(PDValue::ptr_eq(at("a[0].x"), at("c[0]")));
+        assert!(!PDValue::ptr_eq(at("c[0]"), at("c[1]")));
+
+        // `b` as a whole, plus the inner repeats of `a`, counted once each.
+        let a = doc.get_path("a").unwrap();
+        let inner = doc.get_path("a[0]").unwrap();
+        let list = doc.get_path("c[0]").unwrap();
+        assert_eq!(
+            deduped.bytes_saved,
+            a.heap_usage() + inner.heap_usage() + list.heap_usage()
+        );
+
+        assert_eq!(DValue::Number(1.0).dedupe_subtrees().bytes_saved, 0);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::binary_util::Binary;
+use crate::table::{self, Kind};
+use crate::{DValue, Map};
+use polars::prelude::{Column, DataFrame, DataType, PolarsError};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum FrameError {
+    /// The value is not a list of dicts; holds the offending type.
+    NotTable(String),
+    /// A column has a polars type with no `DValue` equivalent.
+    UnsupportedType(DataType),
+    Polars(PolarsError),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::NotTable(found) => write!(f, "expected a list of dicts, found {}", found),
+            FrameError::UnsupportedType(ty) => write!(f, "unsupported column type {}", ty),
+            FrameError::Polars(err) => write!(f, "polars error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<PolarsError> for FrameError {
+    fn from(err: PolarsError) -> Self {
+        FrameError::Polars(err)
+    }
+}
+
+impl DValue {
+    /// Converts a list of dicts into a polars `DataFrame`.
+    ///
+    /// Every key becomes a column, in sorted order, with nulls for rows
+    /// that lack it. Number, boolean, string and binary columns get the
+    /// matching dtype. Mixed or nested columns become strings in the text
+    /// format, which `from_dataframe` returns as plain strings.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let rows = "[{\"id\": 1, \"name\": \"a\"}, {\"id\": 2}]".parse::<DValue>().unwrap();
+    /// let df = rows.to_dataframe().unwrap();
+    ///
+    /// assert_eq!(df.shape(), (2, 2));
+    /// assert_eq!(DValue::from_dataframe(&df).unwrap().get_path("[1].id"), rows.get_path("[1].id"));
+    /// ```
+    pub fn to_dataframe(&self) -> Result<DataFrame, FrameError> {
+        let (rows, kinds) = table::columns(self).map_err(FrameError::NotTable)?;
+
+        let columns = kinds
+            .into_iter()
+            .map(|(key, kind)| {
+                let cells = table::cells(rows, key);
+                let name = key.into();
+
+                match kind {
+                    Kind::Number => {
+                        Column::new(name, cells.map(|c| c?.as_number()).collect::<Vec<_>>())
+                    }
+                    Kind::Boolean => {
+                        Column::new(name, cells.map(|c| c?.as_bool()).collect::<Vec<_>>())
+                    }
+                    Kind::String | Kind::Empty => Column::new(
+                        name,
+                        cells
+                            .map(|c| match c? {
+                                DValue::String(s) => Some(s.as_str()),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                    Kind::Binary => Column::new(
+                        name,
+                        cells
+                            .map(|c| match c? {
+                                DValue::BinaryUtil(bin) => Some(bin.as_bytes()),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>(),
+                    ),
+                    Kind::Text => Column::new(
+                        name,
+                        cells.map(|c| Some(c?.to_string())).collect::<Vec<_>>(),
+                    ),
+                }
+            })
+            .collect();
+
+        Ok(DataFrame::new(rows.len(), columns)?)
+    }
+
+    /// Converts a `DataFrame` into a list of dicts. Null cells are left out
+    /// of their row, and all integer and float columns become numbers.
+    pub fn from_dataframe(df: &DataFrame) -> Result<DValue, FrameError> {
+        let mut rows = vec![Map::new(); df.height()];
+
+        for column in df.columns() {
+            let name = column.name().to_string();
+            let cells: Vec<Option<DValue>> = match column.dtype() {
+                dtype if dtype.is_primitive_numeric() => column
+                    .cast(&DataType::Float64)?
+                    .f64()?
+                    .iter()
+                    .map(|v| v.map(DValue::Number))
+                    .collect(),
+                DataType::Boolean => column
+                    .bool()?
+                    .iter()
+                    .map(|v| v.map(DValue::Boolean))
+                    .collect(),
+                DataType::String => column
+                    .str()?
+                    .iter()
+                    .map(|v| v.map(|s| DValue::String(s.to_string())))
+                    .collect(),
+                DataType::Binary => column
+                    .binary()?
+                    .iter()
+                    .map(|v| v.map(|b| DValue::BinaryUtil(Binary::new(b.to_vec()))))
+                    .collect(),
+                DataType::Null => vec![None; df.height()],
+                other => return Err(FrameError::UnsupportedType(other.clone())),
+            };
+
+            for (row, cell) in rows.iter_mut().zip(cells) {
+                if let Some(value) = cell {
+                    row.insert(name.clone(), value);
+                }
+            }
+        }
+
+        Ok(DValue::List(rows.into_iter().map(DValue::Dict).collect()))
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::polars::FrameError;
+    use crate::DValue;
+
+    #[test]
+    fn dataframe_round_trip() {
+        let rows = "[{\"n\": 1.5, \"s\": \"a\", \"b\": binary!(aGk=)}, {\"n\": 2, \"ok\": false, \"mixed\": [1]}]".parse::<DValue>().unwrap();
+        let df = rows.to_dataframe().unwrap();
+        let back = DValue::from_dataframe(&df).unwrap();
+
+        for path in ["[0].n", "[0].s", "[0].b", "[1].n", "[1].ok"] {
+            assert_eq!(back.get_path(path), rows.get_path(path), "{}", path);
+        }
+        assert_eq!(
+            back.get_path("[1].mixed").unwrap().as_string().unwrap(),
+            "[1]"
+        );
+        assert_eq!(back.get_path("[1].s"), None);
+
+        assert!(matches!(
+            "{}".parse::<DValue>().unwrap().to_dataframe(),
+            Err(FrameError::NotTable(found)) if found == "Dict"
+        ));
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{DValue, NonFinite};
+use serde_json::value::RawValue;
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgHasArrayType, PgTypeInfo, PgValueRef};
+use sqlx::types::Json;
+use sqlx::{Decode, Encode, Postgres, Type};
+
+// Values are stored as the JSON that `to_json` produces, and read back with
+// `serde_json`, which accepts it. NaN and infinities have no JSON form, so
+// encoding them fails rather than storing a `null` that reads back as `None`.
+
+/// Maps to `JSONB`, and also accepts `JSON` columns.
+impl Type<Postgres> for DValue {
+    fn type_info() -> PgTypeInfo {
+        <Json<DValue> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Json<DValue> as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl PgHasArrayType for DValue {
+    fn array_type_info() -> PgTypeInfo {
+        <Json<DValue> as PgHasArrayType>::array_type_info()
+    }
+
+    fn array_compatible(ty: &PgTypeInfo) -> bool {
+        <Json<DValue> as PgHasArrayType>::array_compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for DValue {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        Json(RawValue::from_string(self.to_json_with(NonFinite::Error)?)?).encode_by_ref(buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for DValue {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        <Json<DValue> as Decode<Postgres>>::decode(value).map(|json| json.0)
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::DValue;
+    use sqlx::encode::IsNull;
+    use sqlx::postgres::PgArgumentBuffer;
+    use sqlx::{Encode, Postgres, Type, TypeInfo};
+
+    #[test]
+    fn encodes_as_jsonb() {
+        assert_eq!(<DValue as Type<Postgres>>::type_info().name(), "JSONB");
+
+        let value = "[1, \"a\", -3e300, 0.1]".parse::<DValue>().unwrap();
+        let mut buf = PgArgumentBuffer::default();
+        let is_null = Encode::<Postgres>::encode_by_ref(&value, &mut buf).unwrap();
+
+        assert!(matches!(is_null, IsNull::No));
+
+        assert_eq!(buf[0], 1);
+        assert_eq!(&buf[1..], value.to_json().as_bytes());
+        // Decoding goes through serde_json, whatever exponent style it uses.
+        assert_eq!(serde_json::from_slice::<DValue>(&buf[1..]).unwrap(), value);
+    }
+
+    #[test]
+    fn refuses_non_finite_numbers() {
+        let value = DValue::List(vec![DValue::Number(1.0), DValue::Number(f64::NAN)]);
+        let mut buf = PgArgumentBuffer::default();
+        let Err(error) = Encode::<Postgres>::encode_by_ref(&value, &mut buf) else {
+            panic!("NaN was encoded");
+        };
+
+        assert!(error.is::<crate::NonFiniteError>());
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use crate::binary_util::Binary;
+use crate::{DValue, Map};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple,
+};
+
+/// How deeply lists, dicts and tuples may nest when converting from Python.
+const MAX_DEPTH: usize = 128;
+
+/// Converts to the matching Python object: `None`, `str`, `float`, `bool`,
+/// `list`, `dict`, a 2-tuple, or `bytes` for binary data.
+impl<'py> IntoPyObject<'py> for &DValue {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(match self {
+            DValue::None => py.None().into_bound(py),
+            DValue::String(s) => PyString::new(py, s).into_any(),
+            DValue::Number(n) => PyFloat::new(py, *n).into_any(),
+            DValue::Boolean(b) => PyBool::new(py, *b).to_owned().into_any(),
+            DValue::List(items) => PyList::new(py, items)?.into_any(),
+            DValue::Dict(entries) => {
+                let dict = PyDict::new(py);
+                for (k, v) in entries {
+                    dict.set_item(k, v)?;
+                }
+                dict.into_any()
+            }
+            DValue::Tuple(pair) => PyTuple::new(py, [&*pair.0, &*pair.1])?.into_any(),
+            DValue::BinaryUtil(bin) => PyBytes::new(py, bin.as_bytes()).into_any(),
+        })
+    }
+}
+
+impl<'py> IntoPyObject<'py> for DValue {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        (&self).into_pyobject(py)
+    }
+}
+
+/// Converts from a Python object. Integers become numbers, `bytes` and
+/// `bytearray` become binary data, and only 2-tuples and dicts with `str`
+/// keys are accepted.
+///
+/// Raises `ValueError` for a container that contains itself, or for
+/// containers nested more than 128 deep.
+impl<'a, 'py> FromPyObject<'a, 'py> for DValue {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        extract(&obj, &mut Vec::new())
+    }
+}
+
+/// Converts `obj`, with `parents` holding the containers it is nested in.
+fn extract(
+    obj: &Bound<'_, PyAny>,
+    parents: &mut Vec<*mut pyo3::ffi::PyObject>,
+) -> PyResult<DValue> {
+    if obj.is_instance_of::<PyList>()
+        || obj.is_instance_of::<PyDict>()
+        || obj.is_instance_of::<PyTuple>()
+    {
+        if parents.contains(&obj.as_ptr()) {
+            return Err(PyValueError::new_err(
+                "cannot convert a container that contains itself",
+            ));
+        }
+        if parents.len() >= MAX_DEPTH {
+            return Err(PyValueError::new_err(format!(
+                "cannot convert containers nested more than {} deep",
+                MAX_DEPTH
+            )));
+        }
+        parents.push(obj.as_ptr());
+        let value = extract_container(obj, parents);
+        parents.pop();
+        return value;
+    }
+
+    if obj.is_none() {
+        return Ok(DValue::None);
+    }
+    // `bool` is a subclass of `int`, so it has to be checked first.
+    if let Ok(b) = obj.cast::<PyBool>() {
+        return Ok(DValue::Boolean(b.is_true()));
+    }
+    if obj.is_instance_of::<PyFloat>() || obj.is_instance_of::<PyInt>() {
+        return Ok(DValue::Number(obj.extract()?));
+    }
+    if let Ok(s) = obj.cast::<PyString>() {
+        return Ok(DValue::String(s.to_str()?.to_string()));
+    }
+    if let Ok(bytes) = obj.cast::<PyBytes>() {
+        return Ok(DValue::BinaryUtil(Binary::new(bytes.as_bytes().to_vec())));
+    }
+    if let Ok(bytes) = obj.cast::<PyByteArray>() {
+        return Ok(DValue::BinaryUtil(Binary::new(bytes.to_vec())));
+    }
+
+    Err(PyTypeError::new_err(format!(
+        "cannot convert {} to DValue",
+        obj.get_type().name()?
+    )))
+}
+
+fn extract_container(
+    obj: &Bound<'_, PyAny>,
+    parents: &mut Vec<*mut pyo3::ffi::PyObject>,
+) -> PyResult<DValue> {
+    if let Ok(list) = obj.cast::<PyList>() {
+        return list
+            .iter()
+            .map(|item| extract(&item, parents))
+            .collect::<PyResult<_>>()
+            .map(DValue::List);
+    }
+    if let Ok(dict) = obj.cast::<PyDict>() {
+        return dict
+            .iter()
+            .map(|(k, v)| Ok((k.extract::<String>()?, extract(&v, parents)?)))
+            .collect::<PyResult<Map<_, _>>>()
+            .map(DValue::Dict);
+    }
+    if let Ok(tuple) = obj.cast::<PyTuple>() {
+        if tuple.len() == 2 {
+            return Ok(DValue::Tuple((
+                Box::new(extract(&tuple.get_item(0)?, parents)?),
+                Box::new(extract(&tuple.get_item(1)?, parents)?),
+            )));
+        }
+    }
+
+    Err(PyTypeError::new_err(format!(
+        "cannot convert {} to DValue",
+        obj.get_type().name()?
+    )))
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::DValue;
+    use pyo3::prelude::*;
+
+    #[test]
+    fn round_trip_through_python() {
+        Python::initialize();
+        Python::attach(|py| {
+            let value = "{\"a\": [1, true, (\"x\", binary!(aGk=))], \"b\": 2.5}"
+                .parse::<DValue>()
+                .unwrap();
+            let obj = (&value).into_pyobject(py).unwrap();
+
+            assert_eq!(obj.get_item("a").unwrap().len().unwrap(), 3);
+            assert_eq!(
+                obj.get_item("a")
+                    .unwrap()
+                    .get_item(2)
+                    .unwrap()
+                    .get_item(1)
+                    .unwrap()
+                    .extract::<Vec<u8>>()
+                    .unwrap(),
+                b"hi"
+            );
+
+            let back: DValue = obj.extract().unwrap();
+            assert_eq!(back.get_path("a"), value.get_path("a"));
+            assert_eq!(back.get_path("b"), Some(&DValue::Number(2.5)));
+
+            let set = py.eval(c"{1, 2}", None, None).unwrap();
+            assert!(set.extract::<DValue>().is_err());
+        });
+    }
+
+    #[test]
+    fn refuses_cycles_and_deep_nesting() {
+        Python::initialize();
+        Python::attach(|py| {
+            let cycle = py
+                .eval(c"(lambda l: l.append(l) or l)([])", None, None)
+                .unwrap();
+            let error = cycle.extract::<DValue>().unwrap_err();
+            assert!(error.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+
+            let deep = py
+                .eval(
+                    c"__import__('functools').reduce(lambda a, _: [a], range(100000), [])",
+                    None,
+                    None,
+                )
+                .unwrap();
+            assert!(deep.extract::<DValue>().is_err());
+
+            let shared = py
+                .eval(c"(lambda t: {'a': t, 'b': [t]})([1])", None, None)
+                .unwrap();
+            let value: DValue = shared.extract().unwrap();
+            assert_eq!(value.get_path("b[0][0]"), Some(&DValue::Number(1.0)));
+        });
+    }
+}
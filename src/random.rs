@@ -0,0 +1,908 @@
+//! Randomized `DValue`s for property tests and fixtures.
+//!
+//! With the `rand` feature, `DValue::random` draws a value from any
+//! `rand::Rng`. The `arbitrary` feature implements `arbitrary::Arbitrary` for
+//! `DValue`, and the `proptest` feature adds `strategy`, which shrinks failing
+//! cases towards smaller documents. All three follow the same `Profile`.
+//! With `rand`, `DSchema::generate` also draws values that match a schema.
+//!
+//! Generated numbers are always finite, strings are mostly printable ASCII
+//! with some arbitrary unicode mixed in, and dicts may end up with fewer
+//! entries than drawn when keys collide.
+
+use crate::binary_util::Binary;
+#[cfg(feature = "rand")]
+use crate::schema::{Constraint, DSchema, Field};
+use crate::DValue;
+#[cfg(any(feature = "rand", feature = "arbitrary"))]
+use crate::Map;
+#[cfg(feature = "rand")]
+use std::convert::Infallible;
+
+/// Bounds on the shape of generated values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// How deeply lists, dicts and tuples may nest. At `0` only scalars are
+    /// generated.
+    pub max_depth: usize,
+
+    /// The most elements in a list, entries in a dict, chars in a string or
+    /// bytes in a binary.
+    pub max_len: usize,
+
+    /// How often each variant is picked relative to the others.
+    pub weights: Weights,
+}
+
+/// Relative weights of the `DValue` variants. A weight of `0` disables the
+/// variant; if every allowed variant is disabled, `DValue::None` is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weights {
+    pub none: u32,
+    pub string: u32,
+    pub number: u32,
+    pub boolean: u32,
+    pub list: u32,
+    pub dict: u32,
+    pub tuple: u32,
+    pub binary: u32,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            max_depth: 3,
+            max_len: 8,
+            weights: Weights::default(),
+        }
+    }
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            none: 1,
+            string: 3,
+            number: 3,
+            boolean: 2,
+            list: 2,
+            dict: 2,
+            tuple: 1,
+            binary: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    None,
+    String,
+    Number,
+    Boolean,
+    List,
+    Dict,
+    Tuple,
+    Binary,
+}
+
+impl Weights {
+    fn kinds(&self, containers: bool) -> Vec<(u32, Kind)> {
+        let mut kinds = vec![
+            (self.none, Kind::None),
+            (self.string, Kind::String),
+            (self.number, Kind::Number),
+            (self.boolean, Kind::Boolean),
+            (self.binary, Kind::Binary),
+        ];
+        if containers {
+            kinds.extend([
+                (self.list, Kind::List),
+                (self.dict, Kind::Dict),
+                (self.tuple, Kind::Tuple),
+            ]);
+        }
+        kinds.retain(|(weight, _)| *weight > 0);
+        kinds
+    }
+}
+
+/// Where the generator gets its randomness from.
+#[cfg(any(feature = "rand", feature = "arbitrary"))]
+trait Source {
+    type Error;
+
+    /// A number in `0..n`, with `n > 0`.
+    fn below(&mut self, n: u32) -> Result<u32, Self::Error>;
+
+    /// A finite number.
+    fn number(&mut self) -> Result<f64, Self::Error>;
+}
+
+#[cfg(any(feature = "rand", feature = "arbitrary"))]
+fn generate<S: Source>(src: &mut S, profile: &Profile, depth: usize) -> Result<DValue, S::Error> {
+    let kinds = profile.weights.kinds(depth < profile.max_depth);
+    let total: u32 = kinds.iter().map(|(weight, _)| weight).sum();
+    if total == 0 {
+        return Ok(DValue::None);
+    }
+
+    let mut pick = src.below(total)?;
+    let kind = kinds
+        .iter()
+        .find(|(weight, _)| {
+            let found = pick < *weight;
+            pick = pick.saturating_sub(*weight);
+            found
+        })
+        .map_or(Kind::None, |(_, kind)| *kind);
+
+    Ok(match kind {
+        Kind::None => DValue::None,
+        Kind::String => DValue::String(string(src, profile.max_len)?),
+        Kind::Number => DValue::Number(src.number()?),
+        Kind::Boolean => DValue::Boolean(src.below(2)? == 1),
+        Kind::Binary => {
+            let len = length(src, profile.max_len)?;
+            let bytes = (0..len)
+                .map(|_| src.below(256).map(|b| b as u8))
+                .collect::<Result<_, _>>()?;
+            DValue::BinaryUtil(Binary::new(bytes))
+        }
+        Kind::List => {
+            let len = length(src, profile.max_len)?;
+            let list = (0..len)
+                .map(|_| generate(src, profile, depth + 1))
+                .collect::<Result<_, _>>()?;
+            DValue::List(list)
+        }
+        Kind::Dict => {
+            let len = length(src, profile.max_len)?;
+            let mut dict = Map::new();
+            for _ in 0..len {
+                let key = string(src, profile.max_len)?;
+                dict.insert(key, generate(src, profile, depth + 1)?);
+            }
+            DValue::Dict(dict)
+        }
+        Kind::Tuple => DValue::Tuple((
+            Box::new(generate(src, profile, depth + 1)?),
+            Box::new(generate(src, profile, depth + 1)?),
+        )),
+    })
+}
+
+#[cfg(any(feature = "rand", feature = "arbitrary"))]
+fn length<S: Source>(src: &mut S, max_len: usize) -> Result<usize, S::Error> {
+    let max_len = u32::try_from(max_len).unwrap_or(u32::MAX - 1);
+    Ok(src.below(max_len + 1)? as usize)
+}
+
+#[cfg(any(feature = "rand", feature = "arbitrary"))]
+fn string<S: Source>(src: &mut S, max_len: usize) -> Result<String, S::Error> {
+    let len = length(src, max_len)?;
+    chars(src, len)
+}
+
+#[cfg(any(feature = "rand", feature = "arbitrary"))]
+fn chars<S: Source>(src: &mut S, len: usize) -> Result<String, S::Error> {
+    (0..len)
+        .map(|_| {
+            // One char in eight is drawn from all of unicode.
+            if src.below(8)? == 0 {
+                let code = src.below(char::MAX as u32 + 1)?;
+                Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+            } else {
+                Ok(char::from(b' ' + src.below(95)? as u8))
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "rand")]
+struct RngSource<'a, R: ?Sized>(&'a mut R);
+
+#[cfg(feature = "rand")]
+impl<R: rand::Rng + ?Sized> Source for RngSource<'_, R> {
+    type Error = Infallible;
+
+    fn below(&mut self, n: u32) -> Result<u32, Infallible> {
+        use rand::RngExt;
+        Ok(self.0.random_range(0..n))
+    }
+
+    fn number(&mut self) -> Result<f64, Infallible> {
+        use rand::RngExt;
+        // Mostly small integers, which is what documents tend to hold.
+        Ok(if self.0.random_bool(0.5) {
+            self.0.random_range(-1000..=1000) as f64
+        } else {
+            self.0.random_range(-1e6..1e6)
+        })
+    }
+}
+
+#[cfg(feature = "rand")]
+impl DValue {
+    /// Generates a random value shaped by `profile`.
+    ///
+    /// ```
+    /// use datastruct::random::Profile;
+    /// use datastruct::DValue;
+    ///
+    /// let profile = Profile { max_depth: 0, ..Profile::default() };
+    /// let value = DValue::random(&mut rand::rng(), &profile);
+    ///
+    /// assert!(!matches!(value, DValue::List(_) | DValue::Dict(_) | DValue::Tuple(_)));
+    /// ```
+    pub fn random<R: rand::Rng + ?Sized>(rng: &mut R, profile: &Profile) -> DValue {
+        match generate(&mut RngSource(rng), profile, 0) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// How many times a string or dict is redrawn to meet its constraints.
+#[cfg(feature = "rand")]
+const ATTEMPTS: usize = 64;
+
+/// The constraints that apply to a schema, gathered from the
+/// `DSchema::Constrained` wrappers around it.
+#[cfg(feature = "rand")]
+#[derive(Clone, Default)]
+struct Bounds<'a>(Vec<&'a Constraint>);
+
+#[cfg(feature = "rand")]
+impl<'a> Bounds<'a> {
+    fn min(&self) -> Option<f64> {
+        self.0
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Min(min) => Some(*min),
+                _ => None,
+            })
+            .reduce(f64::max)
+    }
+
+    fn max(&self) -> Option<f64> {
+        self.0
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Max(max) => Some(*max),
+                _ => None,
+            })
+            .reduce(f64::min)
+    }
+
+    fn min_len(&self) -> usize {
+        self.0
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::MinLength(min) => Some(*min),
+                _ => None,
+            })
+            .fold(0, usize::max)
+    }
+
+    /// The longest length worth generating, which is `profile.max_len`
+    /// unless the constraints say otherwise.
+    fn max_len(&self, profile: &Profile) -> usize {
+        self.0
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::MaxLength(max) => Some(*max),
+                _ => None,
+            })
+            .reduce(usize::min)
+            .unwrap_or(profile.max_len.max(self.min_len()))
+    }
+
+    fn len<S: Source>(&self, src: &mut S, profile: &Profile) -> Result<usize, S::Error> {
+        let max = self.max_len(profile);
+        let min = self.min_len().min(max);
+        Ok(min + length(src, max - min)?)
+    }
+
+    fn patterns(&self) -> Vec<&'a regex::Regex> {
+        self.0
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::Pattern(pattern) => Some(&pattern.0),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn key_patterns(&self) -> Vec<&'a regex::Regex> {
+        self.0
+            .iter()
+            .filter_map(|c| match c {
+                Constraint::KeyPattern(pattern) => Some(&pattern.0),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn allows(&self, value: &DValue) -> bool {
+        let mut errors = Vec::new();
+        for constraint in &self.0 {
+            constraint.check(value, &crate::path::Path::root(), &mut errors);
+        }
+        errors.is_empty()
+    }
+}
+
+#[cfg(feature = "rand")]
+fn from_schema<'a, S: Source>(
+    src: &mut S,
+    schema: &'a DSchema,
+    mut bounds: Bounds<'a>,
+    profile: &Profile,
+    depth: usize,
+) -> Result<DValue, S::Error> {
+    Ok(match schema {
+        DSchema::Constrained(inner, constraints) => {
+            bounds.0.extend(constraints);
+            from_schema(src, inner, bounds, profile, depth)?
+        }
+        DSchema::Union(options) if options.is_empty() => DValue::None,
+        DSchema::Union(options) => {
+            let pick = src.below(u32::try_from(options.len()).unwrap_or(u32::MAX))?;
+            from_schema(src, &options[pick as usize], bounds, profile, depth)?
+        }
+        // None of the constraints apply to `DValue::None`.
+        DSchema::Any => Some(generate(src, profile, depth)?)
+            .filter(|value| bounds.allows(value))
+            .unwrap_or(DValue::None),
+        DSchema::None => DValue::None,
+        DSchema::String => DValue::String(text(src, &bounds.patterns(), &bounds, profile)?),
+        DSchema::Number => DValue::Number(number(src, &bounds)?),
+        DSchema::Boolean => DValue::Boolean(src.below(2)? == 1),
+        DSchema::Binary => {
+            let len = length(src, profile.max_len)?;
+            let bytes = (0..len)
+                .map(|_| src.below(256).map(|b| b as u8))
+                .collect::<Result<_, _>>()?;
+            DValue::BinaryUtil(Binary::new(bytes))
+        }
+        DSchema::List(item) => {
+            let len = bounds.len(src, profile)?;
+            let list = (0..len)
+                .map(|_| from_schema(src, item, Bounds::default(), profile, depth + 1))
+                .collect::<Result<_, _>>()?;
+            DValue::List(list)
+        }
+        DSchema::Tuple(first, second) => DValue::Tuple((
+            Box::new(from_schema(
+                src,
+                first,
+                Bounds::default(),
+                profile,
+                depth + 1,
+            )?),
+            Box::new(from_schema(
+                src,
+                second,
+                Bounds::default(),
+                profile,
+                depth + 1,
+            )?),
+        )),
+        DSchema::Dict(fields) => DValue::Dict(dict(src, fields, &bounds, profile, depth)?),
+        DSchema::Map(values) => {
+            let len = bounds.len(src, profile)?;
+            let patterns = bounds.key_patterns();
+            let mut map = Map::new();
+            for _ in 0..ATTEMPTS {
+                if map.len() >= len {
+                    break;
+                }
+                let key = text(src, &patterns, &Bounds::default(), profile)?;
+                let value = from_schema(src, values, Bounds::default(), profile, depth + 1)?;
+                map.insert(key, value);
+            }
+            DValue::Dict(map)
+        }
+    })
+}
+
+/// Draws a number within `Min` and `Max`, a whole one half the time.
+#[cfg(feature = "rand")]
+fn number<S: Source>(src: &mut S, bounds: &Bounds) -> Result<f64, S::Error> {
+    let n = src.number()?;
+    Ok(match (bounds.min(), bounds.max()) {
+        (None, None) => n,
+        (Some(min), None) => min + n.abs(),
+        (None, Some(max)) => max - n.abs(),
+        (Some(min), Some(max)) if min < max => {
+            let (low, high) = (min.ceil(), max.floor());
+            if low <= high && high - low < f64::from(u32::MAX) && src.below(2)? == 0 {
+                low + f64::from(src.below((high - low) as u32 + 1)?)
+            } else {
+                let fraction = f64::from(src.below(u32::MAX)?) / f64::from(u32::MAX - 1);
+                (min + (max - min) * fraction).clamp(min, max)
+            }
+        }
+        (Some(min), Some(_)) => min,
+    })
+}
+
+/// Draws a string matching every pattern, generated from the first one,
+/// redrawing it until it also meets the length bounds.
+#[cfg(feature = "rand")]
+fn text<S: Source>(
+    src: &mut S,
+    patterns: &[&regex::Regex],
+    bounds: &Bounds,
+    profile: &Profile,
+) -> Result<String, S::Error> {
+    let Some(pattern) = patterns.first() else {
+        let len = bounds.len(src, profile)?;
+        return chars(src, len);
+    };
+
+    let hir = regex_syntax::Parser::new().parse(pattern.as_str()).ok();
+    let cap = u32::try_from(bounds.max_len(profile)).unwrap_or(u32::MAX);
+    let mut text = String::new();
+    for _ in 0..ATTEMPTS {
+        text = match &hir {
+            Some(hir) => {
+                let mut bytes = Vec::new();
+                matching(src, hir, cap, &mut bytes)?;
+                String::from_utf8(bytes).unwrap_or_default()
+            }
+            None => string(src, profile.max_len)?,
+        };
+        let matched = patterns.iter().all(|p| p.is_match(&text));
+        if matched && bounds.allows(&DValue::String(text.clone())) {
+            break;
+        }
+    }
+    Ok(text)
+}
+
+/// Appends a string matched by `hir`. Unbounded repetitions repeat at most
+/// `cap` times more than their minimum, and assertions such as `^` or `\b`
+/// are skipped, so the result is only likely to match.
+#[cfg(feature = "rand")]
+fn matching<S: Source>(
+    src: &mut S,
+    hir: &regex_syntax::hir::Hir,
+    cap: u32,
+    out: &mut Vec<u8>,
+) -> Result<(), S::Error> {
+    use regex_syntax::hir::{Class, ClassUnicode, ClassUnicodeRange, HirKind};
+
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.extend_from_slice(&literal.0),
+        HirKind::Class(Class::Unicode(class)) => {
+            // Mostly printable ASCII, as with unconstrained strings.
+            let mut printable = ClassUnicode::new([ClassUnicodeRange::new(' ', '~')]);
+            printable.intersect(class);
+            let ranges = match printable.ranges() {
+                [] => class.ranges(),
+                ranges if src.below(8)? != 0 => ranges,
+                _ => class.ranges(),
+            };
+            if !ranges.is_empty() {
+                let range = ranges[src.below(ranges.len() as u32)? as usize];
+                let span = u32::from(range.end()) - u32::from(range.start());
+                let code = u32::from(range.start()) + src.below(span.saturating_add(1))?;
+                let c = char::from_u32(code).unwrap_or(range.start());
+                out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges = class.ranges();
+            if !ranges.is_empty() {
+                let range = ranges[src.below(ranges.len() as u32)? as usize];
+                let span = u32::from(range.end() - range.start()) + 1;
+                out.push(range.start() + src.below(span)? as u8);
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let extra = repetition
+                .max
+                .map_or(cap, |max| max - repetition.min)
+                .min(cap);
+            let count = repetition.min + src.below(extra.saturating_add(1))?;
+            for _ in 0..count {
+                matching(src, &repetition.sub, cap, out)?;
+            }
+        }
+        HirKind::Capture(capture) => matching(src, &capture.sub, cap, out)?,
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                matching(src, hir, cap, out)?;
+            }
+        }
+        HirKind::Alternation(hirs) => {
+            let pick = src.below(hirs.len() as u32)?;
+            matching(src, &hirs[pick as usize], cap, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fills a dict with its required fields, some of its optional ones and,
+/// up to the length drawn from the bounds, extra keys. Keys named by a
+/// `Requires` constraint are added last.
+#[cfg(feature = "rand")]
+fn dict<S: Source>(
+    src: &mut S,
+    fields: &Map<String, Field>,
+    bounds: &Bounds,
+    profile: &Profile,
+    depth: usize,
+) -> Result<Map<String, DValue>, S::Error> {
+    let len = bounds.len(src, profile)?;
+    let field = |src: &mut S, key: &str| match fields.get(key) {
+        Some(field) => from_schema(src, &field.schema, Bounds::default(), profile, depth + 1),
+        None => generate(src, profile, depth + 1),
+    };
+
+    // Sorted so a seeded RNG gives the same dict every time.
+    let mut names: Vec<&String> = fields.keys().collect();
+    names.sort();
+
+    let mut dict = Map::new();
+    for name in names {
+        if fields[name].required || (dict.len() < len && src.below(2)? == 0) {
+            let value = field(src, name)?;
+            dict.insert(name.clone(), value);
+        }
+    }
+
+    let patterns = bounds.key_patterns();
+    for _ in 0..ATTEMPTS {
+        if dict.len() >= len {
+            break;
+        }
+        let key = text(src, &patterns, &Bounds::default(), profile)?;
+        if !fields.contains_key(&key) && !dict.contains_key(&key) {
+            let value = field(src, &key)?;
+            dict.insert(key, value);
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for constraint in &bounds.0 {
+            let Constraint::Requires(key, required) = constraint else {
+                continue;
+            };
+            if !dict.contains_key(key) {
+                continue;
+            }
+            for other in required {
+                if !dict.contains_key(other) {
+                    let value = field(src, other)?;
+                    dict.insert(other.clone(), value);
+                    changed = true;
+                }
+            }
+        }
+    }
+    Ok(dict)
+}
+
+#[cfg(feature = "rand")]
+impl DSchema {
+    /// Generates a random value that matches the schema, for fixtures and
+    /// load tests.
+    ///
+    /// Numbers stay within `Min` and `Max`, strings, lists and dicts within
+    /// `MinLength` and `MaxLength`, and strings are generated from their
+    /// `Pattern`. Required fields are always present and optional ones half
+    /// the time. Where the schema leaves sizes open, `Profile::default()`
+    /// applies. Constraints that contradict each other, or patterns relying
+    /// on assertions such as `\b`, can still yield a value that fails
+    /// validation.
+    ///
+    /// ```
+    /// use datastruct::schema::{Constraint, DSchema, Field};
+    ///
+    /// let schema = DSchema::dict([
+    ///     ("id", Field::required(DSchema::Number.min(1.0).max(1000.0))),
+    ///     ("email", Field::required(DSchema::String.with(
+    ///         Constraint::pattern("^[a-z]{3,8}@example\\.com$").unwrap(),
+    ///     ))),
+    ///     ("tags", Field::optional(DSchema::list(DSchema::String).max_len(3))),
+    /// ]);
+    ///
+    /// for _ in 0..100 {
+    ///     let value = schema.generate(&mut rand::rng());
+    ///     assert!(schema.validate(&value).is_ok());
+    /// }
+    /// ```
+    pub fn generate<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> DValue {
+        let profile = Profile::default();
+        match from_schema(&mut RngSource(rng), self, Bounds::default(), &profile, 0) {
+            Ok(value) => value,
+            Err(never) => match never {},
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Source for arbitrary::Unstructured<'a> {
+    type Error = arbitrary::Error;
+
+    fn below(&mut self, n: u32) -> arbitrary::Result<u32> {
+        self.int_in_range(0..=n - 1)
+    }
+
+    fn number(&mut self) -> arbitrary::Result<f64> {
+        let number: f64 = self.arbitrary()?;
+        Ok(if number.is_finite() { number } else { 0.0 })
+    }
+}
+
+/// Uses `Profile::default()`. When the input runs out, the remaining values
+/// come out as small as possible rather than failing.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for DValue {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        generate(u, &Profile::default(), 0)
+    }
+}
+
+/// A proptest strategy producing values shaped by `profile`.
+///
+/// ```
+/// use datastruct::random::{strategy, Profile};
+/// use proptest::prelude::*;
+///
+/// proptest!(|(value in strategy(&Profile::default()))| {
+///     prop_assert_eq!(value.clone(), value);
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+pub fn strategy(profile: &Profile) -> proptest::strategy::BoxedStrategy<DValue> {
+    use proptest::collection::{hash_map, vec};
+    use proptest::prelude::*;
+    use proptest::strategy::Union;
+
+    let max_len = profile.max_len;
+    let text = move || {
+        let ascii = (b' '..=b'~').prop_map(char::from);
+        let char = prop_oneof![7 => ascii, 1 => any::<char>()];
+        vec(char, 0..=max_len).prop_map(String::from_iter)
+    };
+    let number = prop_oneof![
+        (-1000..=1000).prop_map(f64::from),
+        any::<f64>().prop_filter("finite", |n| n.is_finite()),
+    ];
+
+    let weights = profile.weights;
+    let leaves: Vec<(u32, BoxedStrategy<DValue>)> = weights
+        .kinds(false)
+        .into_iter()
+        .map(|(weight, kind)| {
+            let strategy = match kind {
+                Kind::String => text().prop_map(DValue::String).boxed(),
+                Kind::Number => number.clone().prop_map(DValue::Number).boxed(),
+                Kind::Boolean => any::<bool>().prop_map(DValue::Boolean).boxed(),
+                Kind::Binary => vec(any::<u8>(), 0..=max_len)
+                    .prop_map(|bytes| DValue::BinaryUtil(Binary::new(bytes)))
+                    .boxed(),
+                _ => Just(DValue::None).boxed(),
+            };
+            (weight, strategy)
+        })
+        .collect();
+    let leaf = match leaves.is_empty() {
+        true => Just(DValue::None).boxed(),
+        false => Union::new_weighted(leaves).boxed(),
+    };
+
+    let containers = weights.kinds(true).len() > weights.kinds(false).len();
+    if profile.max_depth == 0 || !containers {
+        return leaf;
+    }
+
+    let depth = u32::try_from(profile.max_depth).unwrap_or(u32::MAX);
+    let size = u32::try_from(max_len).unwrap_or(u32::MAX);
+    leaf.prop_recursive(depth, size.saturating_mul(depth), size, move |inner| {
+        let nodes: Vec<(u32, BoxedStrategy<DValue>)> = [
+            (
+                weights.list,
+                vec(inner.clone(), 0..=max_len)
+                    .prop_map(DValue::List)
+                    .boxed(),
+            ),
+            (
+                weights.dict,
+                hash_map(text(), inner.clone(), 0..=max_len)
+                    .prop_map(|dict| DValue::Dict(dict.into_iter().collect()))
+                    .boxed(),
+            ),
+            (
+                weights.tuple,
+                (inner.clone(), inner)
+                    .prop_map(|(a, b)| DValue::Tuple((Box::new(a), Box::new(b))))
+                    .boxed(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(weight, _)| *weight > 0)
+        .collect();
+        Union::new_weighted(nodes)
+    })
+    .boxed()
+}
+
+#[cfg(all(test, any(feature = "rand", feature = "arbitrary")))]
+mod test {
+    use super::{generate, Profile, Source, Weights};
+    use crate::DValue;
+    use std::convert::Infallible;
+
+    /// Cycles through a fixed sequence, so tests don't depend on a RNG.
+    struct Sequence(Vec<u32>, usize);
+
+    impl Source for Sequence {
+        type Error = Infallible;
+
+        fn below(&mut self, n: u32) -> Result<u32, Infallible> {
+            self.1 += 1;
+            Ok(self.0[(self.1 - 1) % self.0.len()] % n)
+        }
+
+        fn number(&mut self) -> Result<f64, Infallible> {
+            Ok(self.1 as f64)
+        }
+    }
+
+    #[test]
+    fn respects_bounds() {
+        let profile = Profile {
+            max_depth: 2,
+            max_len: 3,
+            weights: Weights {
+                none: 0,
+                string: 1,
+                number: 0,
+                boolean: 0,
+                list: 5,
+                dict: 0,
+                tuple: 0,
+                binary: 0,
+            },
+        };
+
+        for seed in 0..50 {
+            let mut src = Sequence(vec![seed, seed * 7 + 3, seed * 13 + 1], 0);
+            let value = generate(&mut src, &profile, 0).unwrap();
+
+            assert!(value.depth() <= 2);
+            for (_, node) in value.walk() {
+                match node {
+                    DValue::List(list) => assert!(list.len() <= 3),
+                    DValue::String(s) => assert!(s.chars().count() <= 3),
+                    other => panic!("unexpected {}", other.datatype()),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn all_weights_zero() {
+        let profile = Profile {
+            weights: Weights {
+                none: 0,
+                string: 0,
+                number: 0,
+                boolean: 0,
+                list: 0,
+                dict: 0,
+                tuple: 0,
+                binary: 0,
+            },
+            ..Profile::default()
+        };
+        let mut src = Sequence(vec![1], 0);
+
+        assert_eq!(generate(&mut src, &profile, 0), Ok(DValue::None));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn seeded_random() {
+        use rand::SeedableRng;
+
+        let profile = Profile::default();
+        let a = DValue::random(&mut rand::rngs::StdRng::seed_from_u64(7), &profile);
+        let b = DValue::random(&mut rand::rngs::StdRng::seed_from_u64(7), &profile);
+
+        assert_eq!(a, b);
+        assert!(a.depth() <= profile.max_depth);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn schema_values_validate() {
+        use crate::schema::{Constraint, DSchema, Field};
+        use rand::SeedableRng;
+
+        let schema = DSchema::dict([
+            ("id", Field::required(DSchema::Number.min(1.0).max(10.0))),
+            ("score", Field::required(DSchema::Number.min(0.5).max(0.75))),
+            (
+                "code",
+                Field::required(
+                    DSchema::String.with(Constraint::pattern("^[A-Z]{2}-\\d+$").unwrap()),
+                ),
+            ),
+            (
+                "tags",
+                Field::optional(
+                    DSchema::list(DSchema::String.min_len(1))
+                        .min_len(2)
+                        .max_len(4),
+                ),
+            ),
+            (
+                "labels",
+                Field::optional(
+                    DSchema::map(DSchema::Boolean)
+                        .max_len(3)
+                        .with(Constraint::key_pattern("^(en|fr|de)$").unwrap()),
+                ),
+            ),
+            (
+                "pair",
+                Field::optional(DSchema::tuple(DSchema::None, DSchema::Binary)),
+            ),
+            (
+                "either",
+                Field::required(DSchema::Union(vec![DSchema::Boolean, DSchema::Any])),
+            ),
+            ("user", Field::optional(DSchema::String)),
+        ])
+        .min_len(5)
+        .requires("user", ["password"]);
+
+        for seed in 0..200 {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            let value = schema.generate(&mut rng);
+
+            assert_eq!(schema.validate(&value), Ok(()), "{}", value);
+            assert!(value.dict_ref().unwrap().len() >= 5);
+            if value.get_path("user").is_some() {
+                assert!(value.get_path("password").is_some());
+            }
+        }
+
+        let a = schema.generate(&mut rand::rngs::StdRng::seed_from_u64(3));
+        let b = schema.generate(&mut rand::rngs::StdRng::seed_from_u64(3));
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_exhausted_input() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut u = Unstructured::new(&[]);
+        assert_eq!(DValue::arbitrary(&mut u), Ok(DValue::None));
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let mut u = Unstructured::new(&bytes);
+        assert!(DValue::arbitrary(&mut u).is_ok());
+    }
+
+    #[cfg(all(feature = "proptest", feature = "parser"))]
+    proptest::proptest! {
+        #[test]
+        fn canonical_round_trip(value in super::strategy(&Profile::default())) {
+            let text = value.to_canonical_string();
+            let parsed = text.parse::<DValue>().unwrap();
+
+            proptest::prop_assert_eq!(parsed.to_canonical_string(), text);
+            proptest::prop_assert_eq!(parsed, value);
+        }
+    }
+}
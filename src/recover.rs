@@ -0,0 +1,306 @@
+use crate::{DValue, Map, ParseError, TextError, ValueParser};
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use nom::bytes::complete::tag;
+
+impl DValue {
+    /// Parses as much of `text` as possible, for input that may be broken,
+    /// such as a document that is still being typed.
+    ///
+    /// Where the text is invalid a `ParseError` is recorded and parsing picks
+    /// up again at the next `,` or closing bracket. A value that can't be read
+    /// becomes `DValue::None`, and an unclosed list, dict or tuple keeps what
+    /// was read before the input ran out. Valid text gives the same value as
+    /// `str::parse` and no errors.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let (value, errors) = DValue::parse_recovering("{\"a\": [1, @, 3], \"b\": tru");
+    ///
+    /// assert_eq!(value.to_canonical_string(), "{\"a\":[1,none,3],\"b\":none}");
+    /// assert_eq!(errors[0].position, 10);
+    /// assert_eq!(errors[0].message, "invalid value");
+    /// assert_eq!(errors.len(), 3);
+    /// ```
+    pub fn parse_recovering(text: &str) -> (DValue, Vec<ParseError>) {
+        let mut parser = Recover {
+            len: text.len(),
+            errors: Vec::new(),
+        };
+
+        let (rest, value) = parser.value(text);
+        let rest = rest.trim_start();
+        if !rest.is_empty() {
+            parser.error(rest, "unexpected trailing characters");
+        }
+        (value, parser.errors)
+    }
+}
+
+struct Recover {
+    /// The length of the whole input, to turn what is left into a position.
+    len: usize,
+    errors: Vec<ParseError>,
+}
+
+impl Recover {
+    fn error(&mut self, rest: &str, message: &str) {
+        self.errors.push(ParseError {
+            position: self.len - rest.len(),
+            message: message.to_string(),
+        });
+    }
+
+    fn value<'i>(&mut self, msg: &'i str) -> (&'i str, DValue) {
+        let msg = msg.trim_start();
+
+        match msg.chars().next() {
+            None => {
+                self.error(msg, "unexpected end of input in value");
+                (msg, DValue::None)
+            }
+            Some('[') => {
+                let (rest, items) = self.sequence(&msg[1..], ']', "list", |p, m| p.value(m));
+                (rest, DValue::List(items))
+            }
+            Some('{') => {
+                let (rest, entries) = self.sequence(&msg[1..], '}', "object", |p, m| p.entry(m));
+                let dict: Map<String, DValue> = entries.into_iter().flatten().collect();
+                (rest, DValue::Dict(dict))
+            }
+            Some('(') => {
+                let (rest, mut items) = self.sequence(&msg[1..], ')', "tuple", |p, m| p.value(m));
+                if items.len() != 2 {
+                    self.error(msg, "a tuple needs exactly two items");
+                }
+                items.resize(2, DValue::None);
+                let second = items.pop().unwrap_or(DValue::None);
+                let first = items.pop().unwrap_or(DValue::None);
+                (rest, DValue::Tuple((Box::new(first), Box::new(second))))
+            }
+            Some(',' | ']' | '}' | ')') => {
+                self.error(msg, "expected a value");
+                (msg, DValue::None)
+            }
+            Some(_) => self.scalar(msg),
+        }
+    }
+
+    fn scalar<'i>(&mut self, msg: &'i str) -> (&'i str, DValue) {
+        let parsed = if let Ok((rest, _)) = tag::<_, _, TextError>("none")(msg) {
+            Ok((rest, DValue::None))
+        } else if let Ok((rest, n)) = ValueParser::parse_num(msg) {
+            Ok((rest, DValue::Number(n)))
+        } else if let Ok((rest, b)) = ValueParser::parse_bool(msg) {
+            Ok((rest, DValue::Boolean(b)))
+        } else if let Ok((rest, s)) = ValueParser::parse_string(msg) {
+            Ok((rest, DValue::String(s.into_owned())))
+        } else {
+            ValueParser::parse_bin(msg).map(|(rest, bin)| (rest, DValue::BinaryUtil(bin)))
+        };
+
+        match parsed {
+            Ok(parsed) => parsed,
+            Err(_) if msg.starts_with('"') => {
+                self.error(msg, "invalid string");
+                (skip_string(msg), DValue::None)
+            }
+            Err(_) => {
+                self.error(msg, "invalid value");
+                (skip(msg), DValue::None)
+            }
+        }
+    }
+
+    /// Reads a dict entry. A missing key drops the entry; a missing value
+    /// keeps the key with `DValue::None`.
+    fn entry<'i>(&mut self, msg: &'i str) -> (&'i str, Option<(String, DValue)>) {
+        let msg = msg.trim_start();
+        let Ok((rest, key)) = ValueParser::parse_string(msg) else {
+            self.error(msg, "expected a string key");
+            return (skip(msg), None);
+        };
+        let key = key.into_owned();
+
+        let rest = rest.trim_start();
+        match rest.strip_prefix(':') {
+            Some(rest) => {
+                let (rest, value) = self.value(rest);
+                (rest, Some((key, value)))
+            }
+            None => {
+                self.error(rest, "expected `:`");
+                (skip(rest), Some((key, DValue::None)))
+            }
+        }
+    }
+
+    /// Reads the items of a list, dict or tuple after its opening bracket,
+    /// up to and including `close`.
+    fn sequence<'i, T>(
+        &mut self,
+        mut msg: &'i str,
+        close: char,
+        context: &str,
+        mut item: impl FnMut(&mut Self, &'i str) -> (&'i str, T),
+    ) -> (&'i str, Vec<T>) {
+        let mut items = Vec::new();
+
+        loop {
+            msg = msg.trim_start();
+            match msg.chars().next() {
+                None => {
+                    self.error(
+                        msg,
+                        &alloc::format!("unexpected end of input in {}", context),
+                    );
+                    return (msg, items);
+                }
+                Some(c) if c == close => return (&msg[1..], items),
+                Some(']' | '}' | ')') => {
+                    // A bracket that closes something else: assume this one
+                    // was left open and let the caller deal with it.
+                    self.error(msg, &alloc::format!("unclosed {}", context));
+                    return (msg, items);
+                }
+                _ => {}
+            }
+
+            let (rest, value) = item(self, msg);
+            items.push(value);
+
+            msg = rest.trim_start();
+            match msg.chars().next() {
+                Some(',') => msg = &msg[1..],
+                Some(c) if c == close => {}
+                None | Some(']' | '}' | ')') => {}
+                Some(_) => {
+                    self.error(msg, &alloc::format!("expected `,` or `{}`", close));
+                    msg = skip(msg);
+                    msg = msg.strip_prefix(',').unwrap_or(msg);
+                }
+            }
+        }
+    }
+}
+
+/// Skips to the next `,` or closing bracket outside of nested brackets and
+/// strings, always moving past at least one char.
+fn skip(msg: &str) -> &str {
+    let mut depth = 0usize;
+    let mut rest = msg;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            ',' | ']' | '}' | ')' if depth == 0 && rest.len() < msg.len() => return rest,
+            '"' => {
+                rest = skip_string(rest);
+                continue;
+            }
+            '[' | '{' | '(' => depth += 1,
+            ']' | '}' | ')' if depth > 0 => depth -= 1,
+            _ => {}
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    rest
+}
+
+/// Skips a string that starts at `msg`, or the rest of its line if it is
+/// never closed.
+fn skip_string(msg: &str) -> &str {
+    let mut chars = msg.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return &msg[i + 1..],
+            '\n' => return &msg[i..],
+            _ => {}
+        }
+    }
+    ""
+}
+
+#[cfg(test)]
+mod test {
+    use crate::DValue;
+
+    fn recover(text: &str) -> (String, Vec<(usize, String)>) {
+        let (value, errors) = DValue::parse_recovering(text);
+        let errors = errors
+            .into_iter()
+            .map(|e| (e.position, e.message))
+            .collect();
+        (value.to_canonical_string(), errors)
+    }
+
+    #[test]
+    fn valid_text_has_no_errors() {
+        let text = "{\"a\": [1, \"x\\n\", (none, -inf)], \"b\": binary!(aGk=),}";
+
+        let (value, errors) = DValue::parse_recovering(text);
+        assert_eq!(value, text.parse::<DValue>().unwrap());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn recovers_from_broken_spans() {
+        assert_eq!(
+            recover("[1 2, @@, [3, {\"k\" 4}], \"ok\"]"),
+            (
+                "[1,none,[3,{\"k\":none}],\"ok\"]".to_string(),
+                vec![
+                    (3, "expected `,` or `]`".to_string()),
+                    (6, "invalid value".to_string()),
+                    (19, "expected `:`".to_string()),
+                ]
+            )
+        );
+        assert_eq!(
+            recover("{\"a\": , 1: 2, \"b\": \"open\n}"),
+            (
+                "{\"a\":none,\"b\":none}".to_string(),
+                vec![
+                    (6, "expected a value".to_string()),
+                    (8, "expected a string key".to_string()),
+                    (19, "invalid string".to_string()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn unclosed_input() {
+        assert_eq!(
+            recover("{\"a\": [1, (2"),
+            (
+                "{\"a\":[1,(2, none)]}".to_string(),
+                vec![
+                    (12, "unexpected end of input in tuple".to_string()),
+                    (10, "a tuple needs exactly two items".to_string()),
+                    (12, "unexpected end of input in list".to_string()),
+                    (12, "unexpected end of input in object".to_string()),
+                ]
+            )
+        );
+        assert_eq!(
+            recover("[1, {\"a\": 2]] x"),
+            (
+                "[1,{\"a\":2}]".to_string(),
+                vec![
+                    (11, "unclosed object".to_string()),
+                    (12, "unexpected trailing characters".to_string()),
+                ]
+            )
+        );
+        assert_eq!(
+            recover("").1,
+            vec![(0, "unexpected end of input in value".to_string())]
+        );
+    }
+}
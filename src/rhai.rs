@@ -0,0 +1,250 @@
+use crate::binary_util::Binary;
+use crate::path::Segment;
+use crate::{DValue, ParseError};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Module, Position};
+use std::any::TypeId;
+
+/// Registers `DValue` with a Rhai engine.
+///
+/// Scripts can then read and write documents with the usual syntax:
+/// `doc.name`, `doc["key"]`, `doc.items[0] = 1` and `for x in doc { }`.
+/// Reading a field returns native Rhai values (arrays, maps, numbers...),
+/// and assigning one converts it back. Iterating over a dict yields
+/// `[key, value]` pairs. `parse_dvalue(text)` builds a document from the
+/// text format.
+///
+/// ```
+/// use datastruct::DValue;
+/// use rhai::{Engine, Scope};
+///
+/// let mut engine = Engine::new();
+/// datastruct::rhai::register(&mut engine);
+///
+/// let mut scope = Scope::new();
+/// scope.push("doc", "{\"items\": [1, 2], \"total\": 0}".parse::<DValue>().unwrap());
+/// engine.run_with_scope(&mut scope, "for n in doc.items { doc.total += n; }").unwrap();
+///
+/// let doc = scope.get_value::<DValue>("doc").unwrap();
+/// assert_eq!(doc.get_path("total"), Some(&DValue::Number(3.0)));
+/// ```
+pub fn register(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<DValue>("DValue")
+        .register_indexer_get(get_key)
+        .register_indexer_set(set_key)
+        .register_indexer_get(get_index)
+        .register_indexer_set(set_index)
+        .register_fn(
+            "parse_dvalue",
+            |text: &str| -> Result<DValue, Box<EvalAltResult>> {
+                text.parse().map_err(|e: ParseError| e.to_string().into())
+            },
+        )
+        .register_fn("to_dynamic", |value: &mut DValue| to_dynamic(value.clone()))
+        .register_fn("len", |value: &mut DValue| match value {
+            DValue::List(list) => list.len() as i64,
+            DValue::Dict(dict) => dict.len() as i64,
+            DValue::Tuple(_) => 2,
+            _ => 0,
+        })
+        .register_fn("to_string", |value: &mut DValue| value.to_string())
+        .register_fn("to_debug", |value: &mut DValue| value.to_string());
+
+    let mut module = Module::new();
+    module.set_iter(TypeId::of::<DValue>(), |value| {
+        Box::new(value.cast::<DValue>().into_iter().map(to_dynamic))
+    });
+    engine.register_global_module(module.into());
+}
+
+/// Converts to native Rhai values: `()` for `None`, `FLOAT` for numbers,
+/// arrays for lists and tuples, object maps for dicts and blobs for
+/// binary data.
+impl From<DValue> for Dynamic {
+    fn from(value: DValue) -> Self {
+        to_dynamic(value)
+    }
+}
+
+// `Dynamic::from` is Rhai's generic constructor and would wrap the value as
+// a custom type, so the conversion is called by name.
+fn to_dynamic(value: DValue) -> Dynamic {
+    match value {
+        DValue::None => Dynamic::UNIT,
+        DValue::String(s) => s.into(),
+        DValue::Number(n) => Dynamic::from_float(n),
+        DValue::Boolean(b) => Dynamic::from_bool(b),
+        DValue::List(items) => Dynamic::from_array(items.into_iter().map(to_dynamic).collect()),
+        DValue::Dict(entries) => Dynamic::from_map(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.into(), to_dynamic(v)))
+                .collect(),
+        ),
+        DValue::Tuple(pair) => Dynamic::from_array(vec![to_dynamic(*pair.0), to_dynamic(*pair.1)]),
+        DValue::BinaryUtil(bin) => Dynamic::from_blob(bin.into_vec()),
+    }
+}
+
+/// Converts a Rhai value back. Integers and floats become numbers,
+/// characters become strings and a `DValue` held by the script is returned
+/// as is; functions and other custom types are rejected.
+impl TryFrom<Dynamic> for DValue {
+    type Error = Box<EvalAltResult>;
+
+    fn try_from(value: Dynamic) -> Result<Self, Self::Error> {
+        if value.is_unit() {
+            Ok(DValue::None)
+        } else if let Ok(b) = value.as_bool() {
+            Ok(DValue::Boolean(b))
+        } else if let Ok(n) = value.as_int() {
+            Ok(DValue::Number(n as f64))
+        } else if let Ok(n) = value.as_float() {
+            Ok(DValue::Number(n))
+        } else if let Ok(c) = value.as_char() {
+            Ok(DValue::String(c.to_string()))
+        } else if value.is_string() {
+            Ok(DValue::String(value.into_string()?))
+        } else if value.is_array() {
+            value
+                .cast::<Array>()
+                .into_iter()
+                .map(DValue::try_from)
+                .collect::<Result<_, _>>()
+                .map(DValue::List)
+        } else if value.is_map() {
+            value
+                .cast::<Map>()
+                .into_iter()
+                .map(|(k, v)| Ok((k.to_string(), DValue::try_from(v)?)))
+                .collect::<Result<_, Self::Error>>()
+                .map(DValue::Dict)
+        } else if value.is_blob() {
+            Ok(DValue::BinaryUtil(Binary::new(value.into_blob()?)))
+        } else if value.is::<DValue>() {
+            Ok(value.cast::<DValue>())
+        } else {
+            Err(Box::new(EvalAltResult::ErrorMismatchDataType(
+                "DValue".to_string(),
+                value.type_name().to_string(),
+                Position::NONE,
+            )))
+        }
+    }
+}
+
+fn get_key(value: &mut DValue, key: &str) -> Dynamic {
+    value
+        .child(&Segment::Key(key.to_string()))
+        .cloned()
+        .map(to_dynamic)
+        .unwrap_or(Dynamic::UNIT)
+}
+
+fn set_key(value: &mut DValue, key: &str, new: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    if let DValue::None = value {
+        *value = DValue::Dict(Default::default());
+    }
+    match value {
+        DValue::Dict(dict) => {
+            dict.insert(key.to_string(), DValue::try_from(new)?);
+            Ok(())
+        }
+        _ => Err(Box::new(EvalAltResult::ErrorIndexingType(
+            format!("{} with a string key", value.datatype()),
+            Position::NONE,
+        ))),
+    }
+}
+
+/// Resolves a Rhai index, where negative values count from the end.
+fn position(value: &DValue, index: i64) -> Result<Segment, Box<EvalAltResult>> {
+    let len = match value {
+        DValue::List(list) => list.len(),
+        DValue::Tuple(_) => 2,
+        _ => {
+            return Err(Box::new(EvalAltResult::ErrorIndexingType(
+                format!("{} with an integer index", value.datatype()),
+                Position::NONE,
+            )))
+        }
+    };
+
+    let resolved = if index < 0 {
+        len.checked_sub(index.unsigned_abs() as usize)
+    } else {
+        Some(index as usize).filter(|i| *i < len)
+    };
+    resolved
+        .map(Segment::Index)
+        .ok_or_else(|| Box::new(EvalAltResult::ErrorArrayBounds(len, index, Position::NONE)))
+}
+
+fn get_index(value: &mut DValue, index: i64) -> Result<Dynamic, Box<EvalAltResult>> {
+    let segment = position(value, index)?;
+    Ok(value
+        .child(&segment)
+        .cloned()
+        .map(to_dynamic)
+        .unwrap_or_default())
+}
+
+fn set_index(value: &mut DValue, index: i64, new: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let segment = position(value, index)?;
+    if let Some(slot) = value.child_mut(&segment) {
+        *slot = DValue::try_from(new)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::DValue;
+    use rhai::{Dynamic, Engine, Scope};
+
+    #[test]
+    fn script_edits_document() {
+        let mut engine = Engine::new();
+        crate::rhai::register(&mut engine);
+
+        let mut scope = Scope::new();
+        scope.push(
+            "doc",
+            "{\"items\": [1, 2, 3], \"meta\": {\"n\": 1}}"
+                .parse::<DValue>()
+                .unwrap(),
+        );
+        engine
+            .run_with_scope(
+                &mut scope,
+                r#"
+                doc.items[0] = 10;
+                doc["meta"].n += 1;
+                doc.tags = ["a", 'b'];
+                doc.blob = blob(2, 0xff);
+                let keys = [];
+                for pair in doc { keys.push(pair[0]); }
+                doc.count = keys.len();
+                "#,
+            )
+            .unwrap();
+
+        let doc = scope.get_value::<DValue>("doc").unwrap();
+        assert_eq!(
+            doc.get_path("items"),
+            "[10, 2, 3]".parse::<DValue>().unwrap().get_path("")
+        );
+        assert_eq!(doc.get_path("meta.n"), Some(&DValue::Number(2.0)));
+        assert_eq!(
+            doc.get_path("tags"),
+            "[\"a\", \"b\"]".parse::<DValue>().unwrap().get_path("")
+        );
+        assert_eq!(doc.get_path("blob").unwrap().datatype(), "Binary");
+        assert_eq!(doc.get_path("count"), Some(&DValue::Number(4.0)));
+
+        assert!(engine.eval::<Dynamic>("parse_dvalue(\"[1]\")[5]").is_err());
+        #[derive(Clone)]
+        struct Opaque;
+        assert!(DValue::try_from(Dynamic::from(Opaque)).is_err());
+    }
+}
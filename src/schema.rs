@@ -0,0 +1,811 @@
+use crate::{convert::DType, path::Path, DValue, Map};
+use regex::Regex;
+use std::fmt;
+
+/// Describes the expected shape of a `DValue`.
+///
+/// ```
+/// use datastruct::schema::{DSchema, Field};
+/// use datastruct::DValue;
+///
+/// let schema = DSchema::dict([
+///     ("host", Field::required(DSchema::String)),
+///     ("port", Field::required(DSchema::Number)),
+/// ]);
+///
+/// assert!(schema.validate(&"{\"host\": \"localhost\", \"port\": 8080}".parse::<DValue>().unwrap()).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum DSchema {
+    /// Accepts any value.
+    Any,
+
+    /// Accepts only `DValue::None`.
+    None,
+
+    String,
+
+    Number,
+
+    Boolean,
+
+    Binary,
+
+    /// A list whose items all match the inner schema.
+    List(Box<DSchema>),
+
+    /// A tuple whose elements match the two inner schemas.
+    Tuple(Box<DSchema>, Box<DSchema>),
+
+    /// A dict with known fields. Keys that are not listed are allowed.
+    Dict(Map<String, Field>),
+
+    /// A dict whose values all match the inner schema.
+    Map(Box<DSchema>),
+
+    /// Matches when any of the member schemas matches.
+    Union(Vec<DSchema>),
+
+    /// The inner schema, further restricted by constraints.
+    ///
+    /// ```
+    /// use datastruct::schema::{Constraint, DSchema, Field};
+    /// use datastruct::DValue;
+    ///
+    /// let schema = DSchema::dict([
+    ///     ("port", Field::required(DSchema::Number.min(1.0).max(65535.0))),
+    ///     ("user", Field::optional(DSchema::String.with(Constraint::pattern("^[a-z]+$").unwrap()))),
+    /// ])
+    /// .requires("user", ["password"]);
+    ///
+    /// assert!(schema.validate(&"{\"port\": 0, \"user\": \"root\"}".parse::<DValue>().unwrap()).is_err());
+    /// ```
+    Constrained(Box<DSchema>, Vec<Constraint>),
+}
+
+/// A rule checked in addition to the type of a value.
+///
+/// Constraints that do not apply to the type of the value are ignored, e.g.
+/// `Min` on a `String`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// Numbers must be greater than or equal to the bound.
+    Min(f64),
+
+    /// Numbers must be less than or equal to the bound.
+    Max(f64),
+
+    /// Strings (in chars), lists and dicts must have at least this length.
+    MinLength(usize),
+
+    /// Strings (in chars), lists and dicts must have at most this length.
+    MaxLength(usize),
+
+    /// Strings must match the pattern.
+    Pattern(Pattern),
+
+    /// Every key of a dict must match the pattern.
+    KeyPattern(Pattern),
+
+    /// When the dict contains the first key, it must contain all the others.
+    Requires(String, Vec<String>),
+}
+
+/// A compiled regular expression used by `Constraint`.
+#[derive(Debug, Clone)]
+pub struct Pattern(pub(crate) Regex);
+
+/// A field of a `DSchema::Dict`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub schema: DSchema,
+    pub required: bool,
+}
+
+/// A single validation failure, located by its path in the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: Path,
+    pub message: String,
+}
+
+/// A conversion performed by `DValue::coerce`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coercion {
+    pub path: Path,
+    pub from: String,
+    pub to: String,
+}
+
+impl Field {
+    pub fn required(schema: DSchema) -> Self {
+        Self {
+            schema,
+            required: true,
+        }
+    }
+
+    pub fn optional(schema: DSchema) -> Self {
+        Self {
+            schema,
+            required: false,
+        }
+    }
+}
+
+impl Constraint {
+    /// Builds a `Constraint::Pattern`, failing if `pattern` is not a valid regex.
+    pub fn pattern(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Constraint::Pattern(Pattern(Regex::new(pattern)?)))
+    }
+
+    /// Builds a `Constraint::KeyPattern`, failing if `pattern` is not a valid regex.
+    pub fn key_pattern(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Constraint::KeyPattern(Pattern(Regex::new(pattern)?)))
+    }
+
+    pub(crate) fn check(&self, value: &DValue, path: &Path, errors: &mut Vec<SchemaError>) {
+        let mut fail = |path: Path, message: String| errors.push(SchemaError { path, message });
+
+        match (self, value) {
+            (Constraint::Min(min), DValue::Number(n)) if n < min => fail(
+                path.clone(),
+                format!("expected at least {}, found {}", min, n),
+            ),
+            (Constraint::Max(max), DValue::Number(n)) if n > max => fail(
+                path.clone(),
+                format!("expected at most {}, found {}", max, n),
+            ),
+            (Constraint::MinLength(min), value) => {
+                if let Some(len) = constraint_len(value).filter(|len| len < min) {
+                    fail(
+                        path.clone(),
+                        format!("expected length at least {}, found {}", min, len),
+                    )
+                }
+            }
+            (Constraint::MaxLength(max), value) => {
+                if let Some(len) = constraint_len(value).filter(|len| len > max) {
+                    fail(
+                        path.clone(),
+                        format!("expected length at most {}, found {}", max, len),
+                    )
+                }
+            }
+            (Constraint::Pattern(pattern), DValue::String(s)) if !pattern.0.is_match(s) => fail(
+                path.clone(),
+                format!("does not match pattern {}", pattern.0.as_str()),
+            ),
+            (Constraint::KeyPattern(pattern), DValue::Dict(dict)) => {
+                let mut keys: Vec<&String> = dict.keys().collect();
+                keys.sort();
+
+                for key in keys.into_iter().filter(|k| !pattern.0.is_match(k)) {
+                    fail(
+                        path.key(key),
+                        format!("key does not match pattern {}", pattern.0.as_str()),
+                    )
+                }
+            }
+            (Constraint::Requires(key, required), DValue::Dict(dict)) if dict.contains_key(key) => {
+                for other in required.iter().filter(|k| !dict.contains_key(*k)) {
+                    fail(
+                        path.key(other),
+                        format!("missing key required by \"{}\"", key),
+                    )
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn constraint_len(value: &DValue) -> Option<usize> {
+    match value {
+        DValue::String(s) => Some(s.chars().count()),
+        DValue::List(list) => Some(list.len()),
+        DValue::Dict(dict) => Some(dict.len()),
+        _ => None,
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_root() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl DSchema {
+    pub fn list(item: DSchema) -> Self {
+        DSchema::List(Box::new(item))
+    }
+
+    pub fn tuple(first: DSchema, second: DSchema) -> Self {
+        DSchema::Tuple(Box::new(first), Box::new(second))
+    }
+
+    pub fn map(value: DSchema) -> Self {
+        DSchema::Map(Box::new(value))
+    }
+
+    pub fn dict<'a>(fields: impl IntoIterator<Item = (&'a str, Field)>) -> Self {
+        DSchema::Dict(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    /// Adds a constraint to the schema.
+    pub fn with(self, constraint: Constraint) -> Self {
+        match self {
+            DSchema::Constrained(inner, mut constraints) => {
+                constraints.push(constraint);
+                DSchema::Constrained(inner, constraints)
+            }
+            schema => DSchema::Constrained(Box::new(schema), vec![constraint]),
+        }
+    }
+
+    pub fn min(self, min: f64) -> Self {
+        self.with(Constraint::Min(min))
+    }
+
+    pub fn max(self, max: f64) -> Self {
+        self.with(Constraint::Max(max))
+    }
+
+    pub fn min_len(self, min: usize) -> Self {
+        self.with(Constraint::MinLength(min))
+    }
+
+    pub fn max_len(self, max: usize) -> Self {
+        self.with(Constraint::MaxLength(max))
+    }
+
+    /// Requires every key in `required` whenever `key` is present.
+    pub fn requires<'a>(self, key: &str, required: impl IntoIterator<Item = &'a str>) -> Self {
+        self.with(Constraint::Requires(
+            key.to_string(),
+            required.into_iter().map(|k| k.to_string()).collect(),
+        ))
+    }
+
+    /// Returns a short name for the schema, e.g. `List` or `String | Number`.
+    pub fn name(&self) -> String {
+        match self {
+            DSchema::Any => "Any".to_string(),
+            DSchema::None => "None".to_string(),
+            DSchema::String => "String".to_string(),
+            DSchema::Number => "Number".to_string(),
+            DSchema::Boolean => "Boolean".to_string(),
+            DSchema::Binary => "Binary".to_string(),
+            DSchema::List(_) => "List".to_string(),
+            DSchema::Tuple(_, _) => "Tuple".to_string(),
+            DSchema::Dict(_) | DSchema::Map(_) => "Dict".to_string(),
+            DSchema::Union(options) => options
+                .iter()
+                .map(|s| s.name())
+                .collect::<Vec<String>>()
+                .join(" | "),
+            DSchema::Constrained(inner, _) => inner.name(),
+        }
+    }
+
+    /// Checks `value` against the schema, collecting every mismatch.
+    pub fn validate(&self, value: &DValue) -> Result<(), Vec<SchemaError>> {
+        let mut errors = Vec::new();
+        self.check(value, &Path::root(), &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn matches(&self, value: &DValue) -> bool {
+        self.validate(value).is_ok()
+    }
+
+    /// Infers a schema that every one of `samples` satisfies.
+    ///
+    /// Differing types become a `Union`, list item types are merged, and dict
+    /// keys that are absent from some samples become optional.
+    ///
+    /// ```
+    /// use datastruct::schema::{DSchema, Field};
+    /// use datastruct::DValue;
+    ///
+    /// let schema = DSchema::infer(&[
+    ///     "{\"id\": 1, \"name\": \"a\"}".parse::<DValue>().unwrap(),
+    ///     "{\"id\": 2}".parse::<DValue>().unwrap(),
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     schema,
+    ///     DSchema::dict([
+    ///         ("id", Field::required(DSchema::Number)),
+    ///         ("name", Field::optional(DSchema::String)),
+    ///     ])
+    /// );
+    /// ```
+    pub fn infer(samples: &[DValue]) -> Self {
+        samples
+            .iter()
+            .map(infer_value)
+            .reduce(merge)
+            .map(finish)
+            .unwrap_or(DSchema::Any)
+    }
+
+    fn check(&self, value: &DValue, path: &Path, errors: &mut Vec<SchemaError>) {
+        match (self, value) {
+            (DSchema::Any, _)
+            | (DSchema::None, DValue::None)
+            | (DSchema::String, DValue::String(_))
+            | (DSchema::Number, DValue::Number(_))
+            | (DSchema::Boolean, DValue::Boolean(_))
+            | (DSchema::Binary, DValue::BinaryUtil(_)) => {}
+
+            (DSchema::Constrained(inner, constraints), value) => {
+                let before = errors.len();
+                inner.check(value, path, errors);
+
+                // Nested errors don't stop the checks, a type mismatch here does.
+                if !errors[before..].iter().any(|e| e.path == *path) {
+                    for constraint in constraints {
+                        constraint.check(value, path, errors);
+                    }
+                }
+            }
+
+            (DSchema::List(item), DValue::List(list)) => {
+                for (i, v) in list.iter().enumerate() {
+                    item.check(v, &path.index(i), errors);
+                }
+            }
+
+            (DSchema::Tuple(first, second), DValue::Tuple(tuple)) => {
+                first.check(&tuple.0, &path.index(0), errors);
+                second.check(&tuple.1, &path.index(1), errors);
+            }
+
+            (DSchema::Dict(fields), DValue::Dict(dict)) => {
+                let mut keys: Vec<&String> = fields.keys().collect();
+                keys.sort();
+
+                for key in keys {
+                    let field = &fields[key];
+                    match dict.get(key) {
+                        Some(v) => field.schema.check(v, &path.key(key), errors),
+                        None if field.required => errors.push(SchemaError {
+                            path: path.key(key),
+                            message: "missing required key".to_string(),
+                        }),
+                        None => {}
+                    }
+                }
+            }
+
+            (DSchema::Map(schema), DValue::Dict(dict)) => {
+                let mut keys: Vec<&String> = dict.keys().collect();
+                keys.sort();
+
+                for key in keys {
+                    schema.check(&dict[key], &path.key(key), errors);
+                }
+            }
+
+            (DSchema::Union(options), value) => {
+                if !options.iter().any(|s| s.matches(value)) {
+                    errors.push(mismatch(self, value, path));
+                }
+            }
+
+            (schema, value) => errors.push(mismatch(schema, value, path)),
+        }
+    }
+}
+
+impl DValue {
+    /// Converts the value toward `schema`, returning the converted value and
+    /// a report of every conversion performed.
+    ///
+    /// Values that cannot be converted are left as they are, so the result
+    /// should still be checked with `DSchema::validate`.
+    ///
+    /// ```
+    /// use datastruct::schema::{DSchema, Field};
+    /// use datastruct::DValue;
+    ///
+    /// let schema = DSchema::dict([("port", Field::required(DSchema::Number))]);
+    /// let (value, coercions) = "{\"port\": \"8080\"}".parse::<DValue>().unwrap().coerce(&schema);
+    ///
+    /// assert_eq!(value, "{\"port\": 8080}".parse::<DValue>().unwrap());
+    /// assert_eq!(coercions.len(), 1);
+    /// ```
+    pub fn coerce(&self, schema: &DSchema) -> (DValue, Vec<Coercion>) {
+        let mut report = Vec::new();
+        let value = coerce_value(self, schema, &Path::root(), &mut report);
+        (value, report)
+    }
+}
+
+fn coerce_value(
+    value: &DValue,
+    schema: &DSchema,
+    path: &Path,
+    report: &mut Vec<Coercion>,
+) -> DValue {
+    let converted = match (schema, value) {
+        (DSchema::Any, _) => return value.clone(),
+
+        (DSchema::Constrained(inner, _), value) => return coerce_value(value, inner, path, report),
+
+        (DSchema::List(item), DValue::List(list)) => {
+            return DValue::List(
+                list.iter()
+                    .enumerate()
+                    .map(|(i, v)| coerce_value(v, item, &path.index(i), report))
+                    .collect(),
+            )
+        }
+
+        (DSchema::Tuple(first, second), DValue::Tuple(tuple)) => {
+            return DValue::Tuple((
+                Box::new(coerce_value(&tuple.0, first, &path.index(0), report)),
+                Box::new(coerce_value(&tuple.1, second, &path.index(1), report)),
+            ))
+        }
+
+        (DSchema::Dict(fields), DValue::Dict(dict)) => {
+            return DValue::Dict(
+                dict.iter()
+                    .map(|(k, v)| {
+                        let v = match fields.get(k) {
+                            Some(field) => coerce_value(v, &field.schema, &path.key(k), report),
+                            None => v.clone(),
+                        };
+                        (k.clone(), v)
+                    })
+                    .collect(),
+            )
+        }
+
+        (DSchema::Map(schema), DValue::Dict(dict)) => {
+            return DValue::Dict(
+                dict.iter()
+                    .map(|(k, v)| (k.clone(), coerce_value(v, schema, &path.key(k), report)))
+                    .collect(),
+            )
+        }
+
+        (DSchema::Union(options), value) => {
+            if schema.matches(value) {
+                return value.clone();
+            }
+
+            for option in options {
+                let mut attempt = Vec::new();
+                let converted = coerce_value(value, option, path, &mut attempt);
+                if option.matches(&converted) {
+                    report.extend(attempt);
+                    return converted;
+                }
+            }
+            return value.clone();
+        }
+
+        (schema, value) if schema.matches(value) => return value.clone(),
+
+        (DSchema::None, DValue::String(_)) => value.cast(DType::None).ok(),
+        (DSchema::Number, DValue::String(_) | DValue::Boolean(_)) => value.cast(DType::Number).ok(),
+        (DSchema::Boolean, DValue::Number(_) | DValue::String(_)) => {
+            value.cast(DType::Boolean).ok()
+        }
+        (DSchema::String, DValue::Number(_) | DValue::Boolean(_)) => value.cast(DType::String).ok(),
+
+        #[cfg(feature = "base64")]
+        (DSchema::Binary, DValue::String(s)) => crate::binary_util::Binary::from_b64(s.to_string())
+            .ok()
+            .map(DValue::BinaryUtil),
+
+        (DSchema::Tuple(first, second), DValue::List(list)) if list.len() == 2 => {
+            Some(DValue::Tuple((
+                Box::new(coerce_value(&list[0], first, &path.index(0), report)),
+                Box::new(coerce_value(&list[1], second, &path.index(1), report)),
+            )))
+        }
+
+        (DSchema::List(item), value) if !matches!(value, DValue::None) => {
+            Some(DValue::List(vec![coerce_value(
+                value,
+                item,
+                &path.index(0),
+                report,
+            )]))
+        }
+
+        _ => None,
+    };
+
+    match converted {
+        Some(converted) => {
+            report.push(Coercion {
+                path: path.clone(),
+                from: value.datatype(),
+                to: converted.datatype(),
+            });
+            converted
+        }
+        None => value.clone(),
+    }
+}
+
+fn infer_value(value: &DValue) -> DSchema {
+    match value {
+        DValue::None => DSchema::None,
+        DValue::String(_) => DSchema::String,
+        DValue::Number(_) => DSchema::Number,
+        DValue::Boolean(_) => DSchema::Boolean,
+        DValue::BinaryUtil(_) => DSchema::Binary,
+        // An empty union stands for "no items seen yet" and disappears when merged.
+        DValue::List(list) => DSchema::list(
+            list.iter()
+                .map(infer_value)
+                .reduce(merge)
+                .unwrap_or(DSchema::Union(vec![])),
+        ),
+        DValue::Tuple(tuple) => DSchema::tuple(infer_value(&tuple.0), infer_value(&tuple.1)),
+        DValue::Dict(dict) => DSchema::Dict(
+            dict.iter()
+                .map(|(k, v)| (k.clone(), Field::required(infer_value(v))))
+                .collect(),
+        ),
+    }
+}
+
+fn merge(a: DSchema, b: DSchema) -> DSchema {
+    let mut options = into_options(a);
+
+    for schema in into_options(b) {
+        match options
+            .iter()
+            .position(|o| std::mem::discriminant(o) == std::mem::discriminant(&schema))
+        {
+            Some(i) => {
+                let existing = options.remove(i);
+                options.insert(i, merge_same_kind(existing, schema));
+            }
+            None => options.push(schema),
+        }
+    }
+
+    if options.len() == 1 {
+        options.remove(0)
+    } else {
+        DSchema::Union(options)
+    }
+}
+
+fn merge_same_kind(a: DSchema, b: DSchema) -> DSchema {
+    match (a, b) {
+        (DSchema::List(a), DSchema::List(b)) => DSchema::list(merge(*a, *b)),
+        (DSchema::Tuple(a1, a2), DSchema::Tuple(b1, b2)) => {
+            DSchema::tuple(merge(*a1, *b1), merge(*a2, *b2))
+        }
+        (DSchema::Dict(mut a), DSchema::Dict(mut b)) => {
+            for (key, field) in a.iter_mut() {
+                match b.remove(key) {
+                    Some(other) => {
+                        let schema = std::mem::replace(&mut field.schema, DSchema::Any);
+                        field.schema = merge(schema, other.schema);
+                        field.required &= other.required;
+                    }
+                    None => field.required = false,
+                }
+            }
+
+            for (key, field) in b {
+                a.insert(key, Field::optional(field.schema));
+            }
+            DSchema::Dict(a)
+        }
+        (a, _) => a,
+    }
+}
+
+fn into_options(schema: DSchema) -> Vec<DSchema> {
+    match schema {
+        DSchema::Union(options) => options,
+        schema => vec![schema],
+    }
+}
+
+fn finish(schema: DSchema) -> DSchema {
+    match schema {
+        DSchema::List(item) => DSchema::list(finish(*item)),
+        DSchema::Tuple(a, b) => DSchema::tuple(finish(*a), finish(*b)),
+        DSchema::Dict(fields) => DSchema::Dict(
+            fields
+                .into_iter()
+                .map(|(k, f)| {
+                    (
+                        k,
+                        Field {
+                            schema: finish(f.schema),
+                            required: f.required,
+                        },
+                    )
+                })
+                .collect(),
+        ),
+        DSchema::Union(options) if options.is_empty() => DSchema::Any,
+        DSchema::Union(options) => DSchema::Union(options.into_iter().map(finish).collect()),
+        DSchema::Constrained(inner, constraints) => {
+            DSchema::Constrained(Box::new(finish(*inner)), constraints)
+        }
+        schema => schema,
+    }
+}
+
+fn mismatch(schema: &DSchema, value: &DValue, path: &Path) -> SchemaError {
+    SchemaError {
+        path: path.clone(),
+        message: format!("expected {}, found {}", schema.name(), value.datatype()),
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::path::Path;
+    use crate::schema::{Coercion, Constraint, DSchema, Field};
+    use crate::DValue;
+
+    #[test]
+    fn validate_reports_paths() {
+        let schema = DSchema::dict([
+            ("name", Field::required(DSchema::String)),
+            ("tags", Field::optional(DSchema::list(DSchema::String))),
+            ("port", Field::required(DSchema::Number)),
+        ]);
+
+        let errors = schema
+            .validate(
+                &"{\"name\": \"api\", \"tags\": [\"a\", 1]}"
+                    .parse::<DValue>()
+                    .unwrap(),
+            )
+            .unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].path.to_string(), "port");
+        assert_eq!(errors[1].path.to_string(), "tags[1]");
+        assert_eq!(errors[1].message, "expected String, found Number");
+    }
+
+    #[test]
+    fn coerce_stringly_typed_values() {
+        let schema = DSchema::dict([
+            ("port", Field::required(DSchema::Number)),
+            ("debug", Field::required(DSchema::Boolean)),
+            ("hosts", Field::required(DSchema::list(DSchema::String))),
+        ]);
+
+        let (value, coercions) = "{\"port\": \"8080\", \"debug\": 1, \"hosts\": \"a.local\"}"
+            .parse::<DValue>()
+            .unwrap()
+            .coerce(&schema);
+
+        assert!(schema.matches(&value));
+        assert_eq!(
+            value.as_dict().unwrap()["hosts"],
+            DValue::List(vec![DValue::String("a.local".to_string())])
+        );
+        assert!(coercions.contains(&Coercion {
+            path: Path::root().key("port"),
+            from: "String".to_string(),
+            to: "Number".to_string(),
+        }));
+        assert_eq!(coercions.len(), 3);
+    }
+
+    #[test]
+    fn coerce_leaves_unconvertible_values() {
+        let (value, coercions) = DValue::String("eighty".to_string()).coerce(&DSchema::Number);
+
+        assert_eq!(value, DValue::String("eighty".to_string()));
+        assert!(coercions.is_empty());
+    }
+
+    #[test]
+    fn validate_constraints() {
+        let schema = DSchema::dict([
+            (
+                "port",
+                Field::required(DSchema::Number.min(1.0).max(65535.0)),
+            ),
+            (
+                "name",
+                Field::required(
+                    DSchema::String
+                        .max_len(8)
+                        .with(Constraint::pattern("^[a-z-]+$").unwrap()),
+                ),
+            ),
+            (
+                "labels",
+                Field::optional(
+                    DSchema::map(DSchema::String)
+                        .with(Constraint::key_pattern("^[a-z]+$").unwrap()),
+                ),
+            ),
+        ])
+        .requires("tls", ["cert", "key"]);
+
+        assert!(schema
+            .validate(
+                &"{\"port\": 443, \"name\": \"api\", \"labels\": {\"env\": \"prod\"}}"
+                    .parse::<DValue>()
+                    .unwrap()
+            )
+            .is_ok());
+
+        let errors = schema
+            .validate(&"{\"port\": 70000, \"name\": \"Api-Server\", \"labels\": {\"Env\": \"prod\"}, \"tls\": true, \"cert\": \"c\"}".parse::<DValue>().unwrap())
+            .unwrap_err();
+        let paths: Vec<String> = errors.iter().map(|e| e.path.to_string()).collect();
+
+        assert_eq!(paths, vec!["labels.Env", "name", "name", "port", "key"]);
+        assert_eq!(errors[3].message, "expected at most 65535, found 70000");
+    }
+
+    #[test]
+    fn infer_unions_and_item_types() {
+        let samples = [
+            "{\"id\": 1, \"tags\": [], \"owner\": \"a\"}"
+                .parse::<DValue>()
+                .unwrap(),
+            "{\"id\": \"2\", \"tags\": [\"x\", \"y\"]}"
+                .parse::<DValue>()
+                .unwrap(),
+        ];
+        let schema = DSchema::infer(&samples);
+
+        assert_eq!(
+            schema,
+            DSchema::dict([
+                (
+                    "id",
+                    Field::required(DSchema::Union(vec![DSchema::Number, DSchema::String]))
+                ),
+                ("tags", Field::required(DSchema::list(DSchema::String))),
+                ("owner", Field::optional(DSchema::String)),
+            ])
+        );
+        assert!(samples.iter().all(|s| schema.matches(s)));
+    }
+
+    #[test]
+    fn infer_without_samples() {
+        assert_eq!(DSchema::infer(&[]), DSchema::Any);
+        assert_eq!(
+            DSchema::infer(&[DValue::List(vec![])]),
+            DSchema::list(DSchema::Any)
+        );
+    }
+}
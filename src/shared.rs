@@ -0,0 +1,103 @@
+use crate::DValue;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A reference-counted `DValue` that clones in O(1) and copies on write.
+///
+/// Clones share the same tree until one of them is mutated through
+/// `make_mut`. Only the root is shared, not its children, so that first
+/// write deep-copies the whole document, however small the change. This
+/// suits documents that are handed to many readers and rarely modified; for
+/// frequent small updates to large documents, `persistent::PDValue` (with
+/// the `persistent` feature) copies only the path that changed.
+///
+/// ```
+/// use datastruct::shared::SharedDValue;
+/// use datastruct::DValue;
+///
+/// let original = SharedDValue::new("{\"hits\": 1}".parse::<DValue>().unwrap());
+/// let mut copy = original.clone();
+/// assert!(SharedDValue::ptr_eq(&original, &copy));
+///
+/// copy.make_mut().incr("hits", 1.0).unwrap();
+///
+/// assert_eq!(original.get_path("hits"), Some(&DValue::Number(1.0)));
+/// assert_eq!(copy.get_path("hits"), Some(&DValue::Number(2.0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedDValue(Arc<DValue>);
+
+impl SharedDValue {
+    pub fn new(value: DValue) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Returns a mutable reference to the value, first copying the whole
+    /// tree if other handles still share it.
+    pub fn make_mut(&mut self) -> &mut DValue {
+        Arc::make_mut(&mut self.0)
+    }
+
+    /// Returns the value, copying it only if other handles still share it.
+    pub fn into_inner(self) -> DValue {
+        Arc::try_unwrap(self.0).unwrap_or_else(|shared| (*shared).clone())
+    }
+
+    /// Returns `true` if both handles point to the same tree.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+
+    /// Returns the number of handles sharing the tree.
+    pub fn share_count(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+}
+
+impl Deref for SharedDValue {
+    type Target = DValue;
+
+    fn deref(&self) -> &DValue {
+        &self.0
+    }
+}
+
+impl From<DValue> for SharedDValue {
+    fn from(value: DValue) -> Self {
+        Self::new(value)
+    }
+}
+
+impl Serialize for SharedDValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedDValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DValue::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::shared::SharedDValue;
+    use crate::DValue;
+
+    #[test]
+    fn copy_on_write() {
+        let mut a = SharedDValue::new("[1, 2]".parse::<DValue>().unwrap());
+        let b = a.clone();
+        assert_eq!(a.share_count(), 2);
+
+        a.make_mut().iter_mut().for_each(|v| *v = DValue::None);
+
+        assert!(!SharedDValue::ptr_eq(&a, &b));
+        assert_eq!(a.share_count(), 1);
+        assert_eq!(b.into_inner(), "[1, 2]".parse::<DValue>().unwrap());
+        #[cfg(feature = "json")]
+        assert_eq!(a.to_json(), r#"{"List":["None","None"]}"#);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::binary_util::Binary;
+use crate::{DValue, DigestWriter};
+use hmac::{Hmac, KeyInit, Mac};
+use sha2::Sha256;
+use std::fmt;
+
+const TAG_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The tag doesn't match: the value was changed or signed with another
+    /// key.
+    Invalid,
+
+    /// The value passed to `SignedDValue::try_from` isn't a
+    /// `(value, binary)` tuple with a 32-byte tag.
+    Malformed,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureError::Invalid => write!(f, "signature does not match"),
+            SignatureError::Malformed => write!(f, "not a signed value"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// A value together with an HMAC-SHA256 tag over its canonical encoding.
+///
+/// To send it, convert it into a `DValue`, which is the tuple
+/// `(value, binary!(tag))`, and write that in the text format; the
+/// receiver parses it, converts it back with `try_from` and calls `verify`.
+/// JSON drops the tuple and binary types, so it can't carry a signed value.
+///
+/// ```
+/// use datastruct::sign::SignedDValue;
+/// use datastruct::DValue;
+///
+/// let key = b"webhook secret";
+/// let event = "{\"type\": \"paid\", \"amount\": 42}".parse::<DValue>().unwrap();
+/// let signed: DValue = event.sign(key).into();
+/// let wire = signed.to_canonical_string();
+///
+/// let received = SignedDValue::try_from(wire.parse::<DValue>().unwrap()).unwrap();
+/// assert_eq!(received.verify(key), Ok(&event));
+/// assert!(received.verify(b"other key").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedDValue {
+    value: DValue,
+    tag: [u8; TAG_LEN],
+}
+
+impl SignedDValue {
+    /// Returns the value if the tag was made with `key` over this exact
+    /// value. The comparison takes the same time wherever the tags differ.
+    pub fn verify(&self, key: &[u8]) -> Result<&DValue, SignatureError> {
+        mac(&self.value, key)
+            .verify_slice(&self.tag)
+            .map_err(|_| SignatureError::Invalid)?;
+        Ok(&self.value)
+    }
+
+    /// Like `verify`, returning the value itself.
+    pub fn into_verified(self, key: &[u8]) -> Result<DValue, SignatureError> {
+        self.verify(key)?;
+        Ok(self.value)
+    }
+
+    /// The value, whether or not it has been verified.
+    pub fn unverified(&self) -> &DValue {
+        &self.value
+    }
+
+    pub fn tag(&self) -> &[u8; TAG_LEN] {
+        &self.tag
+    }
+}
+
+impl From<SignedDValue> for DValue {
+    fn from(signed: SignedDValue) -> Self {
+        let tag = DValue::BinaryUtil(Binary::new(signed.tag.to_vec()));
+        DValue::Tuple((Box::new(signed.value), Box::new(tag)))
+    }
+}
+
+impl TryFrom<DValue> for SignedDValue {
+    type Error = SignatureError;
+
+    fn try_from(value: DValue) -> Result<Self, SignatureError> {
+        let DValue::Tuple((value, tag)) = value else {
+            return Err(SignatureError::Malformed);
+        };
+        let DValue::BinaryUtil(tag) = *tag else {
+            return Err(SignatureError::Malformed);
+        };
+        let tag = tag
+            .as_bytes()
+            .try_into()
+            .map_err(|_| SignatureError::Malformed)?;
+        Ok(SignedDValue { value: *value, tag })
+    }
+}
+
+impl DValue {
+    /// Signs a copy of the value with HMAC-SHA256 under `key`. Keys of any
+    /// length work, but should be at least 32 random bytes.
+    pub fn sign(&self, key: &[u8]) -> SignedDValue {
+        SignedDValue {
+            value: self.clone(),
+            tag: mac(self, key).finalize().into_bytes().into(),
+        }
+    }
+}
+
+fn mac(value: &DValue, key: &[u8]) -> Hmac<Sha256> {
+    let mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    let mut writer = DigestWriter(mac);
+    // Hashing can't fail.
+    let _ = value.write_text(&mut writer, true);
+    writer.0
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::binary_util::Binary;
+    use crate::sign::{SignatureError, SignedDValue};
+    use crate::DValue;
+
+    #[test]
+    fn tampering_is_detected() {
+        let key = [7u8; 32];
+        let doc = "{\"b\": [1, none], \"a\": (true, binary!(aGk=))}"
+            .parse::<DValue>()
+            .unwrap();
+        let signed = doc.sign(&key);
+
+        // Key order doesn't matter.
+        let reordered = "{\"a\": (true, binary!(aGk=)), \"b\": [1, none]}"
+            .parse::<DValue>()
+            .unwrap();
+        assert_eq!(reordered.sign(&key), signed);
+
+        let wire = DValue::to_canonical_string(&signed.clone().into());
+        let tampered = wire.replace("[1,", "[2,").parse::<DValue>().unwrap();
+        let tampered = SignedDValue::try_from(tampered).unwrap();
+        assert_eq!(tampered.verify(&key), Err(SignatureError::Invalid));
+
+        assert_eq!(signed.clone().into_verified(&key), Ok(doc));
+        assert_eq!(signed.verify(&[8u8; 32]), Err(SignatureError::Invalid));
+    }
+
+    #[test]
+    fn malformed() {
+        let short = DValue::Tuple((
+            Box::new(DValue::None),
+            Box::new(DValue::BinaryUtil(Binary::new(vec![0; 31]))),
+        ));
+
+        assert_eq!(
+            SignedDValue::try_from(short),
+            Err(SignatureError::Malformed)
+        );
+        assert_eq!(
+            SignedDValue::try_from(DValue::None),
+            Err(SignatureError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use crate::DValue;
+use std::ops::{Deref, DerefMut};
+
+/// A `DValue` that caches its `size()` and `len()`.
+///
+/// Reads go through `Deref` and never recompute anything. Mutation is only
+/// possible through `get_mut`, whose guard refreshes the cached metadata
+/// when it is dropped, so the cache can't drift from the value.
+///
+/// ```
+/// use datastruct::sized::SizedDValue;
+/// use datastruct::DValue;
+///
+/// let mut doc = SizedDValue::new("[\"abc\", 1]".parse::<DValue>().unwrap());
+/// assert_eq!((doc.size(), doc.len()), (11, Some(2)));
+///
+/// if let DValue::List(items) = &mut *doc.get_mut() {
+///     items.push(DValue::Boolean(true));
+/// }
+///
+/// assert_eq!((doc.size(), doc.len()), (12, Some(3)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizedDValue {
+    value: DValue,
+    size: usize,
+    len: Option<usize>,
+}
+
+impl SizedDValue {
+    pub fn new(value: DValue) -> Self {
+        let mut sized = Self {
+            value,
+            size: 0,
+            len: None,
+        };
+        sized.refresh();
+        sized
+    }
+
+    /// The cached result of `DValue::size`.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The cached result of `DValue::len`.
+    pub fn len(&self) -> Option<usize> {
+        self.len
+    }
+
+    /// Whether the cached `len` is `Some(0)`, as `DValue::is_empty`.
+    pub fn is_empty(&self) -> bool {
+        self.len == Some(0)
+    }
+
+    /// Returns a guard for mutating the value; the cache is refreshed when
+    /// the guard is dropped.
+    pub fn get_mut(&mut self) -> SizedMut<'_> {
+        SizedMut { inner: self }
+    }
+
+    pub fn into_inner(self) -> DValue {
+        self.value
+    }
+
+    fn refresh(&mut self) {
+        self.size = self.value.size();
+        self.len = self.value.len();
+    }
+}
+
+impl Deref for SizedDValue {
+    type Target = DValue;
+
+    fn deref(&self) -> &DValue {
+        &self.value
+    }
+}
+
+impl From<DValue> for SizedDValue {
+    fn from(value: DValue) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Mutable access to a `SizedDValue`, returned by `SizedDValue::get_mut`.
+pub struct SizedMut<'a> {
+    inner: &'a mut SizedDValue,
+}
+
+impl Deref for SizedMut<'_> {
+    type Target = DValue;
+
+    fn deref(&self) -> &DValue {
+        &self.inner.value
+    }
+}
+
+impl DerefMut for SizedMut<'_> {
+    fn deref_mut(&mut self) -> &mut DValue {
+        &mut self.inner.value
+    }
+}
+
+impl Drop for SizedMut<'_> {
+    fn drop(&mut self) {
+        self.inner.refresh();
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::sized::SizedDValue;
+    use crate::DValue;
+
+    #[test]
+    fn cache_follows_mutation() {
+        let mut doc = SizedDValue::from(
+            "{\"a\": \"xy\", \"b\": (1, true)}"
+                .parse::<DValue>()
+                .unwrap(),
+        );
+        assert_eq!((doc.size(), doc.len()), (11, Some(2)));
+
+        doc.get_mut().incr("b[0]", 1.0).unwrap();
+        assert_eq!(doc.size(), 11);
+
+        *doc.get_mut().get_path_mut("a").unwrap() = DValue::None;
+        assert_eq!((doc.size(), doc.len()), (9, Some(2)));
+
+        // The cache agrees with `DValue` on every variant.
+        for text in ["\"héllo\"", "\"\"", "binary!(aGk=)", "[]", "1", "none"] {
+            *doc.get_mut() = text.parse().unwrap();
+            assert_eq!(doc.len(), doc.get_mut().len(), "{}", text);
+            assert_eq!(doc.is_empty(), doc.get_mut().is_empty(), "{}", text);
+        }
+        assert_eq!(doc.into_inner(), DValue::None);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{DValue, Map};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// A dict that keeps its entries in key order, for when iteration and
+/// output have to be deterministic or keys need range scans.
+///
+/// It derefs to the underlying `BTreeMap` and converts to and from
+/// `DValue::Dict`. Dicts nested inside the values are ordinary
+/// `DValue::Dict`s, but serializing a `SortedDict` writes them in key order
+/// too, as `DValue::sorted` does.
+///
+/// ```
+/// use datastruct::sorted::SortedDict;
+/// use datastruct::DValue;
+///
+/// let mut dict = SortedDict::new();
+/// dict.insert("b".to_string(), DValue::Number(2.0));
+/// dict.insert("a".to_string(), DValue::Number(1.0));
+/// dict.insert("c".to_string(), DValue::Number(3.0));
+///
+/// assert_eq!(dict.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+/// assert_eq!(dict.range("b".to_string()..).count(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct SortedDict(BTreeMap<String, DValue>);
+
+impl SortedDict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_inner(self) -> BTreeMap<String, DValue> {
+        self.0
+    }
+}
+
+impl Deref for SortedDict {
+    type Target = BTreeMap<String, DValue>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for SortedDict {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<BTreeMap<String, DValue>> for SortedDict {
+    fn from(entries: BTreeMap<String, DValue>) -> Self {
+        Self(entries)
+    }
+}
+
+impl From<Map<String, DValue>> for SortedDict {
+    fn from(entries: Map<String, DValue>) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl From<SortedDict> for DValue {
+    fn from(dict: SortedDict) -> Self {
+        DValue::Dict(dict.0.into_iter().collect())
+    }
+}
+
+impl FromIterator<(String, DValue)> for SortedDict {
+    fn from_iter<I: IntoIterator<Item = (String, DValue)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for SortedDict {
+    type Item = (String, DValue);
+    type IntoIter = alloc::collections::btree_map::IntoIter<String, DValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Serialize for SortedDict {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, value.sorted())))
+    }
+}
+
+impl<'a> IntoIterator for &'a SortedDict {
+    type Item = (&'a String, &'a DValue);
+    type IntoIter = alloc::collections::btree_map::Iter<'a, String, DValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl DValue {
+    /// Wraps the value so that serializing it writes the entries of every
+    /// dict, at any depth, in key order. Otherwise the output is the same
+    /// as serializing the value itself, and deserializes back to it.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let doc = "{\"b\": [{\"y\": 1, \"x\": 2}], \"a\": true}".parse::<DValue>().unwrap();
+    /// let json = serde_json::to_string(&doc.sorted()).unwrap();
+    ///
+    /// assert_eq!(
+    ///     json,
+    ///     "{\"Dict\":{\"a\":{\"Boolean\":true},\"b\":{\"List\":[{\"Dict\":{\"x\":{\"Number\":2.0},\"y\":{\"Number\":1.0}}}]}}}"
+    /// );
+    /// assert_eq!(serde_json::from_str::<DValue>(&json).unwrap(), doc);
+    /// ```
+    pub fn sorted(&self) -> Sorted<'_> {
+        Sorted(self)
+    }
+}
+
+/// A `DValue` that serializes with every dict in key order, returned by
+/// `DValue::sorted`.
+#[derive(Debug, Clone, Copy)]
+pub struct Sorted<'a>(&'a DValue);
+
+impl Serialize for Sorted<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The variant indices and names of the derived `Serialize` for `DValue`.
+        match self.0 {
+            DValue::List(list) => {
+                serializer.serialize_newtype_variant("DValue", 4, "List", &List(list))
+            }
+            DValue::Dict(dict) => {
+                serializer.serialize_newtype_variant("DValue", 5, "Dict", &Dict(dict))
+            }
+            DValue::Tuple((first, second)) => serializer.serialize_newtype_variant(
+                "DValue",
+                6,
+                "Tuple",
+                &(first.sorted(), second.sorted()),
+            ),
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+struct List<'a>(&'a [DValue]);
+
+impl Serialize for List<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(DValue::sorted))
+    }
+}
+
+struct Dict<'a>(&'a Map<String, DValue>);
+
+impl Serialize for Dict<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        serializer.collect_map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, value.sorted())),
+        )
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::sorted::SortedDict;
+    use crate::DValue;
+
+    #[test]
+    fn round_trip() {
+        let value = "{\"b\": 1, \"c\": {\"z\": 2, \"y\": 3}, \"a\": 4}"
+            .parse::<DValue>()
+            .unwrap();
+        let DValue::Dict(entries) = value.clone() else {
+            unreachable!()
+        };
+
+        let sorted = SortedDict::from(entries);
+        assert_eq!(sorted.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+        #[cfg(feature = "json")]
+        assert!(serde_json::to_string(&sorted)
+            .unwrap()
+            .starts_with("{\"a\":{\"Number\":4.0},\"b\":"));
+        let back: DValue = sorted.into();
+        assert_eq!(back, value);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serializes_nested_dicts_in_order() {
+        let value = "{\"b\": (1, {\"z\": 2, \"y\": 3}), \"c\": {\"z\": [{\"q\": 1, \"p\": 2}]}, \"a\": binary!(aGk=)}"
+            .parse::<DValue>()
+            .unwrap();
+        let json = serde_json::to_string(&value.sorted()).unwrap();
+
+        let keys = [
+            "\"a\"", "\"b\"", "\"y\"", "\"z\"", "\"c\"", "\"p\"", "\"q\"",
+        ];
+        let positions: Vec<_> = keys.iter().map(|key| json.find(key).unwrap()).collect();
+        assert!(
+            positions.windows(2).all(|pair| pair[0] < pair[1]),
+            "{}",
+            json
+        );
+        assert_eq!(serde_json::from_str::<DValue>(&json).unwrap(), value);
+
+        let DValue::Dict(entries) = value.clone() else {
+            unreachable!()
+        };
+        assert_eq!(
+            serde_json::to_string(&SortedDict::from(entries)).unwrap(),
+            serde_json::to_string(&value.sorted()).unwrap()[8..json.len() - 1]
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use crate::convert::DType;
+use crate::DValue;
+use alloc::collections::BTreeSet;
+
+/// Structural metrics of a value, returned by `DValue::stats`.
+///
+/// ```
+/// use datastruct::convert::DType;
+/// use datastruct::DValue;
+///
+/// let doc = "{\"a\": [1, 2, 3], \"b\": {\"a\": true}}"
+///     .parse::<DValue>()
+///     .unwrap();
+/// let stats = doc.stats();
+///
+/// assert_eq!((stats.depth, stats.node_count), (2, 7));
+/// assert_eq!(stats.count(DType::Number), 3);
+/// assert_eq!(stats.max_list_len, 3);
+/// assert_eq!((stats.key_count, stats.distinct_keys), (3, 2));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// See `DValue::depth`.
+    pub depth: usize,
+
+    /// See `DValue::node_count`.
+    pub node_count: usize,
+
+    /// The length of the longest list, or 0 if there are none.
+    pub max_list_len: usize,
+
+    /// The number of dict entries, counting every dict.
+    pub key_count: usize,
+
+    /// The number of different keys used across all dicts.
+    pub distinct_keys: usize,
+
+    counts: [usize; 8],
+}
+
+impl Stats {
+    /// The number of values of type `dtype`, including the root.
+    pub fn count(&self, dtype: DType) -> usize {
+        self.counts[dtype as usize]
+    }
+}
+
+impl DValue {
+    /// How deeply containers are nested: 0 for a scalar, and one more than
+    /// the deepest child for a list, dict or tuple. Empty containers have a
+    /// depth of 1.
+    pub fn depth(&self) -> usize {
+        let children = match self {
+            DValue::List(list) => list.iter().map(DValue::depth).max(),
+            DValue::Dict(dict) => dict.values().map(DValue::depth).max(),
+            DValue::Tuple(tuple) => Some(tuple.0.depth().max(tuple.1.depth())),
+            _ => return 0,
+        };
+        1 + children.unwrap_or(0)
+    }
+
+    /// The number of values in the tree, including this one.
+    pub fn node_count(&self) -> usize {
+        1 + match self {
+            DValue::List(list) => list.iter().map(DValue::node_count).sum(),
+            DValue::Dict(dict) => dict.values().map(DValue::node_count).sum(),
+            DValue::Tuple(tuple) => tuple.0.node_count() + tuple.1.node_count(),
+            _ => 0,
+        }
+    }
+
+    /// Collects `depth`, `node_count`, per-type counts and list and key
+    /// metrics in a single walk of the tree.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        let mut keys = BTreeSet::new();
+        stats.depth = collect(self, &mut stats, &mut keys);
+        stats.distinct_keys = keys.len();
+        stats
+    }
+}
+
+/// Adds `value` and its children to `stats`, returning its depth.
+fn collect<'a>(value: &'a DValue, stats: &mut Stats, keys: &mut BTreeSet<&'a str>) -> usize {
+    stats.node_count += 1;
+    stats.counts[value.dtype() as usize] += 1;
+
+    let children = match value {
+        DValue::List(list) => {
+            stats.max_list_len = stats.max_list_len.max(list.len());
+            list.iter().map(|item| collect(item, stats, keys)).max()
+        }
+        DValue::Dict(dict) => {
+            stats.key_count += dict.len();
+            dict.iter()
+                .map(|(key, item)| {
+                    keys.insert(key);
+                    collect(item, stats, keys)
+                })
+                .max()
+        }
+        DValue::Tuple(tuple) => {
+            Some(collect(&tuple.0, stats, keys).max(collect(&tuple.1, stats, keys)))
+        }
+        _ => return 0,
+    };
+    1 + children.unwrap_or(0)
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::convert::DType;
+    use crate::DValue;
+
+    #[test]
+    fn structural_metrics() {
+        let doc = "{\"users\": [{\"name\": \"ada\", \"tags\": []}, {\"name\": \"bo\"}], \
+                   \"pair\": (none, binary!(aGk=)), \"ok\": true}"
+            .parse::<DValue>()
+            .unwrap();
+
+        assert_eq!(doc.depth(), 4);
+        assert_eq!(doc.node_count(), 11);
+
+        let stats = doc.stats();
+        assert_eq!(stats.depth, doc.depth());
+        assert_eq!(stats.node_count, doc.node_count());
+        assert_eq!(stats.count(DType::Dict), 3);
+        assert_eq!(stats.count(DType::List), 2);
+        assert_eq!(stats.count(DType::String), 2);
+        assert_eq!(stats.count(DType::Binary), 1);
+        assert_eq!(stats.count(DType::Number), 0);
+        assert_eq!(stats.max_list_len, 2);
+        assert_eq!((stats.key_count, stats.distinct_keys), (6, 5));
+    }
+
+    #[test]
+    fn scalars_and_empty_containers() {
+        assert_eq!(DValue::Number(1.0).depth(), 0);
+        assert_eq!(DValue::Number(1.0).node_count(), 1);
+        assert_eq!(DValue::List(vec![]).depth(), 1);
+        assert_eq!(DValue::None.stats().count(DType::None), 1);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::DValue;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A value in a `Store` along with the version of its last write.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Versioned {
+    pub value: DValue,
+    pub version: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    /// A key changed since it was read. Version `0` means absent.
+    Conflict {
+        key: String,
+        expected: u64,
+        found: u64,
+    },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Conflict {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "conflict on {:?}: expected version {}, found {}",
+                key, expected, found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+#[derive(Debug, Default)]
+struct Inner {
+    entries: HashMap<String, Versioned>,
+    clock: u64,
+}
+
+impl Inner {
+    fn version(&self, key: &str) -> u64 {
+        self.entries.get(key).map_or(0, |entry| entry.version)
+    }
+
+    fn write(&mut self, key: &str, value: Option<DValue>) -> u64 {
+        match value {
+            Some(value) => {
+                self.clock += 1;
+                let version = self.clock;
+                self.entries
+                    .insert(key.to_string(), Versioned { value, version });
+                version
+            }
+            None => {
+                self.entries.remove(key);
+                0
+            }
+        }
+    }
+}
+
+/// A keyed collection of documents with optimistic transactions.
+///
+/// Every write stamps its key with a new version taken from a counter shared
+/// by the whole store, so a version is never reused, even after the key is
+/// deleted and recreated. An absent key has version `0`. Writers that may
+/// race can use `compare_and_set`, or group several reads and writes in a
+/// `Transaction` that fails on commit if any key it touched was changed by
+/// someone else in the meantime.
+///
+/// The store locks internally, so it can be shared between threads in an
+/// `Arc`.
+///
+/// ```
+/// use datastruct::store::{Store, StoreError};
+/// use datastruct::DValue;
+///
+/// let store = Store::new();
+/// store.put("alice", "{\"balance\": 10}".parse::<DValue>().unwrap());
+/// store.put("bob", "{\"balance\": 0}".parse::<DValue>().unwrap());
+///
+/// let mut tx = store.begin();
+/// let mut alice = tx.get("alice").unwrap();
+/// let mut bob = tx.get("bob").unwrap();
+/// alice.incr("balance", -5.0).unwrap();
+/// bob.incr("balance", 5.0).unwrap();
+/// tx.put("alice", alice);
+/// tx.put("bob", bob);
+///
+/// // A concurrent write makes the transaction fail as a whole.
+/// store.put("bob", "{\"balance\": 1}".parse::<DValue>().unwrap());
+/// assert!(matches!(tx.commit(), Err(StoreError::Conflict { key, .. }) if key == "bob"));
+/// assert_eq!(store.get("alice").unwrap().value.get_path("balance"), Some(&DValue::Number(10.0)));
+/// ```
+#[derive(Debug, Default)]
+pub struct Store {
+    inner: RwLock<Inner>,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the value at `key` and its version.
+    pub fn get(&self, key: &str) -> Option<Versioned> {
+        self.read().entries.get(key).cloned()
+    }
+
+    /// Returns the version of `key`, or `0` if it is absent.
+    pub fn version(&self, key: &str) -> u64 {
+        self.read().version(key)
+    }
+
+    /// Stores `value` at `key` unconditionally and returns its new version.
+    pub fn put(&self, key: &str, value: DValue) -> u64 {
+        self.write().write(key, Some(value))
+    }
+
+    /// Removes `key` and returns what it held.
+    pub fn delete(&self, key: &str) -> Option<Versioned> {
+        self.write().entries.remove(key)
+    }
+
+    /// Stores `value` at `key` only if the key is still at `expected`, and
+    /// returns the new version. Pass `0` to create a key that must not exist.
+    pub fn compare_and_set(
+        &self,
+        key: &str,
+        expected: u64,
+        value: DValue,
+    ) -> Result<u64, StoreError> {
+        let mut inner = self.write();
+        let found = inner.version(key);
+
+        if found != expected {
+            return Err(StoreError::Conflict {
+                key: key.to_string(),
+                expected,
+                found,
+            });
+        }
+        Ok(inner.write(key, Some(value)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the keys currently in the store, in no particular order.
+    pub fn keys(&self) -> Vec<String> {
+        self.read().entries.keys().cloned().collect()
+    }
+
+    /// Starts a transaction. Nothing it writes is visible to others until
+    /// `commit`, and dropping it without committing rolls it back.
+    pub fn begin(&self) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            seen: HashMap::new(),
+            writes: HashMap::new(),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Inner> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A set of reads and writes applied to a `Store` atomically, returned by
+/// `Store::begin`.
+#[derive(Debug)]
+pub struct Transaction<'a> {
+    store: &'a Store,
+    /// The version of every key touched, as of the first time it was.
+    seen: HashMap<String, u64>,
+    /// Pending writes; `None` is a delete.
+    writes: HashMap<String, Option<DValue>>,
+}
+
+impl Transaction<'_> {
+    /// Returns the value at `key`, including this transaction's own
+    /// uncommitted writes.
+    pub fn get(&mut self, key: &str) -> Option<DValue> {
+        if let Some(pending) = self.writes.get(key) {
+            return pending.clone();
+        }
+
+        let entry = self.store.get(key);
+        self.seen
+            .entry(key.to_string())
+            .or_insert_with(|| entry.as_ref().map_or(0, |e| e.version));
+        entry.map(|e| e.value)
+    }
+
+    pub fn put(&mut self, key: &str, value: DValue) {
+        self.touch(key);
+        self.writes.insert(key.to_string(), Some(value));
+    }
+
+    pub fn delete(&mut self, key: &str) {
+        self.touch(key);
+        self.writes.insert(key.to_string(), None);
+    }
+
+    /// Applies every write, or none of them if a key this transaction
+    /// touched has been changed since.
+    pub fn commit(self) -> Result<(), StoreError> {
+        let mut inner = self.store.write();
+
+        for (key, &expected) in &self.seen {
+            let found = inner.version(key);
+            if found != expected {
+                return Err(StoreError::Conflict {
+                    key: key.clone(),
+                    expected,
+                    found,
+                });
+            }
+        }
+
+        for (key, value) in self.writes {
+            inner.write(&key, value);
+        }
+        Ok(())
+    }
+
+    /// Discards every write. Equivalent to dropping the transaction.
+    pub fn rollback(self) {}
+
+    fn touch(&mut self, key: &str) {
+        if !self.seen.contains_key(key) {
+            let version = self.store.version(key);
+            self.seen.insert(key.to_string(), version);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::store::{Store, StoreError};
+    use crate::DValue;
+
+    #[test]
+    fn compare_and_set() {
+        let store = Store::new();
+        let v1 = store.compare_and_set("k", 0, DValue::Number(1.0)).unwrap();
+
+        assert_eq!(
+            store.compare_and_set("k", 0, DValue::Number(2.0)),
+            Err(StoreError::Conflict {
+                key: "k".to_string(),
+                expected: 0,
+                found: v1
+            })
+        );
+        let v2 = store.compare_and_set("k", v1, DValue::Number(2.0)).unwrap();
+        assert!(v2 > v1);
+
+        store.delete("k");
+        assert_eq!(store.version("k"), 0);
+        assert!(store.put("k", DValue::None) > v2);
+    }
+
+    #[test]
+    fn transactions() {
+        let store = Store::new();
+        store.put("a", DValue::Number(1.0));
+
+        let mut tx = store.begin();
+        tx.put("b", DValue::Number(2.0));
+        tx.delete("a");
+        assert_eq!(tx.get("a"), None);
+        assert_eq!(tx.get("b"), Some(DValue::Number(2.0)));
+        assert_eq!(store.get("b"), None);
+        tx.commit().unwrap();
+
+        assert_eq!(store.keys(), vec!["b".to_string()]);
+
+        let mut tx = store.begin();
+        tx.put("c", DValue::None);
+        tx.rollback();
+        assert_eq!(store.len(), 1);
+
+        let mut tx = store.begin();
+        tx.get("c");
+        store.put("c", DValue::Boolean(true));
+        tx.put("d", DValue::None);
+        assert!(tx.commit().is_err());
+        assert_eq!(store.get("d"), None);
+    }
+}
@@ -0,0 +1,141 @@
+//! String operations that take and return `DValue`s.
+//!
+//! Each works on `DValue::String` and gives `DValue::None` for any other
+//! variant, as the arithmetic operators do, so they can be chained without
+//! unwrapping. Lengths and positions count chars, not bytes.
+//!
+//! ```
+//! use datastruct::DValue;
+//!
+//! let value = DValue::String("  Hello, World  ".to_string());
+//!
+//! assert_eq!(value.trim().to_upper(), DValue::String("HELLO, WORLD".to_string()));
+//! assert_eq!(value.trim().substring(7, 5), DValue::String("World".to_string()));
+//! assert_eq!(DValue::Number(1.0).trim(), DValue::None);
+//! ```
+
+use crate::DValue;
+use alloc::string::ToString;
+
+impl DValue {
+    fn map_str(&self, f: impl FnOnce(&str) -> DValue) -> DValue {
+        match self {
+            DValue::String(s) => f(s),
+            _ => DValue::None,
+        }
+    }
+
+    /// The length of a string in chars.
+    pub fn str_len(&self) -> DValue {
+        self.map_str(|s| DValue::Number(s.chars().count() as f64))
+    }
+
+    /// The length of a string in bytes of UTF-8.
+    pub fn byte_len(&self) -> DValue {
+        self.map_str(|s| DValue::Number(s.len() as f64))
+    }
+
+    /// Up to `len` chars starting at char `start`. Out of range positions
+    /// give a shorter or empty string rather than `None`.
+    pub fn substring(&self, start: usize, len: usize) -> DValue {
+        self.map_str(|s| DValue::String(s.chars().skip(start).take(len).collect()))
+    }
+
+    /// Splits a string on `sep` into a list of strings.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let tags = DValue::String("a,b,,c".to_string()).split_to_list(",");
+    /// assert_eq!(tags.to_string(), "[\"a\",\"b\",\"\",\"c\"]");
+    /// ```
+    pub fn split_to_list(&self, sep: &str) -> DValue {
+        self.map_str(|s| {
+            DValue::List(
+                s.split(sep)
+                    .map(|part| DValue::String(part.to_string()))
+                    .collect(),
+            )
+        })
+    }
+
+    /// Removes leading and trailing whitespace.
+    pub fn trim(&self) -> DValue {
+        self.map_str(|s| DValue::String(s.trim().to_string()))
+    }
+
+    pub fn to_upper(&self) -> DValue {
+        self.map_str(|s| DValue::String(s.to_uppercase()))
+    }
+
+    pub fn to_lower(&self) -> DValue {
+        self.map_str(|s| DValue::String(s.to_lowercase()))
+    }
+
+    /// Whether a string matches `pattern` anywhere, as a `DValue::Boolean`.
+    /// Fails only if `pattern` isn't a valid regex.
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let email = DValue::String("ada@example.com".to_string());
+    ///
+    /// assert_eq!(email.matches_regex(r"^\w+@"), Ok(DValue::Boolean(true)));
+    /// assert_eq!(DValue::None.matches_regex(r"^\w+@"), Ok(DValue::None));
+    /// assert!(email.matches_regex("(").is_err());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn matches_regex(&self, pattern: &str) -> Result<DValue, regex::Error> {
+        let regex = regex::Regex::new(pattern)?;
+        Ok(self.map_str(|s| DValue::Boolean(regex.is_match(s))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::DValue;
+
+    fn string(s: &str) -> DValue {
+        DValue::String(s.to_string())
+    }
+
+    #[test]
+    fn string_ops() {
+        let text = string("héllo wörld");
+
+        assert_eq!(text.str_len(), DValue::Number(11.0));
+        assert_eq!(text.byte_len(), DValue::Number(13.0));
+        assert_eq!(text.substring(1, 4), string("éllo"));
+        assert_eq!(text.substring(6, 100), string("wörld"));
+        assert_eq!(text.substring(20, 1), string(""));
+        assert_eq!(text.to_upper(), string("HÉLLO WÖRLD"));
+        assert_eq!(string("ÀB").to_lower(), string("àb"));
+        assert_eq!(string("\t x \n").trim(), string("x"));
+        assert_eq!(
+            string("a b").split_to_list(" "),
+            DValue::List(vec![string("a"), string("b")])
+        );
+        assert_eq!(
+            string("").split_to_list(","),
+            DValue::List(vec![string("")])
+        );
+    }
+
+    #[test]
+    fn other_variants() {
+        let values = [
+            DValue::None,
+            DValue::Number(1.0),
+            DValue::List(vec![string("a")]),
+        ];
+
+        for value in values {
+            assert_eq!(value.str_len(), DValue::None);
+            assert_eq!(value.substring(0, 1), DValue::None);
+            assert_eq!(value.split_to_list(","), DValue::None);
+            assert_eq!(value.to_upper(), DValue::None);
+            #[cfg(feature = "std")]
+            assert_eq!(value.matches_regex("a"), Ok(DValue::None));
+        }
+    }
+}
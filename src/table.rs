@@ -0,0 +1,69 @@
+//! Column inference shared by the Arrow and polars conversions.
+
+use crate::DValue;
+use std::collections::BTreeMap;
+
+/// The column type inferred from the non-null cells seen so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Kind {
+    Empty,
+    Number,
+    Boolean,
+    String,
+    Binary,
+    /// Mixed or nested values, stored in the text format.
+    Text,
+}
+
+impl Kind {
+    fn of(value: &DValue) -> Kind {
+        match value {
+            DValue::None => Kind::Empty,
+            DValue::Number(_) => Kind::Number,
+            DValue::Boolean(_) => Kind::Boolean,
+            DValue::String(_) => Kind::String,
+            DValue::BinaryUtil(_) => Kind::Binary,
+            _ => Kind::Text,
+        }
+    }
+
+    fn merge(self, other: Kind) -> Kind {
+        match (self, other) {
+            (a, Kind::Empty) => a,
+            (Kind::Empty, b) => b,
+            (a, b) if a == b => a,
+            _ => Kind::Text,
+        }
+    }
+}
+
+/// Checks that `value` is a list of dicts and infers a kind for every key,
+/// in sorted order. On failure, returns the type of the offending value.
+pub(crate) fn columns(value: &DValue) -> Result<(&[DValue], BTreeMap<&str, Kind>), String> {
+    let DValue::List(rows) = value else {
+        return Err(value.datatype());
+    };
+
+    let mut kinds: BTreeMap<&str, Kind> = BTreeMap::new();
+    for row in rows {
+        let DValue::Dict(entries) = row else {
+            return Err(row.datatype());
+        };
+        for (key, value) in entries {
+            let kind = kinds.entry(key).or_insert(Kind::Empty);
+            *kind = kind.merge(Kind::of(value));
+        }
+    }
+    Ok((rows, kinds))
+}
+
+/// The cells of column `key`, with missing keys and `DValue::None` as `None`.
+pub(crate) fn cells<'a>(
+    rows: &'a [DValue],
+    key: &'a str,
+) -> impl Iterator<Item = Option<&'a DValue>> + 'a {
+    rows.iter().map(move |row| match row.dict_ref()?.get(key) {
+        Some(DValue::None) | None => None,
+        value => value,
+    })
+}
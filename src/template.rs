@@ -0,0 +1,156 @@
+use crate::json;
+use crate::path::{Path, PathError};
+use crate::{DValue, NonFinite};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    /// A `{{` at this byte offset has no matching `}}`.
+    Unclosed(usize),
+
+    /// The placeholder at `position` holds a malformed path.
+    InvalidPath { position: usize, error: PathError },
+
+    /// The placeholder at `position` uses a filter that doesn't exist.
+    UnknownFilter { position: usize, name: String },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderError::Unclosed(position) => write!(f, "unclosed placeholder at {}", position),
+            RenderError::InvalidPath { position, error } => {
+                write!(f, "placeholder at {}: {}", position, error)
+            }
+            RenderError::UnknownFilter { position, name } => {
+                write!(f, "placeholder at {}: unknown filter {:?}", position, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// A placeholder's value on its way through the filters. It stays a value
+/// until a filter needs text, so `json` can see the structure.
+enum Piece<'a> {
+    Value(Option<&'a DValue>),
+    Text(String),
+}
+
+impl Piece<'_> {
+    fn into_text(self) -> String {
+        match self {
+            Piece::Value(None | Some(DValue::None)) => String::new(),
+            Piece::Value(Some(DValue::String(s))) => s.clone(),
+            Piece::Value(Some(value)) => value.to_string(),
+            Piece::Text(text) => text,
+        }
+    }
+}
+
+impl DValue {
+    /// Fills in the `{{path}}` placeholders of `template` with values from
+    /// this document.
+    ///
+    /// Strings are inserted without quotes, and a missing path or `None`
+    /// inserts nothing. A placeholder can pass its value through filters,
+    /// as in `{{ user.name | upper }}`:
+    ///
+    /// - `upper`, `lower`, `trim`: change the text
+    /// - `json`: the value as plain JSON, e.g. for embedding in a script
+    ///
+    /// ```
+    /// use datastruct::DValue;
+    ///
+    /// let doc = "{\"user\": {\"name\": \" Ada \"}, \"tags\": [\"x\"]}".parse::<DValue>().unwrap();
+    /// let text = doc.render("Hi {{ user.name | trim | upper }}, tags={{tags | json}}{{missing}}").unwrap();
+    ///
+    /// assert_eq!(text, "Hi ADA, tags=[\"x\"]");
+    /// ```
+    pub fn render(&self, template: &str) -> Result<String, RenderError> {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+
+            let position = template.len() - rest.len() + start;
+            let body = &rest[start + 2..];
+            let end = body.find("}}").ok_or(RenderError::Unclosed(position))?;
+
+            out.push_str(&self.placeholder(&body[..end], position)?);
+            rest = &body[end + 2..];
+        }
+
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn placeholder(&self, body: &str, position: usize) -> Result<String, RenderError> {
+        let mut parts = body.split('|').map(str::trim);
+        let path = Path::parse(parts.next().unwrap_or_default())
+            .map_err(|error| RenderError::InvalidPath { position, error })?;
+
+        let mut piece = Piece::Value(self.get_path(&path));
+        for filter in parts {
+            piece = match filter {
+                "upper" => Piece::Text(piece.into_text().to_uppercase()),
+                "lower" => Piece::Text(piece.into_text().to_lowercase()),
+                "trim" => Piece::Text(piece.into_text().trim().to_string()),
+                "json" => {
+                    let value = match piece {
+                        Piece::Value(value) => value.cloned().unwrap_or(DValue::None),
+                        Piece::Text(text) => DValue::String(text),
+                    };
+                    let mut buf = Vec::new();
+                    json::write_plain(&mut buf, &value, NonFinite::Null)
+                        .expect("writing to a Vec can't fail");
+                    Piece::Text(String::from_utf8(buf).expect("JSON output is UTF-8"))
+                }
+                name => {
+                    return Err(RenderError::UnknownFilter {
+                        position,
+                        name: name.to_string(),
+                    })
+                }
+            };
+        }
+        Ok(piece.into_text())
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::template::RenderError;
+    use crate::DValue;
+
+    #[test]
+    fn render() {
+        let doc = "{\"n\": 3, \"s\": \"Ada\", \"list\": [1.5, true, binary!(aGk=)], \"pair\": (\"x\", 1)}".parse::<DValue>().unwrap();
+
+        assert_eq!(
+            doc.render("no placeholders"),
+            Ok("no placeholders".to_string())
+        );
+        assert_eq!(
+            doc.render("{{n}}/{{list[0]}}/{{ s | json }}/{{list|json}}/{{pair | json}}"),
+            Ok("3/1.5/\"Ada\"/[1.5,true,\"aGk=\"]/[\"x\",1]".to_string())
+        );
+        assert_eq!(
+            doc.render("{{ n | upper | json }}"),
+            Ok("\"3\"".to_string())
+        );
+        assert_eq!(doc.render("{{nope | json}}"), Ok("null".to_string()));
+
+        assert_eq!(doc.render("a {{n"), Err(RenderError::Unclosed(2)));
+        assert!(matches!(
+            doc.render("{{ n | shout }}"),
+            Err(RenderError::UnknownFilter { position: 0, name }) if name == "shout"
+        ));
+        assert!(matches!(
+            doc.render("x{{ a..b }}"),
+            Err(RenderError::InvalidPath { position: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,131 @@
+//! Unicode normalization of strings and dict keys.
+//!
+//! The same text can be encoded as different sequences of code points: `é`
+//! is either one precomposed char or `e` followed by a combining accent, and
+//! macOS, for one, stores file names in the decomposed form. Normalizing
+//! both documents to one form makes them compare, hash and fingerprint
+//! equal.
+//!
+//! ```
+//! use datastruct::unicode::NormalizationForm;
+//! use datastruct::DValue;
+//!
+//! let mut from_mac = DValue::String("cafe\u{301}.txt".to_string());
+//! let from_linux = DValue::String("caf\u{e9}.txt".to_string());
+//! assert_ne!(from_mac, from_linux);
+//!
+//! from_mac.normalize_unicode(NormalizationForm::Nfc);
+//! assert_eq!(from_mac, from_linux);
+//! ```
+
+use crate::DValue;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem;
+use unicode_normalization::{is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    /// Canonical composition: only merges sequences that mean exactly the
+    /// same text, such as `e` plus a combining acute accent into `é`.
+    Nfc,
+
+    /// Compatibility composition: also folds variants with the same meaning
+    /// but a different look, such as `ﬁ` into `fi` and full-width digits
+    /// into ASCII ones.
+    Nfkc,
+}
+
+impl NormalizationForm {
+    fn is_normalized(self, s: &str) -> bool {
+        let quick = match self {
+            NormalizationForm::Nfc => is_nfc_quick(s.chars()),
+            NormalizationForm::Nfkc => is_nfkc_quick(s.chars()),
+        };
+        quick == IsNormalized::Yes
+    }
+
+    fn apply(self, s: &str) -> String {
+        match self {
+            NormalizationForm::Nfc => s.nfc().collect(),
+            NormalizationForm::Nfkc => s.nfkc().collect(),
+        }
+    }
+
+    fn normalize(self, s: &mut String) {
+        if !self.is_normalized(s) {
+            *s = self.apply(s);
+        }
+    }
+}
+
+impl DValue {
+    /// Normalizes every string and dict key, however deeply nested, to
+    /// `form`.
+    ///
+    /// If two keys of a dict become the same, only one entry is kept: the
+    /// one whose original key sorts last.
+    pub fn normalize_unicode(&mut self, form: NormalizationForm) {
+        match self {
+            DValue::String(s) => form.normalize(s),
+            DValue::List(items) => {
+                for item in items {
+                    item.normalize_unicode(form);
+                }
+            }
+            DValue::Dict(dict) => {
+                for value in dict.values_mut() {
+                    value.normalize_unicode(form);
+                }
+                if dict.keys().all(|key| form.is_normalized(key)) {
+                    return;
+                }
+                let mut entries: Vec<_> = mem::take(dict).into_iter().collect();
+                entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+                for (mut key, value) in entries {
+                    form.normalize(&mut key);
+                    dict.insert(key, value);
+                }
+            }
+            DValue::Tuple((a, b)) => {
+                a.normalize_unicode(form);
+                b.normalize_unicode(form);
+            }
+            DValue::None | DValue::Number(_) | DValue::Boolean(_) | DValue::BinaryUtil(_) => {}
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::unicode::NormalizationForm;
+    use crate::DValue;
+
+    #[test]
+    fn strings_and_keys() {
+        let mut nfd = "{\"re\u{301}sume\u{301}\": [(\"A\u{30a}\", 1)], \"x\": \"\u{fb01}\"}"
+            .parse::<DValue>()
+            .unwrap();
+        let nfc = "{\"r\u{e9}sum\u{e9}\": [(\"\u{c5}\", 1)], \"x\": \"\u{fb01}\"}"
+            .parse::<DValue>()
+            .unwrap();
+
+        nfd.normalize_unicode(NormalizationForm::Nfc);
+        assert_eq!(nfd, nfc);
+        #[cfg(feature = "checksum")]
+        assert_eq!(nfd.fingerprint(), nfc.fingerprint());
+
+        nfd.normalize_unicode(NormalizationForm::Nfkc);
+        assert_eq!(nfd.get_path("x"), Some(&DValue::String("fi".to_string())));
+    }
+
+    #[test]
+    fn colliding_keys() {
+        let mut value = "{\"e\u{301}\": 1, \"\u{e9}\": 2}"
+            .parse::<DValue>()
+            .unwrap();
+        value.normalize_unicode(NormalizationForm::Nfc);
+
+        assert_eq!(value.to_canonical_string(), "{\"\u{e9}\":2}");
+    }
+}
@@ -0,0 +1,292 @@
+use crate::frame;
+use crate::json::check_finite;
+use crate::path::{AccessError, Path};
+use crate::{DValue, NonFinite};
+use std::fmt;
+use std::io::{self, Write};
+
+const TAG_SET: u8 = 1;
+const TAG_REMOVE: u8 = 2;
+const TAG_MERGE: u8 = 3;
+
+/// A mutation recorded in a write-ahead log.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    /// Replaces the value at the path. Missing dict keys on the way are
+    /// created, as `DValue::incr` does.
+    Set(Path, DValue),
+
+    /// Removes the dict entry or list item at the path.
+    Remove(Path),
+
+    /// Merges a value into the one at the path. Dicts are merged key by key,
+    /// recursively; anything else replaces what was there.
+    Merge(Path, DValue),
+}
+
+impl Op {
+    /// Applies the op to `doc`. An op that fails leaves `doc` unchanged.
+    pub fn apply(&self, doc: &mut DValue) -> Result<(), AccessError> {
+        match self {
+            Op::Set(path, value) => *doc.entry_path(path)? = value.clone(),
+            Op::Remove(path) => {
+                doc.remove_path(path)?;
+            }
+            Op::Merge(path, value) => doc.entry_path(path)?.merge(value.clone()),
+        }
+        Ok(())
+    }
+
+    /// Encodes the op as a frame holding `[tag: u8][path len: u32][path][value]`,
+    /// with the path in its text form and the value as JSON. Fails without
+    /// writing anything if the value holds a NaN or infinite number, which
+    /// JSON can't represent, or if the record is over 4 GiB.
+    fn encode(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        let (tag, path, value) = match self {
+            Op::Set(path, value) => (TAG_SET, path, Some(value)),
+            Op::Remove(path) => (TAG_REMOVE, path, None),
+            Op::Merge(path, value) => (TAG_MERGE, path, Some(value)),
+        };
+
+        let mut body = vec![tag];
+        frame::write_field(&mut body, path.to_string().as_bytes())?;
+        if let Some(value) = value {
+            check_finite(value, NonFinite::Error)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            body.extend(value.to_json().into_bytes());
+        }
+        frame::write(out, &body)
+    }
+
+    fn decode(body: &[u8]) -> Result<Op, String> {
+        let (&tag, rest) = body.split_first().ok_or("empty record")?;
+        let (path, value) = frame::split_field(rest).ok_or("truncated record")?;
+        let path = std::str::from_utf8(path).map_err(|e| e.to_string())?;
+        let path = Path::parse(path).map_err(|e| e.to_string())?;
+        let value = || serde_json::from_slice(value).map_err(|e| e.to_string());
+
+        match tag {
+            TAG_SET => Ok(Op::Set(path, value()?)),
+            TAG_REMOVE => Ok(Op::Remove(path)),
+            TAG_MERGE => Ok(Op::Merge(path, value()?)),
+            _ => Err(format!("unknown op {}", tag)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalError {
+    /// A complete record could not be decoded.
+    Decode { offset: usize, message: String },
+
+    /// The op at `index` could not be applied during replay.
+    Apply { index: usize, error: AccessError },
+
+    /// The compacted document could not be written as a record.
+    Encode(String),
+}
+
+impl fmt::Display for WalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalError::Decode { offset, message } => {
+                write!(f, "invalid record at byte {}: {}", offset, message)
+            }
+            WalError::Apply { index, error } => write!(f, "op {} failed: {}", index, error),
+            WalError::Encode(message) => write!(f, "cannot write record: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for WalError {}
+
+/// Appends ops to a write-ahead log.
+///
+/// Each op is written as one checksummed record, so a log whose last record
+/// was torn by a crash still reads back up to the record before it. Ops
+/// reach `out` as soon as they are appended; durability is up to the
+/// writer, e.g. calling `File::sync_data` through `get_mut`.
+///
+/// ```
+/// use datastruct::path::Path;
+/// use datastruct::wal::{self, Op, WalWriter};
+/// use datastruct::DValue;
+///
+/// let mut log = WalWriter::new(Vec::new());
+/// log.append(&Op::Set(Path::root(), "{\"tags\": [\"a\"]}".parse::<DValue>().unwrap())).unwrap();
+/// log.append(&Op::Merge(Path::root(), "{\"owner\": \"ada\"}".parse::<DValue>().unwrap())).unwrap();
+/// log.append(&Op::Remove("tags[0]".parse().unwrap())).unwrap();
+///
+/// let mut doc = DValue::None;
+/// assert_eq!(wal::replay(&mut doc, log.get_ref()), Ok(3));
+/// assert_eq!(doc.get_path("owner"), Some(&DValue::String("ada".to_string())));
+/// assert_eq!(doc.get_path("tags"), Some(&DValue::List(vec![])));
+/// ```
+#[derive(Debug)]
+pub struct WalWriter<W: Write> {
+    out: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> WalWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Appends `op`. Nothing reaches `out` if it can't be encoded.
+    pub fn append(&mut self, op: &Op) -> io::Result<()> {
+        self.buf.clear();
+        op.encode(&mut self.buf)?;
+        self.out.write_all(&self.buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// Reads the ops in a log, along with the length of the prefix holding
+/// them. Reading stops at a torn or corrupt record; truncate the log to
+/// the returned length before appending to it again.
+pub fn read(data: &[u8]) -> Result<(Vec<Op>, usize), WalError> {
+    let mut ops = Vec::new();
+    let mut offset = 0;
+
+    while let Some((body, len)) = frame::next(&data[offset..]) {
+        let op = Op::decode(body).map_err(|message| WalError::Decode { offset, message })?;
+        ops.push(op);
+        offset += len;
+    }
+    Ok((ops, offset))
+}
+
+/// Applies every op in a log to `doc` and returns how many there were.
+pub fn replay(doc: &mut DValue, data: &[u8]) -> Result<usize, WalError> {
+    let (ops, _) = read(data)?;
+
+    for (index, op) in ops.iter().enumerate() {
+        op.apply(doc)
+            .map_err(|error| WalError::Apply { index, error })?;
+    }
+    Ok(ops.len())
+}
+
+/// Replays a log onto an empty document and returns an equivalent log of a
+/// single `Set` at the root.
+pub fn compact(data: &[u8]) -> Result<Vec<u8>, WalError> {
+    let mut doc = DValue::None;
+    replay(&mut doc, data)?;
+
+    let mut out = Vec::new();
+    Op::Set(Path::root(), doc)
+        .encode(&mut out)
+        .map_err(|e| WalError::Encode(e.to_string()))?;
+    Ok(out)
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::path::{AccessError, Path};
+    use crate::wal::{self, Op, WalError, WalWriter};
+    use crate::DValue;
+
+    #[test]
+    fn replay_and_compact() {
+        let mut log = WalWriter::new(Vec::new());
+        let ops = [
+            Op::Set(
+                "a.b".parse().unwrap(),
+                "[1, 2, 3]".parse::<DValue>().unwrap(),
+            ),
+            Op::Merge(
+                Path::root(),
+                "{\"a\": {\"c\": true}}".parse::<DValue>().unwrap(),
+            ),
+            Op::Remove("a.b[1]".parse().unwrap()),
+            Op::Set(
+                "[\"odd.key\"]".parse().unwrap(),
+                "binary!(aGk=)".parse::<DValue>().unwrap(),
+            ),
+        ];
+        for op in &ops {
+            log.append(op).unwrap();
+        }
+        let mut data = log.into_inner();
+        let complete = data.len();
+        data.extend([9, 0, 0, 0, 1]);
+
+        assert_eq!(wal::read(&data), Ok((ops.to_vec(), complete)));
+
+        let mut doc = DValue::None;
+        wal::replay(&mut doc, &data).unwrap();
+        assert_eq!(
+            doc.get_path("a.b"),
+            Some(&"[1, 3]".parse::<DValue>().unwrap())
+        );
+        assert_eq!(doc.get_path("a.c"), Some(&DValue::Boolean(true)));
+        assert_eq!(doc.get_path("[\"odd.key\"]").unwrap().datatype(), "Binary");
+
+        let compacted = wal::compact(&data).unwrap();
+        let mut replayed = DValue::None;
+        assert_eq!(wal::replay(&mut replayed, &compacted), Ok(1));
+        for path in ["a.b", "a.c", "[\"odd.key\"]"] {
+            assert_eq!(replayed.get_path(path), doc.get_path(path), "{}", path);
+        }
+    }
+
+    #[test]
+    fn failed_op_is_reported() {
+        let mut log = WalWriter::new(Vec::new());
+        log.append(&Op::Set(Path::root(), "[]".parse::<DValue>().unwrap()))
+            .unwrap();
+        log.append(&Op::Remove("[0]".parse().unwrap())).unwrap();
+
+        assert_eq!(
+            wal::replay(&mut DValue::None, log.get_ref()),
+            Err(WalError::Apply {
+                index: 1,
+                error: AccessError::NotFound("[0]".parse().unwrap())
+            })
+        );
+
+        let mut doc = "{\"a\": 1}".parse::<DValue>().unwrap();
+        let before = doc.clone();
+        for path in ["b[0]", "b.c[1].d", "a.b"] {
+            let path = path.parse::<Path>().unwrap();
+            assert!(Op::Set(path.clone(), DValue::None).apply(&mut doc).is_err());
+            assert!(Op::Merge(path, before.clone()).apply(&mut doc).is_err());
+            assert_eq!(doc, before);
+        }
+    }
+
+    #[test]
+    fn refuses_non_finite_numbers() {
+        let mut log = WalWriter::new(Vec::new());
+        log.append(&Op::Set(Path::root(), DValue::Number(1.0)))
+            .unwrap();
+        let len = log.get_ref().len();
+
+        for n in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let value = DValue::List(vec![DValue::Number(n)]);
+            assert!(log.append(&Op::Set(Path::root(), value.clone())).is_err());
+            assert!(log.append(&Op::Merge(Path::root(), value)).is_err());
+        }
+        assert_eq!(log.get_ref().len(), len);
+        assert_eq!(wal::compact(log.get_ref()).map(|data| data.len()), Ok(len));
+    }
+}
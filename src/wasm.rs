@@ -0,0 +1,79 @@
+use crate::binary_util::Binary;
+use crate::DValue;
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Converts to the matching JavaScript value: `null`, a string, number or
+/// boolean, an `Array` for lists and tuples, a plain object for dicts, and
+/// a `Uint8Array` for binary data.
+impl From<&DValue> for JsValue {
+    fn from(value: &DValue) -> Self {
+        match value {
+            DValue::None => JsValue::NULL,
+            DValue::String(s) => JsValue::from_str(s),
+            DValue::Number(n) => JsValue::from_f64(*n),
+            DValue::Boolean(b) => JsValue::from_bool(*b),
+            DValue::List(items) => items.iter().map(JsValue::from).collect::<Array>().into(),
+            DValue::Dict(entries) => {
+                let object = Object::new();
+                for (k, v) in entries {
+                    // Setting a property on a fresh plain object can't fail.
+                    let _ = Reflect::set(&object, &JsValue::from_str(k), &JsValue::from(v));
+                }
+                object.into()
+            }
+            DValue::Tuple(pair) => {
+                Array::of2(&JsValue::from(&*pair.0), &JsValue::from(&*pair.1)).into()
+            }
+            DValue::BinaryUtil(bin) => Uint8Array::from(bin.as_bytes()).into(),
+        }
+    }
+}
+
+impl From<DValue> for JsValue {
+    fn from(value: DValue) -> Self {
+        JsValue::from(&value)
+    }
+}
+
+/// Converts from a JavaScript value. Arrays always become lists, and values
+/// with no equivalent, such as functions and symbols, become `DValue::None`.
+impl From<JsValue> for DValue {
+    fn from(value: JsValue) -> Self {
+        from_js(&value)
+    }
+}
+
+impl From<&JsValue> for DValue {
+    fn from(value: &JsValue) -> Self {
+        from_js(value)
+    }
+}
+
+// `DValue::from` is the inherent text parser, so the trait impls delegate here.
+fn from_js(value: &JsValue) -> DValue {
+    if let Some(s) = value.as_string() {
+        DValue::String(s)
+    } else if let Some(n) = value.as_f64() {
+        DValue::Number(n)
+    } else if let Some(b) = value.as_bool() {
+        DValue::Boolean(b)
+    } else if let Some(bytes) = value.dyn_ref::<Uint8Array>() {
+        DValue::BinaryUtil(Binary::new(bytes.to_vec()))
+    } else if let Some(array) = value.dyn_ref::<Array>() {
+        DValue::List(array.iter().map(|v| from_js(&v)).collect())
+    } else if value.is_object() && !value.is_function() {
+        let entries = Object::entries(value.unchecked_ref());
+        DValue::Dict(
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let pair: Array = entry.unchecked_into();
+                    Some((pair.get(0).as_string()?, from_js(&pair.get(1))))
+                })
+                .collect(),
+        )
+    } else {
+        DValue::None
+    }
+}
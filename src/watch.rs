@@ -0,0 +1,237 @@
+use crate::path::{AccessError, IntoPath, Path};
+use crate::DValue;
+use std::fmt;
+use std::ops::Deref;
+
+/// A mutation made through a `WatchedDValue`.
+///
+/// `old` is `None` when the value was created and `new` is `None` when it
+/// was removed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub path: Path,
+    pub old: Option<DValue>,
+    pub new: Option<DValue>,
+}
+
+/// Identifies a subscriber so it can be removed with `unsubscribe`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Subscriber = Box<dyn FnMut(&Change)>;
+
+/// A `DValue` that notifies subscribers of every change made through it.
+///
+/// Reads go through `Deref`. Mutations are only possible through the
+/// methods below, each of which reports the `Change` it made to every
+/// subscriber in the order they subscribed. Calls that leave the value as
+/// it was report nothing.
+///
+/// ```
+/// use datastruct::watch::WatchedDValue;
+/// use datastruct::DValue;
+/// use std::sync::mpsc;
+///
+/// let mut doc = WatchedDValue::new("{\"title\": \"draft\"}".parse::<DValue>().unwrap());
+/// let (tx, rx) = mpsc::channel();
+/// doc.subscribe(move |change| tx.send(change.path.to_string()).unwrap());
+///
+/// doc.set("title", DValue::String("final".to_string())).unwrap();
+/// doc.set("tags", DValue::List(vec![])).unwrap();
+/// doc.remove("title").unwrap();
+///
+/// assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec!["title", "tags", "title"]);
+/// ```
+pub struct WatchedDValue {
+    value: DValue,
+    subscribers: Vec<(SubscriptionId, Subscriber)>,
+    next_id: u64,
+}
+
+impl WatchedDValue {
+    pub fn new(value: DValue) -> Self {
+        Self {
+            value,
+            subscribers: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Registers `f` to be called with every change from now on.
+    pub fn subscribe(&mut self, f: impl FnMut(&Change) + 'static) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscribers.push((id, Box::new(f)));
+        id
+    }
+
+    /// Removes a subscriber, returning `false` if it was already removed.
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscribers.len();
+        self.subscribers.retain(|(other, _)| *other != id);
+        self.subscribers.len() != before
+    }
+
+    /// Replaces the value at `path`. Missing dict keys on the way are
+    /// created, as `DValue::incr` does.
+    pub fn set<P: IntoPath>(&mut self, path: P, value: DValue) -> Result<(), AccessError> {
+        let path = path.into_path()?;
+        let old = self.value.get_path(&path).cloned();
+        *self.value.entry_path(&path)? = value.clone();
+        self.notify(path, old, Some(value));
+        Ok(())
+    }
+
+    /// Removes the dict entry or list item at `path` and returns it.
+    ///
+    /// Removing a list item shifts the ones after it, but only the removal
+    /// itself is reported.
+    pub fn remove<P: IntoPath>(&mut self, path: P) -> Result<DValue, AccessError> {
+        let path = path.into_path()?;
+        let old = self.value.remove_path(&path)?;
+        self.notify(path, Some(old.clone()), None);
+        Ok(old)
+    }
+
+    /// Runs `f` on the value at `path`, creating it as `DValue::None` if it
+    /// is missing, and reports the result as a single change.
+    pub fn update<P: IntoPath, R>(
+        &mut self,
+        path: P,
+        f: impl FnOnce(&mut DValue) -> R,
+    ) -> Result<R, AccessError> {
+        let path = path.into_path()?;
+        let old = self.value.get_path(&path).cloned();
+        let target = self.value.entry_path(&path)?;
+        let result = f(target);
+        let new = target.clone();
+        self.notify(path, old, Some(new));
+        Ok(result)
+    }
+
+    /// Replaces the whole value, reported as a change at the root path.
+    pub fn replace(&mut self, value: DValue) -> DValue {
+        let old = std::mem::replace(&mut self.value, value.clone());
+        self.notify(Path::root(), Some(old.clone()), Some(value));
+        old
+    }
+
+    pub fn into_inner(self) -> DValue {
+        self.value
+    }
+
+    fn notify(&mut self, path: Path, old: Option<DValue>, new: Option<DValue>) {
+        if old == new {
+            return;
+        }
+
+        let change = Change { path, old, new };
+        for (_, subscriber) in &mut self.subscribers {
+            subscriber(&change);
+        }
+    }
+}
+
+impl Deref for WatchedDValue {
+    type Target = DValue;
+
+    fn deref(&self) -> &DValue {
+        &self.value
+    }
+}
+
+impl From<DValue> for WatchedDValue {
+    fn from(value: DValue) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Debug for WatchedDValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchedDValue")
+            .field("value", &self.value)
+            .field("subscribers", &self.subscribers.len())
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::path::{AccessError, Path};
+    use crate::watch::{Change, WatchedDValue};
+    use crate::DValue;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn recorded(doc: &mut WatchedDValue) -> Rc<RefCell<Vec<Change>>> {
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let sink = changes.clone();
+        doc.subscribe(move |change| sink.borrow_mut().push(change.clone()));
+        changes
+    }
+
+    fn change(path: &str, old: Option<&str>, new: Option<&str>) -> Change {
+        let value = |text: &str| text.parse::<DValue>().unwrap();
+        Change {
+            path: Path::parse(path).unwrap(),
+            old: old.map(value),
+            new: new.map(value),
+        }
+    }
+
+    #[test]
+    fn reports_changes() {
+        let mut doc = WatchedDValue::new("{\"n\": 1, \"items\": [\"a\", \"b\"]}".parse().unwrap());
+        let changes = recorded(&mut doc);
+
+        doc.set("n", DValue::Number(2.0)).unwrap();
+        doc.set("n", DValue::Number(2.0)).unwrap();
+        doc.set("meta.owner", DValue::String("ada".to_string()))
+            .unwrap();
+        assert_eq!(doc.remove("items[0]"), Ok(DValue::String("a".to_string())));
+        assert_eq!(doc.update("n", |v| v.incr("", 1.0)).unwrap(), Ok(3.0));
+        doc.replace(DValue::None);
+
+        assert_eq!(
+            *changes.borrow(),
+            vec![
+                change("n", Some("1"), Some("2")),
+                change("meta.owner", None, Some("\"ada\"")),
+                change("items[0]", Some("\"a\""), None),
+                change("n", Some("2"), Some("3")),
+                change(
+                    "",
+                    Some("{\"n\": 3, \"items\": [\"b\"], \"meta\": {\"owner\": \"ada\"}}"),
+                    Some("none")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn subscriptions() {
+        let mut doc = WatchedDValue::new("{}".parse().unwrap());
+        let first = recorded(&mut doc);
+        let second = recorded(&mut doc);
+
+        doc.set("a", DValue::Boolean(true)).unwrap();
+        let id = doc.subscribe(|_| panic!("unsubscribed"));
+        assert!(doc.unsubscribe(id));
+        assert!(!doc.unsubscribe(id));
+        doc.set("b", DValue::Boolean(true)).unwrap();
+
+        // Failed mutations report nothing.
+        assert!(matches!(
+            doc.remove("missing"),
+            Err(AccessError::NotFound(_))
+        ));
+        assert!(doc.set("a.b", DValue::None).is_err());
+        assert!(doc.set("c[0]", DValue::None).is_err());
+        assert!(doc.update("c.d[0]", |_| ()).is_err());
+        assert_eq!(doc.get_path("c"), None);
+
+        assert_eq!(first.borrow().len(), 2);
+        assert_eq!(second.borrow().len(), 2);
+        assert_eq!(doc.get_path("b"), Some(&DValue::Boolean(true)));
+    }
+}
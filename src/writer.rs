@@ -0,0 +1,251 @@
+use crate::events::{self, Event, Nesting};
+use crate::DValue;
+#[cfg(feature = "json")]
+use crate::{json, NonFinite};
+use std::io::{self, BufWriter, Write};
+
+/// The output format of a `DocumentWriter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// The compact text format, as `to_string` writes it.
+    #[default]
+    Text,
+
+    /// The JSON that `to_json` writes, with non-finite numbers as `null`.
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Writes one document piece by piece to an `io::Write`, so a huge list or
+/// dict can be exported without holding all of it in memory.
+///
+/// Containers are opened and closed with `begin_*` and `end_*`, dict
+/// entries start with `key`, and `value` writes a whole value in one go.
+/// Calls that don't form a single well-formed document fail with
+/// `io::ErrorKind::InvalidInput` and write nothing. Output is buffered;
+/// `finish` flushes it.
+///
+/// ```
+/// use datastruct::writer::{DocumentWriter, Format};
+/// use datastruct::DValue;
+///
+/// let mut writer = DocumentWriter::new(Vec::new(), Format::Text);
+/// writer.begin_dict().unwrap();
+/// writer.key("rows").unwrap();
+/// writer.begin_list().unwrap();
+/// for id in 0..3 {
+///     // Each row can be dropped as soon as it is written.
+///     let row = DValue::List(vec![DValue::Number(id as f64), DValue::Boolean(id % 2 == 0)]);
+///     writer.value(&row).unwrap();
+/// }
+/// writer.end_list().unwrap();
+/// writer.end_dict().unwrap();
+///
+/// let out = writer.finish().unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "{\"rows\":[[0,true],[1,false],[2,true]]}");
+/// ```
+pub struct DocumentWriter<W: Write> {
+    out: BufWriter<W>,
+    format: Format,
+    nesting: Nesting,
+    /// Reused for text that has to go through `fmt::Write` first.
+    scratch: String,
+}
+
+impl<W: Write> DocumentWriter<W> {
+    pub fn new(out: W, format: Format) -> Self {
+        Self {
+            out: BufWriter::new(out),
+            format,
+            nesting: Nesting::default(),
+            scratch: String::new(),
+        }
+    }
+
+    pub fn begin_list(&mut self) -> io::Result<()> {
+        self.event(Event::StartList)
+    }
+
+    pub fn end_list(&mut self) -> io::Result<()> {
+        self.event(Event::EndList)
+    }
+
+    pub fn begin_dict(&mut self) -> io::Result<()> {
+        self.event(Event::StartDict)
+    }
+
+    pub fn end_dict(&mut self) -> io::Result<()> {
+        self.event(Event::EndDict)
+    }
+
+    pub fn begin_tuple(&mut self) -> io::Result<()> {
+        self.event(Event::StartTuple)
+    }
+
+    pub fn end_tuple(&mut self) -> io::Result<()> {
+        self.event(Event::EndTuple)
+    }
+
+    /// Starts a dict entry; its value comes next.
+    pub fn key(&mut self, key: &str) -> io::Result<()> {
+        self.event(Event::Key(key.into()))
+    }
+
+    /// Writes a whole value as the next item.
+    pub fn value(&mut self, value: &DValue) -> io::Result<()> {
+        // Any scalar event stands for a value as far as nesting goes.
+        let separator = self.accept(&Event::None)?;
+        self.write_value(separator, value)
+    }
+
+    /// Writes a single event, such as one read by `EventReader`.
+    pub fn event(&mut self, event: Event) -> io::Result<()> {
+        let separator = self.accept(&event)?;
+        if let Some(value) = event.to_scalar() {
+            return self.write_value(separator, &value);
+        }
+
+        match self.format {
+            Format::Text => {
+                self.scratch.clear();
+                // Writing into a String can't fail.
+                let _ = events::write_event(&mut self.scratch, separator, &event);
+                self.out.write_all(self.scratch.as_bytes())
+            }
+            #[cfg(feature = "json")]
+            Format::Json => {
+                self.out.write_all(json_separator(separator))?;
+                match &event {
+                    Event::Key(key) => json::write_str(&mut json::Io(&mut self.out), key),
+                    Event::StartList => self.out.write_all(b"{\"List\":["),
+                    Event::StartDict => self.out.write_all(b"{\"Dict\":{"),
+                    Event::StartTuple => self.out.write_all(b"{\"Tuple\":["),
+                    Event::EndDict => self.out.write_all(b"}}"),
+                    _ => self.out.write_all(b"]}"),
+                }
+            }
+        }
+    }
+
+    /// Flushes the output and returns the writer, failing if the document
+    /// isn't complete.
+    pub fn finish(self) -> io::Result<W> {
+        if !self.nesting.is_complete() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                events::EventError::Incomplete,
+            ));
+        }
+        self.out.into_inner().map_err(|e| e.into_error())
+    }
+
+    fn accept(&mut self, event: &Event) -> io::Result<&'static str> {
+        self.nesting
+            .accept(event)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    fn write_value(&mut self, separator: &str, value: &DValue) -> io::Result<()> {
+        match self.format {
+            Format::Text => {
+                self.scratch.clear();
+                self.scratch.push_str(separator);
+                let _ = value.write_to(&mut self.scratch);
+                self.out.write_all(self.scratch.as_bytes())
+            }
+            #[cfg(feature = "json")]
+            Format::Json => {
+                self.out.write_all(json_separator(separator))?;
+                json::write_value(&mut json::Io(&mut self.out), value, NonFinite::Null, false)
+            }
+        }
+    }
+}
+
+/// JSON has no `, ` between tuple items.
+#[cfg(feature = "json")]
+fn json_separator(separator: &str) -> &[u8] {
+    separator.trim_end().as_bytes()
+}
+
+#[cfg(all(test, feature = "parser"))]
+mod test {
+    use crate::events::EventReader;
+    use crate::writer::{DocumentWriter, Format};
+    use crate::DValue;
+    use std::io;
+
+    fn write_rows(format: Format) -> String {
+        let mut writer = DocumentWriter::new(Vec::new(), format);
+        writer.begin_list().unwrap();
+        for i in 0..100 {
+            writer.begin_tuple().unwrap();
+            writer.value(&DValue::Number(i as f64)).unwrap();
+            writer.begin_dict().unwrap();
+            writer.key("name").unwrap();
+            writer.value(&DValue::String(format!("row {}", i))).unwrap();
+            writer.end_dict().unwrap();
+            writer.end_tuple().unwrap();
+        }
+        writer.end_list().unwrap();
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    fn rows() -> DValue {
+        let rows = (0..100)
+            .map(|i| format!("({}, {{\"name\": \"row {}\"}})", i, i))
+            .collect::<Vec<_>>();
+        format!("[{}]", rows.join(",")).parse().unwrap()
+    }
+
+    #[test]
+    fn matches_whole_value_output() {
+        assert_eq!(write_rows(Format::Text), rows().to_string());
+        #[cfg(feature = "json")]
+        assert_eq!(write_rows(Format::Json), rows().to_json());
+    }
+
+    #[test]
+    fn streams_events() {
+        let text = "[1, (\"a\", binary!(aGk=)), {\"k\": [none, true]}]";
+        let formats = [
+            Format::Text,
+            #[cfg(feature = "json")]
+            Format::Json,
+        ];
+
+        for format in formats {
+            let mut writer = DocumentWriter::new(Vec::new(), format);
+            for event in EventReader::new(text) {
+                writer.event(event.unwrap()).unwrap();
+            }
+            let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+            let value = text.parse::<DValue>().unwrap();
+            match format {
+                Format::Text => assert_eq!(out, value.to_string()),
+                #[cfg(feature = "json")]
+                Format::Json => assert_eq!(out, value.to_json()),
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        let mut writer = DocumentWriter::new(Vec::new(), Format::Text);
+        writer.begin_dict().unwrap();
+
+        let err = writer.value(&DValue::None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "unexpected None event");
+        assert!(writer.end_list().is_err());
+
+        writer.key("a").unwrap();
+        assert!(writer.key("b").is_err());
+        writer.value(&DValue::None).unwrap();
+        assert_eq!(
+            writer.finish().unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}